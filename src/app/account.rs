@@ -1,65 +1,369 @@
 use crate::{
     app::App,
-    types::{AppEvent, NavEvent, ScreenMode},
-    widgets::transactions::{TransactionsTable, TransactionsTableState},
+    currency::account_exponents,
+    types::{AppEvent, NavEvent, ScreenMode, Transaction, TrnColumn},
+    widgets::{
+        editable::EditableTableState,
+        transactions::{amount_exponent, apply_edit, cell_value, TransactionsTable},
+    },
 };
 use color_eyre::eyre;
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
     buffer::Buffer,
-    layout::Rect,
-    widgets::{Clear, StatefulWidget, Widget},
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Clear, StatefulWidget, Tabs, Widget},
 };
 
+const CATEGORY_COLUMN: usize = 1;
+
+const ALL_ACCOUNTS_TAB: &str = "All accounts";
+const TABS_HINT: &str = " <Tab> next account ";
+
+/// Transactions loaded around the selected row, and how close the selection may
+/// come to the ends of the loaded window before another one is loaded
+const WINDOW_SIZE: usize = 300;
+const WINDOW_MARGIN: usize = 100;
+
 impl App {
     pub(super) fn draw_account_screen(&self, area: Rect, buf: &mut Buffer) {
         let mut state = self.account_state.borrow_mut();
 
-        let table = TransactionsTable::new(&self.transactions);
+        let status_height = u16::from(self.reconciliation.is_some());
+        let [tabs_area, table_area, status_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(status_height),
+        ])
+        .areas(area);
+        let titles = std::iter::once(ALL_ACCOUNTS_TAB)
+            .chain(self.accounts.iter().map(|account| account.name.as_str()));
+        let tabs = Tabs::new(titles)
+            .select(self.ledger_tab())
+            .highlight_style(Style::default().bg(self.theme().selected_bg).bold());
+
+        let exponents = account_exponents(&self.accounts);
+        let table = TransactionsTable::new(&self.transactions, &self.config.date_format)
+            .first_row(self.trn_window)
+            .account(self.trn_filter.account_id)
+            .balances(self.trn_balances.as_ref())
+            .expanded(&self.expanded_trns)
+            .exponents(&exponents)
+            .query(&self.trn_query)
+            .sort(self.trn_filter.sort)
+            .theme(self.theme());
         Clear.render(area, buf);
-        StatefulWidget::render(table, area, buf, &mut state);
+        tabs.render(tabs_area, buf);
+        Line::from(TABS_HINT)
+            .dark_gray()
+            .right_aligned()
+            .render(tabs_area, buf);
+        StatefulWidget::render(table, table_area, buf, &mut state);
+        self.draw_reconciliation(status_area, buf);
     }
 
     pub(super) fn sync_account_screen(&mut self) -> eyre::Result<()> {
-        let selected = self.account_state.borrow().selected();
+        let (row, col) = self.account_state.borrow().selected();
 
-        self.transactions = self.service.get_trns()?;
-        let mut new_state = TransactionsTableState::new(self.transactions.len());
-        new_state.select(selected.0, selected.1);
+        // the ledger of a deleted account falls back to all accounts
+        self.accounts = self.service.get_accounts()?;
+        if self.ledger_tab() == 0 {
+            self.trn_filter.account_id = None;
+        }
+
+        self.trn_count = self.service.count_trns(&self.trn_filter)?;
+        self.categories = self.service.get_categories()?;
+        let row = row.map(|row| row.min(self.trn_count.saturating_sub(1)));
+        let mut new_state = EditableTableState::new(self.trn_count);
+        new_state.select(row, col);
 
         self.account_state.replace(new_state);
+        self.load_trn_window(row.unwrap_or(0))?;
+        self.sync_reconciliation()
+    }
+
+    /// Position of the tab of the shown ledger, 0 is all accounts
+    fn ledger_tab(&self) -> usize {
+        self.accounts
+            .iter()
+            .position(|account| account.account_id == self.trn_filter.account_id)
+            .map_or(0, |idx| idx + 1)
+    }
+
+    /// Shows the ledger `step` tabs to the right, wrapping around
+    fn switch_ledger(&mut self, step: isize) {
+        let tabs = self.accounts.len() as isize + 1;
+        let tab = (self.ledger_tab() as isize + step).rem_euclid(tabs) as usize;
+        self.trn_filter.account_id = match tab {
+            0 => None,
+            tab => self.accounts[tab - 1].account_id,
+        };
+        self.reconciliation = None;
+
+        let (_, col) = self.account_state.borrow().selected();
+        self.account_state.borrow_mut().select(Some(0), col);
+        if let Err(report) = self.sync_account_screen() {
+            self.events
+                .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+        }
+    }
+
+    /// Loads the transactions around `row`
+    fn load_trn_window(&mut self, row: usize) -> eyre::Result<()> {
+        self.trn_window = row.saturating_sub(WINDOW_SIZE / 2);
+        self.transactions =
+            self.service
+                .find_trns_window(&self.trn_filter, self.trn_window, WINDOW_SIZE)?;
+        self.load_balances()
+    }
 
+    /// Running balances of the loaded transactions when they are scoped to one account
+    fn load_balances(&mut self) -> eyre::Result<()> {
+        self.trn_balances = match self.trn_filter.account_id {
+            Some(account_id) => {
+                let ids: Vec<isize> = self
+                    .transactions
+                    .iter()
+                    .filter_map(|trn| trn.transaction_id)
+                    .collect();
+                Some(self.service.get_running_balances(account_id, &ids)?)
+            }
+            None => None,
+        };
         Ok(())
     }
 
+    /// Loads another window when the selection comes close to an end of the loaded one
+    fn follow_selection(&mut self) -> eyre::Result<()> {
+        let (Some(row), _) = self.account_state.borrow().selected() else {
+            return Ok(());
+        };
+        let end = self.trn_window + self.transactions.len();
+
+        let near_start = self.trn_window > 0 && row < self.trn_window + WINDOW_MARGIN;
+        let near_end = end < self.trn_count && row + WINDOW_MARGIN >= end;
+        if near_start || near_end || row >= end {
+            self.load_trn_window(row)?;
+        }
+        Ok(())
+    }
+
+    /// Loaded transaction at `row` of the whole list
+    pub(super) fn trn_at(&self, row: usize) -> Option<&Transaction> {
+        self.transactions.get(row.checked_sub(self.trn_window)?)
+    }
+
     pub(super) fn input_account_screen(&mut self, key_event: &KeyEvent) {
         self.account_state.borrow_mut().handle_input(key_event);
         if self.account_state.borrow().mode == ScreenMode::Browsing {
             match key_event.code {
                 KeyCode::Char('q' | 'Q') => self.exit(),
+                KeyCode::Char('a' | 'A') => {
+                    if let Err(report) = self.open_new_trn_popup() {
+                        self.events
+                            .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+                    }
+                }
+                KeyCode::Char('i' | 'I') => {
+                    if let Err(report) = self.open_import_popup() {
+                        self.events
+                            .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+                    }
+                }
+                KeyCode::Char('e' | 'E') => {
+                    if let Err(report) = self.open_export_popup() {
+                        self.events
+                            .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+                    }
+                }
                 KeyCode::Char('d' | 'D') => self.delete_selected_trns(),
+                KeyCode::Char('/') => self.open_filter_popup(),
+                KeyCode::Char('s' | 'S') => self.sort_by_selected_column(),
+                KeyCode::Char('x' | 'X') => self.toggle_splits(),
+                KeyCode::Tab => self.switch_ledger(1),
+                KeyCode::BackTab => self.switch_ledger(-1),
+                KeyCode::Char('r') if key_event.modifiers == KeyModifiers::CONTROL => self.redo(),
+                KeyCode::Char('r' | 'R') => {
+                    if let Err(report) = self.open_reconcile_popup() {
+                        self.events
+                            .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+                    }
+                }
+                KeyCode::Char('c' | 'C')
+                    if matches!(
+                        key_event.modifiers,
+                        KeyModifiers::NONE | KeyModifiers::SHIFT
+                    ) =>
+                {
+                    self.toggle_cleared()
+                }
+                KeyCode::Char('f' | 'F') => self.finish_reconciliation(),
+                KeyCode::Char('u' | 'U') => self.undo(),
                 _ => {}
             };
         }
     }
-    pub(super) fn nav_account_screen(&self, event: NavEvent) {
-        let _value = self.account_state.borrow_mut().navigate(event);
-    }
+    pub(super) fn nav_account_screen(&mut self, event: NavEvent) {
+        let mut state = self.account_state.borrow_mut();
 
-    fn delete_selected_trns(&mut self) {
-        if let (Some(row), _) = self.account_state.borrow().selected() {
-            let trn = &self.transactions[row];
-            if let Some(trn_id) = trn.transaction_id {
-                if let Err(report) = self.service.del_trns(&[trn_id]) {
+        if let (ScreenMode::Browsing, NavEvent::Interact) = (state.mode, &event) {
+            // start editing with the current cell content
+            if let (Some(row), Some(col)) = state.selected() {
+                if let Some(trn) = self.trn_at(row) {
+                    let value = cell_value(
+                        trn,
+                        col,
+                        &self.config.date_format,
+                        self.trn_filter.account_id,
+                        amount_exponent(trn, &account_exponents(&self.accounts)),
+                    );
+                    if col == CATEGORY_COLUMN {
+                        state.start_editing_with(value, self.category_names());
+                    } else {
+                        state.start_editing(value);
+                    }
+                }
+            }
+            return;
+        }
+        if let (ScreenMode::Browsing, NavEvent::Cancel) = (state.mode, &event) {
+            if self.reconciliation.take().is_some() {
+                return;
+            }
+        }
+
+        let edited = state.navigate(event);
+        drop(state);
+        match edited {
+            Some(value) => self.commit_edit(&value),
+            None => {
+                if let Err(report) = self.follow_selection() {
                     self.events
-                        .push_back(AppEvent::Notifiction(format!("Error: {report}")))
+                        .push_back(AppEvent::Notifiction(format!("Error: {report}")));
                 }
             }
+        }
+    }
+
+    /// Saves edited cell value. On failure the cell stays in Editing mode
+    fn commit_edit(&mut self, value: &str) {
+        let (Some(row), Some(col)) = self.account_state.borrow().selected() else {
+            return;
+        };
+        let Some(trn) = self.trn_at(row) else {
+            return;
+        };
+
+        let value = value.trim();
+        let known = value.is_empty() || self.categories.iter().any(|c| c.name == value);
+        if col == CATEGORY_COLUMN && !known {
             self.events.push_back(AppEvent::Notifiction(format!(
-                "Deleting transaction {:?}",
-                &trn.transaction_id
+                "Error: unknown category '{value}', add it on the categories screen"
             )));
-            self.transactions.remove(row);
+            return;
+        }
+
+        let result = apply_edit(
+            trn,
+            col,
+            value,
+            &self.config.date_format,
+            self.trn_filter.account_id,
+            amount_exponent(trn, &account_exponents(&self.accounts)),
+        )
+        .and_then(|mut updated| {
+            updated.transaction_id = Some(self.service.put_trn(&updated)?);
+            Ok(updated)
+        });
+
+        match result {
+            Ok(updated) => {
+                self.transactions[row - self.trn_window] = updated;
+                self.account_state.borrow_mut().stop_editing();
+                if let Err(report) = self.load_balances() {
+                    self.events
+                        .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+                }
+            }
+            Err(report) => self
+                .events
+                .push_back(AppEvent::Notifiction(format!("Error: {report}"))),
         }
     }
+
+    fn delete_selected_trns(&mut self) {
+        let (Some(row), _) = self.account_state.borrow().selected() else {
+            return;
+        };
+        let Some(trn_id) = self.trn_at(row).and_then(|trn| trn.transaction_id) else {
+            return;
+        };
+
+        let result = self
+            .service
+            .del_trns(&[trn_id])
+            .and_then(|_| self.sync_account_screen());
+        let msg = match result {
+            Ok(()) => format!("Deleted transaction {trn_id}, press <u> to undo"),
+            Err(report) => format!("Error: {report}"),
+        };
+        self.events.push_back(AppEvent::Notifiction(msg));
+    }
+
+    /// Shows or hides the splits of the selected transaction under it
+    fn toggle_splits(&mut self) {
+        let (Some(row), _) = self.account_state.borrow().selected() else {
+            return;
+        };
+        let Some(trn_id) = self.trn_at(row).and_then(|trn| trn.transaction_id) else {
+            return;
+        };
+        if !self.expanded_trns.remove(&trn_id) {
+            self.expanded_trns.insert(trn_id);
+        }
+    }
+
+    /// Sorts by the selected column, asking again flips the direction, then resets the order
+    fn sort_by_selected_column(&mut self) {
+        let (_, Some(col)) = self.account_state.borrow().selected() else {
+            return;
+        };
+        let Some(&column) = TrnColumn::ALL.get(col) else {
+            return;
+        };
+
+        self.trn_filter.sort = self.trn_filter.sort.cycle(column);
+        if let Err(report) = self.sync_account_screen() {
+            self.events
+                .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+        }
+    }
+
+    fn undo(&mut self) {
+        let result = self.service.undo().and_then(|step| {
+            self.sync_account_screen()?;
+            Ok(step)
+        });
+        let msg = match result {
+            Ok(Some(step)) => format!("Undone: {step}, press <Ctrl-r> to redo"),
+            Ok(None) => String::from("Nothing to undo"),
+            Err(report) => format!("Error: {report}"),
+        };
+        self.events.push_back(AppEvent::Notifiction(msg));
+    }
+
+    fn redo(&mut self) {
+        let result = self.service.redo().and_then(|step| {
+            self.sync_account_screen()?;
+            Ok(step)
+        });
+        let msg = match result {
+            Ok(Some(step)) => format!("Redone: {step}"),
+            Ok(None) => String::from("Nothing to redo"),
+            Err(report) => format!("Error: {report}"),
+        };
+        self.events.push_back(AppEvent::Notifiction(msg));
+    }
 }
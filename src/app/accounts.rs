@@ -0,0 +1,136 @@
+use crate::{
+    app::App,
+    types::{Account, AppEvent, NavEvent, ScreenMode},
+    widgets::{
+        accounts::{apply_edit, cell_value, AccountsTable},
+        editable::EditableTableState,
+    },
+};
+use color_eyre::eyre;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    widgets::{Clear, StatefulWidget, Widget},
+};
+
+const NAME_COLUMN: usize = 1;
+
+impl App {
+    pub(super) fn draw_accounts_screen(&self, area: Rect, buf: &mut Buffer) {
+        let mut state = self.accounts_state.borrow_mut();
+
+        let table = AccountsTable::new(&self.accounts, &self.balances).theme(self.theme());
+        Clear.render(area, buf);
+        StatefulWidget::render(table, area, buf, &mut state);
+    }
+
+    pub(super) fn sync_accounts_screen(&mut self) -> eyre::Result<()> {
+        let selected = self.accounts_state.borrow().selected();
+
+        self.accounts = self.service.get_accounts()?;
+        self.balances = self.service.get_balances()?;
+        let mut new_state = EditableTableState::new(self.accounts.len());
+        new_state.select(selected.0, selected.1);
+
+        self.accounts_state.replace(new_state);
+
+        Ok(())
+    }
+
+    pub(super) fn input_accounts_screen(&mut self, key_event: &KeyEvent) {
+        self.accounts_state.borrow_mut().handle_input(key_event);
+        if self.accounts_state.borrow().mode == ScreenMode::Browsing {
+            match key_event.code {
+                KeyCode::Char('q' | 'Q') => self.exit(),
+                KeyCode::Char('a' | 'A') => self.add_account(),
+                KeyCode::Char('d' | 'D') => self.delete_selected_account(),
+                _ => {}
+            };
+        }
+    }
+
+    pub(super) fn nav_accounts_screen(&mut self, event: NavEvent) {
+        let mut state = self.accounts_state.borrow_mut();
+
+        if let (ScreenMode::Browsing, NavEvent::Interact) = (state.mode, &event) {
+            // start editing with the current cell content
+            if let (Some(row), Some(col)) = state.selected() {
+                if let Some(account) = self.accounts.get(row) {
+                    state.start_editing(cell_value(account, col));
+                }
+            }
+            return;
+        }
+
+        if let Some(value) = state.navigate(event) {
+            drop(state);
+            self.commit_account_edit(&value);
+        }
+    }
+
+    /// Saves edited cell value. On failure the cell stays in Editing mode
+    fn commit_account_edit(&mut self, value: &str) {
+        let (Some(row), Some(col)) = self.accounts_state.borrow().selected() else {
+            return;
+        };
+        let Some(account) = self.accounts.get(row) else {
+            return;
+        };
+
+        let result = apply_edit(account, col, value)
+            .and_then(|updated| self.service.put_account(&updated))
+            .and_then(|_| self.sync_accounts_screen());
+
+        match result {
+            Ok(()) => self.accounts_state.borrow_mut().stop_editing(),
+            Err(report) => self
+                .events
+                .push_back(AppEvent::Notifiction(format!("Error: {report}"))),
+        }
+    }
+
+    /// Creates an account with placeholder name and starts editing its name
+    fn add_account(&mut self) {
+        let account = Account {
+            account_id: None,
+            name: format!("Account #{}", self.accounts.len() + 1),
+            acc_type: Default::default(),
+            currency: self.config.currency.clone(),
+            opening_balance: 0,
+        };
+
+        let result = self
+            .service
+            .put_account(&account)
+            .and_then(|_| self.sync_accounts_screen());
+        if let Err(report) = result {
+            self.events
+                .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+            return;
+        }
+
+        let mut state = self.accounts_state.borrow_mut();
+        state.select(Some(self.accounts.len() - 1), Some(NAME_COLUMN));
+        state.start_editing(account.name);
+    }
+
+    fn delete_selected_account(&mut self) {
+        let (Some(row), _) = self.accounts_state.borrow().selected() else {
+            return;
+        };
+        let Some(account_id) = self.accounts.get(row).and_then(|acc| acc.account_id) else {
+            return;
+        };
+
+        let result = self
+            .service
+            .del_account(account_id)
+            .and_then(|_| self.sync_accounts_screen());
+        let msg = match result {
+            Ok(()) => format!("Deleted account {account_id}"),
+            Err(report) => format!("Error: {report}"),
+        };
+        self.events.push_back(AppEvent::Notifiction(msg));
+    }
+}
@@ -0,0 +1,309 @@
+use chrono::{Datelike, Local, Months, NaiveDate};
+use color_eyre::eyre::{self, eyre};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Borders, Clear, Gauge, StatefulWidget, Widget},
+};
+
+use super::{App, PopUp};
+use crate::{
+    currency::exponent,
+    parsing::{format_money, parse_money},
+    types::{AppEvent, Budget, BudgetStatus, NavEvent},
+    widgets::form::{Form, FormState},
+};
+
+const SCREEN_TITLE_BOTTOM: &str = " ← → month, ↑ ↓ select, <a> add, <Enter> edit, <d> delete ";
+const ROW_HIGHLIGHT_SYMBOL: &str = " > ";
+const MONTH_FORMAT: &str = "%Y-%m";
+
+const FORM_TITLE: &str = "Budget";
+const FORM_FIELDS: [&str; 3] = ["Category", "Month", "Limit"];
+
+const CATEGORY: usize = 0;
+const MONTH: usize = 1;
+const LIMIT: usize = 2;
+
+impl App {
+    pub(super) fn draw_budgets_screen(&self, area: Rect, buf: &mut Buffer) {
+        let theme = self.theme();
+        let spent: i64 = self.budgets.iter().map(|status| status.spent).sum();
+        let limit: i64 = self.budgets.iter().map(|status| status.budget.limit).sum();
+
+        let block = Block::default()
+            .title(format!("Budgets {}", self.budgets_month.format("%B %Y")))
+            .title(
+                Line::from(format!(
+                    " {} of {} ",
+                    self.format_budget(spent),
+                    self.format_budget(limit)
+                ))
+                .right_aligned(),
+            )
+            .title_bottom(Line::from(SCREEN_TITLE_BOTTOM).right_aligned())
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded);
+        let inner = block.inner(area);
+
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        if self.budgets.is_empty() {
+            Line::from(" No budgets for this month, press <a> to add one")
+                .dark_gray()
+                .render(inner, buf);
+            return;
+        }
+
+        // keep the selected budget visible
+        let height = inner.height.max(1) as usize;
+        let offset = self.budgets_selected.saturating_sub(height - 1);
+
+        for (idx, status) in self.budgets.iter().enumerate().skip(offset).take(height) {
+            let row = Rect {
+                y: inner.y + (idx - offset) as u16,
+                height: 1,
+                ..inner
+            };
+            let [symbol, category, gauge, amounts] = Layout::horizontal([
+                Constraint::Length(ROW_HIGHLIGHT_SYMBOL.len() as u16),
+                Constraint::Length(20),
+                Constraint::Fill(1),
+                Constraint::Length(30),
+            ])
+            .spacing(1)
+            .areas(row);
+
+            let mut style = Style::default();
+            if idx == self.budgets_selected {
+                buf.set_style(row, Style::default().bg(theme.selected_bg));
+                Line::from(ROW_HIGHLIGHT_SYMBOL).render(symbol, buf);
+            }
+            if status.is_over() {
+                style = style.fg(theme.error_fg).add_modifier(Modifier::BOLD);
+            }
+
+            Line::from(status.budget.category.as_str())
+                .style(style)
+                .render(category, buf);
+            Gauge::default()
+                .ratio(ratio(status))
+                .label(percent(status))
+                .gauge_style(Style::default().fg(match status.is_over() {
+                    true => theme.error_fg,
+                    false => theme.gauge_fg,
+                }))
+                .render(gauge, buf);
+            Line::from(format!(
+                "{} / {}",
+                self.format_budget(status.spent),
+                self.format_budget(status.budget.limit)
+            ))
+            .style(style)
+            .right_aligned()
+            .render(amounts, buf);
+        }
+    }
+
+    /// Formats an amount in the configured currency
+    fn format_budget(&self, amount: i64) -> String {
+        format_money(amount, exponent(&self.config.currency))
+    }
+
+    pub(super) fn sync_budgets_screen(&mut self) -> eyre::Result<()> {
+        self.budgets = self
+            .service
+            .get_budget_statuses(&self.config.currency, self.budgets_month)?;
+        self.budgets_selected = self
+            .budgets_selected
+            .min(self.budgets.len().saturating_sub(1));
+        Ok(())
+    }
+
+    pub(super) fn input_budgets_screen(&mut self, key_event: &KeyEvent) {
+        match key_event.code {
+            KeyCode::Char('q' | 'Q') => self.exit(),
+            KeyCode::Char('a' | 'A') => self.open_budget_popup(None),
+            KeyCode::Char('d' | 'D') => self.delete_selected_budget(),
+            _ => {}
+        }
+    }
+
+    pub(super) fn nav_budgets_screen(&mut self, event: NavEvent) {
+        let months = Months::new(1);
+        let result = match event {
+            NavEvent::Up => {
+                self.budgets_selected = self.budgets_selected.saturating_sub(1);
+                Ok(())
+            }
+            NavEvent::Down => {
+                if self.budgets_selected + 1 < self.budgets.len() {
+                    self.budgets_selected += 1;
+                }
+                Ok(())
+            }
+            NavEvent::Left => {
+                self.show_budgets_month(self.budgets_month.checked_sub_months(months))
+            }
+            NavEvent::Rigth => {
+                self.show_budgets_month(self.budgets_month.checked_add_months(months))
+            }
+            NavEvent::Interact => {
+                let budget = self
+                    .budgets
+                    .get(self.budgets_selected)
+                    .map(|status| status.budget.clone());
+                if budget.is_some() {
+                    self.open_budget_popup(budget);
+                }
+                Ok(())
+            }
+            NavEvent::Cancel => Ok(()),
+        };
+
+        if let Err(report) = result {
+            self.events
+                .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+        }
+    }
+
+    fn show_budgets_month(&mut self, month: Option<NaiveDate>) -> eyre::Result<()> {
+        self.budgets_month = month.ok_or_else(|| eyre!("month is out of range"))?;
+        self.sync_budgets_screen()
+    }
+
+    fn delete_selected_budget(&mut self) {
+        let Some(budget) = self
+            .budgets
+            .get(self.budgets_selected)
+            .map(|status| status.budget.clone())
+        else {
+            return;
+        };
+
+        let result = self
+            .service
+            .del_budget(&budget.category, budget.period)
+            .and_then(|_| self.sync_budgets_screen());
+        let msg = match result {
+            Ok(()) => format!(
+                "Deleted budget of {} set for {}",
+                budget.category,
+                budget.period.format(MONTH_FORMAT)
+            ),
+            Err(report) => format!("Error: {report}"),
+        };
+        self.events.push_back(AppEvent::Notifiction(msg));
+    }
+
+    /// Opens the budget form, filled with `budget` values when editing
+    fn open_budget_popup(&mut self, budget: Option<Budget>) {
+        let mut form = FormState::new(&FORM_FIELDS)
+            .with_value(MONTH, self.budgets_month.format(MONTH_FORMAT).to_string());
+        if let Some(budget) = budget {
+            form.set_value(CATEGORY, budget.category);
+            form.set_value(LIMIT, self.format_budget(budget.limit));
+            form.focus(LIMIT);
+        }
+        self.budget_form.replace(form);
+        self.popup = Some(PopUp::Budget);
+    }
+
+    pub(super) fn draw_budget_popup(&self, area: Rect, buf: &mut Buffer) {
+        let form = Form::new(FORM_TITLE).hints(vec![
+            Line::default(),
+            Line::from("The limit applies from this month until a later budget").dark_gray(),
+        ]);
+        StatefulWidget::render(form, area, buf, &mut self.budget_form.borrow_mut());
+    }
+
+    pub(super) fn input_budget_popup(&mut self, key_event: &KeyEvent) {
+        let mut form = self.budget_form.borrow_mut();
+        match key_event.code {
+            KeyCode::Esc => self.popup = None,
+            KeyCode::Tab | KeyCode::Down => form.next_field(),
+            KeyCode::BackTab | KeyCode::Up => form.previous_field(),
+            KeyCode::Enter => {
+                drop(form);
+                self.submit_budget();
+            }
+            _ => form.handle_input(key_event),
+        }
+    }
+
+    fn submit_budget(&mut self) {
+        let parsed = parse_form(&self.budget_form.borrow(), exponent(&self.config.currency));
+        let budget = match parsed {
+            Ok(budget) => budget,
+            Err((field, report)) => {
+                self.budget_form.borrow_mut().focus(field);
+                self.events
+                    .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+                return;
+            }
+        };
+
+        let result = self
+            .service
+            .put_budget(&budget)
+            .and_then(|_| self.sync_budgets_screen());
+        let msg = match result {
+            Ok(()) => {
+                self.popup = None;
+                format!(
+                    "Budget of {} set to {} from {}",
+                    budget.category,
+                    self.format_budget(budget.limit),
+                    budget.period.format(MONTH_FORMAT)
+                )
+            }
+            Err(report) => format!("Error: {report}"),
+        };
+        self.events.push_back(AppEvent::Notifiction(msg));
+    }
+}
+
+/// First day of the current month
+pub(super) fn current_month() -> NaiveDate {
+    let today = Local::now().date_naive();
+    today.with_day(1).unwrap_or(today)
+}
+
+/// Spent part of the limit, full when anything is spent over a zero limit
+fn ratio(status: &BudgetStatus) -> f64 {
+    match status.budget.limit {
+        limit if limit > 0 => (status.spent.max(0) as f64 / limit as f64).min(1.0),
+        _ if status.spent > 0 => 1.0,
+        _ => 0.0,
+    }
+}
+
+fn percent(status: &BudgetStatus) -> String {
+    match status.budget.limit {
+        limit if limit > 0 => format!("{}%", status.spent * 100 / limit),
+        _ => String::from("-"),
+    }
+}
+
+/// Builds budget from form values, the limit in minor units of `exponent`.
+/// Errors carry the index of the invalid field
+fn parse_form(form: &FormState, exponent: u32) -> Result<Budget, (usize, eyre::Report)> {
+    let category = match form.value(CATEGORY).trim() {
+        "" => return Err((CATEGORY, eyre!("budget category can not be empty"))),
+        category => category.to_string(),
+    };
+    let month = form.value(MONTH).trim();
+    let period = NaiveDate::parse_from_str(&format!("{month}-01"), "%Y-%m-%d")
+        .map_err(|_| (MONTH, eyre!("invalid month '{month}', expected YYYY-MM")))?;
+    let limit = parse_money(form.value(LIMIT), exponent).map_err(|report| (LIMIT, report))?;
+
+    Ok(Budget {
+        category,
+        period,
+        limit,
+    })
+}
@@ -0,0 +1,184 @@
+use crate::{
+    app::App,
+    types::{AppEvent, Category, NavEvent, ScreenMode},
+    widgets::{
+        categories::{apply_edit, cell_value, CategoriesTable},
+        editable::EditableTableState,
+    },
+};
+use color_eyre::eyre;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    widgets::{Clear, StatefulWidget, Widget},
+};
+
+const NAME_COLUMN: usize = 1;
+const PARENT_COLUMN: usize = 2;
+
+impl App {
+    pub(super) fn draw_categories_screen(&self, area: Rect, buf: &mut Buffer) {
+        let mut state = self.categories_state.borrow_mut();
+
+        let table =
+            CategoriesTable::new(&self.categories, &self.category_usage).theme(self.theme());
+        Clear.render(area, buf);
+        StatefulWidget::render(table, area, buf, &mut state);
+    }
+
+    pub(super) fn sync_categories_screen(&mut self) -> eyre::Result<()> {
+        let selected = self.categories_state.borrow().selected();
+
+        self.categories = self.service.get_categories()?;
+        self.category_usage = self.service.get_category_usage()?;
+        let mut new_state = EditableTableState::new(self.categories.len());
+        new_state.select(selected.0, selected.1);
+
+        self.categories_state.replace(new_state);
+
+        Ok(())
+    }
+
+    pub(super) fn input_categories_screen(&mut self, key_event: &KeyEvent) {
+        self.categories_state.borrow_mut().handle_input(key_event);
+        if self.categories_state.borrow().mode == ScreenMode::Browsing {
+            match key_event.code {
+                KeyCode::Char('q' | 'Q') => self.exit(),
+                KeyCode::Char('a' | 'A') => self.add_category(),
+                KeyCode::Char('d' | 'D') => self.delete_selected_category(),
+                _ => {}
+            };
+        }
+    }
+
+    pub(super) fn nav_categories_screen(&mut self, event: NavEvent) {
+        let mut state = self.categories_state.borrow_mut();
+
+        if let (ScreenMode::Browsing, NavEvent::Interact) = (state.mode, &event) {
+            // start editing with the current cell content, parents are picked from the list
+            if let (Some(row), Some(col)) = state.selected() {
+                if let Some(category) = self.categories.get(row) {
+                    let value = cell_value(category, col, &self.categories);
+                    if col == PARENT_COLUMN {
+                        state.start_editing_with(value, self.category_names());
+                    } else {
+                        state.start_editing(value);
+                    }
+                }
+            }
+            return;
+        }
+
+        if let Some(value) = state.navigate(event) {
+            drop(state);
+            self.commit_category_edit(&value);
+        }
+    }
+
+    /// Saves edited cell value. Renaming to the name of another category merges
+    /// them. On failure the cell stays in Editing mode
+    fn commit_category_edit(&mut self, value: &str) {
+        let (Some(row), Some(col)) = self.categories_state.borrow().selected() else {
+            return;
+        };
+        let Some(category) = self.categories.get(row).cloned() else {
+            return;
+        };
+
+        let merge_into = self
+            .categories
+            .iter()
+            .find(|other| col == NAME_COLUMN && other.name == value.trim() && *other != &category)
+            .and_then(|other| other.category_id);
+
+        let result = match (category.category_id, merge_into) {
+            (Some(from), Some(into)) => self.service.merge_categories(from, into).map(|_| {
+                Some(format!(
+                    "Merged category '{}' into '{}'",
+                    category.name,
+                    value.trim()
+                ))
+            }),
+            _ => apply_edit(&category, col, value, &self.categories)
+                .and_then(|updated| self.service.put_category(&updated))
+                .map(|_| None),
+        }
+        .and_then(|msg| {
+            self.sync_categories_screen()?;
+            Ok(msg)
+        });
+
+        match result {
+            Ok(msg) => {
+                self.categories_state.borrow_mut().stop_editing();
+                if let Some(msg) = msg {
+                    self.events.push_back(AppEvent::Notifiction(msg));
+                }
+            }
+            Err(report) => self
+                .events
+                .push_back(AppEvent::Notifiction(format!("Error: {report}"))),
+        }
+    }
+
+    /// Creates a category with placeholder name and starts editing its name
+    fn add_category(&mut self) {
+        let category = Category {
+            category_id: None,
+            name: format!("Category #{}", self.categories.len() + 1),
+            parent_id: None,
+        };
+
+        let result = self
+            .service
+            .put_category(&category)
+            .and_then(|category_id| {
+                self.sync_categories_screen()?;
+                Ok(category_id)
+            });
+        let category_id = match result {
+            Ok(category_id) => category_id,
+            Err(report) => {
+                self.events
+                    .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+                return;
+            }
+        };
+
+        let row = self
+            .categories
+            .iter()
+            .position(|c| c.category_id == Some(category_id));
+        let mut state = self.categories_state.borrow_mut();
+        state.select(row, Some(NAME_COLUMN));
+        state.start_editing(category.name);
+    }
+
+    fn delete_selected_category(&mut self) {
+        let (Some(row), _) = self.categories_state.borrow().selected() else {
+            return;
+        };
+        let Some(category) = self.categories.get(row).cloned() else {
+            return;
+        };
+        let Some(category_id) = category.category_id else {
+            return;
+        };
+
+        let result = self
+            .service
+            .del_category(category_id)
+            .and_then(|_| self.sync_categories_screen());
+        let msg = match result {
+            Ok(()) => format!("Deleted category '{}'", category.name),
+            Err(report) => format!("Error: {report}"),
+        };
+        self.events.push_back(AppEvent::Notifiction(msg));
+    }
+
+    /// Names of all known categories, offered by category pickers
+    pub(super) fn category_names(&self) -> Vec<String> {
+        self.categories.iter().map(|c| c.name.clone()).collect()
+    }
+}
@@ -0,0 +1,163 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Margin, Rect},
+    style::{Modifier, Style, Stylize},
+    text::{Line, Text},
+    widgets::{
+        Block, BorderType, Borders, Cell, Clear, Row, StatefulWidget, Table, TableState, Widget,
+    },
+};
+
+use super::{App, PopUp};
+use crate::{
+    currency::account_exponents,
+    parsing::format_money,
+    types::{AppEvent, Duplicate, Transaction},
+    widgets::transactions::amount_exponent,
+};
+
+const POPUP_TITLE: &str = "Likely duplicates";
+const POPUP_TITLE_BOTTOM: &str =
+    " ↑ ↓ select, <m> merge into stored, <k> keep both, <d> discard new, <Esc> discard all ";
+const TABLE_HEADER: [&str; 5] = ["", "Date", "Description", "Category", "Amount"];
+const ROW_HIGHLIGHT_SYMBOL: &str = " > ";
+
+/// What to do with the new transaction of a duplicate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Resolution {
+    Merge,
+    Keep,
+    Discard,
+}
+
+impl App {
+    /// Opens the review of new transactions that look like stored ones
+    pub(super) fn open_duplicates_popup(&mut self, duplicates: Vec<Duplicate>) {
+        self.duplicates = duplicates;
+        self.duplicates_selected = 0;
+        self.popup = Some(PopUp::Duplicates);
+    }
+
+    pub(super) fn draw_duplicates_popup(&self, area: Rect, buf: &mut Buffer) {
+        let area = area.inner(Margin::new(4, 2));
+        let theme = self.theme();
+        let block = Block::default()
+            .title(format!("{POPUP_TITLE} ({})", self.duplicates.len()))
+            .title_bottom(Line::from(POPUP_TITLE_BOTTOM).right_aligned())
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded);
+
+        let exponents = account_exponents(&self.accounts);
+        let fields = |trn: &Transaction| {
+            [
+                trn.timestamp.format(&self.config.date_format).to_string(),
+                trn.description.clone().unwrap_or_default(),
+                trn.category.clone().unwrap_or_default(),
+                format_money(trn.amount, amount_exponent(trn, &exponents)),
+            ]
+        };
+        let rows = self.duplicates.iter().enumerate().map(|(idx, duplicate)| {
+            let stored = duplicate
+                .existing
+                .transaction_id
+                .map_or(String::from("stored"), |id| format!("#{id}"));
+            let label = Text::from(vec![Line::from("new"), Line::from(stored).dark_gray()]);
+            let values = fields(&duplicate.candidate)
+                .into_iter()
+                .zip(fields(&duplicate.existing))
+                .enumerate()
+                .map(|(col, (new, existing))| {
+                    let text = Text::from(vec![Line::from(new), Line::from(existing).dark_gray()]);
+                    match col {
+                        3 => Cell::from(text.right_aligned()),
+                        _ => Cell::from(text),
+                    }
+                });
+            let row = Row::new(std::iter::once(Cell::from(label)).chain(values)).height(2);
+            match idx % 2 {
+                0 => row,
+                _ => row.bg(theme.alt_row_bg),
+            }
+        });
+
+        let header = Row::new(
+            TABLE_HEADER
+                .iter()
+                .enumerate()
+                .map(|(col, &title)| match col {
+                    4 => Text::from(title).right_aligned(),
+                    _ => Text::from(title),
+                }),
+        )
+        .style(Style::default().add_modifier(Modifier::REVERSED));
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(6),
+                Constraint::Length(12),
+                Constraint::Fill(3),
+                Constraint::Fill(1),
+                Constraint::Min(13),
+            ],
+        )
+        .header(header)
+        .row_highlight_style(Style::default().bg(theme.selected_bg))
+        .highlight_symbol(ROW_HIGHLIGHT_SYMBOL)
+        .block(block);
+
+        let mut state = TableState::default().with_selected(Some(self.duplicates_selected));
+        Clear.render(area, buf);
+        StatefulWidget::render(table, area, buf, &mut state);
+    }
+
+    pub(super) fn input_duplicates_popup(&mut self, key_event: &KeyEvent) {
+        match key_event.code {
+            KeyCode::Up => self.duplicates_selected = self.duplicates_selected.saturating_sub(1),
+            KeyCode::Down if self.duplicates_selected + 1 < self.duplicates.len() => {
+                self.duplicates_selected += 1;
+            }
+            KeyCode::Char('m' | 'M') => self.resolve_duplicate(Resolution::Merge),
+            KeyCode::Char('k' | 'K') => self.resolve_duplicate(Resolution::Keep),
+            KeyCode::Char('d' | 'D') => self.resolve_duplicate(Resolution::Discard),
+            KeyCode::Esc => {
+                let count = self.duplicates.len();
+                self.duplicates.clear();
+                self.popup = None;
+                self.events.push_back(AppEvent::Notifiction(format!(
+                    "Discarded {count} likely duplicates"
+                )));
+            }
+            _ => {}
+        }
+    }
+
+    /// Applies the resolution to the selected duplicate and takes it off the list,
+    /// closing the review after the last one
+    fn resolve_duplicate(&mut self, resolution: Resolution) {
+        let Some(duplicate) = self.duplicates.get(self.duplicates_selected).cloned() else {
+            return;
+        };
+        let result = match resolution {
+            Resolution::Merge => self.service.merge_duplicate(&duplicate),
+            Resolution::Keep => self
+                .service
+                .put_trns_with_categories(std::slice::from_ref(&duplicate.candidate))
+                .map(|_| ()),
+            Resolution::Discard => Ok(()),
+        };
+        if let Err(report) = result.and_then(|_| self.screen_sync()) {
+            self.events
+                .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+            return;
+        }
+
+        self.duplicates.remove(self.duplicates_selected);
+        self.duplicates_selected = self
+            .duplicates_selected
+            .min(self.duplicates.len().saturating_sub(1));
+        if self.duplicates.is_empty() {
+            self.popup = None;
+        }
+    }
+}
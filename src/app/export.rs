@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+
+use color_eyre::eyre;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{buffer::Buffer, layout::Rect, style::Stylize, text::Line, widgets::StatefulWidget};
+
+use super::{App, PopUp};
+use crate::{
+    export,
+    parsing::{parse_account, parse_date},
+    types::{Account, AppEvent, TrnFilter},
+    widgets::form::{Form, FormState},
+};
+
+const EXPORT_TITLE: &str = "Export transactions";
+const EXPORT_FIELDS: [&str; 4] = ["File", "From", "To", "Account"];
+
+const FILE: usize = 0;
+const FROM: usize = 1;
+const TO: usize = 2;
+const ACCOUNT: usize = 3;
+
+impl App {
+    pub(super) fn open_export_popup(&mut self) -> eyre::Result<()> {
+        self.accounts = self.service.get_accounts()?;
+        self.export_form
+            .replace(FormState::new(&EXPORT_FIELDS).with_value(FILE, String::from("budget.csv")));
+        self.popup = Some(PopUp::Export);
+        Ok(())
+    }
+
+    pub(super) fn draw_export_popup(&self, area: Rect, buf: &mut Buffer) {
+        let form = Form::new(EXPORT_TITLE).hints(vec![
+            Line::default(),
+            Line::from("Format is chosen by file extension: .csv, .json or .qif").dark_gray(),
+            Line::from("QIF files hold the transactions of one account").dark_gray(),
+            Line::from("Leave dates or account empty to export everything").dark_gray(),
+        ]);
+        StatefulWidget::render(form, area, buf, &mut self.export_form.borrow_mut());
+    }
+
+    pub(super) fn input_export_popup(&mut self, key_event: &KeyEvent) {
+        let mut form = self.export_form.borrow_mut();
+        match key_event.code {
+            KeyCode::Esc => self.popup = None,
+            KeyCode::Tab | KeyCode::Down => form.next_field(),
+            KeyCode::BackTab | KeyCode::Up => form.previous_field(),
+            KeyCode::Enter => {
+                drop(form);
+                self.submit_export();
+            }
+            _ => form.handle_input(key_event),
+        }
+    }
+
+    fn submit_export(&mut self) {
+        let form = self.export_form.borrow();
+        let path = PathBuf::from(form.value(FILE).trim());
+        let filter = parse_filter(&form, &self.accounts, &self.config.date_format);
+        drop(form);
+
+        let result = filter.and_then(|filter| export::export_file(&self.service, &path, &filter));
+        let msg = match result {
+            Ok(count) => {
+                self.popup = None;
+                format!("Exported {count} transactions to {}", path.display())
+            }
+            Err(report) => format!("Error: {report}"),
+        };
+        self.events.push_back(AppEvent::Notifiction(msg));
+    }
+}
+
+fn parse_filter(
+    form: &FormState,
+    accounts: &[Account],
+    date_format: &str,
+) -> eyre::Result<TrnFilter> {
+    let optional = |idx: usize| Some(form.value(idx).trim()).filter(|value| !value.is_empty());
+
+    Ok(TrnFilter {
+        from: optional(FROM)
+            .map(|value| parse_date(value, date_format))
+            .transpose()?,
+        to: optional(TO)
+            .map(|value| parse_date(value, date_format))
+            .transpose()?,
+        account_id: optional(ACCOUNT)
+            .map(|value| parse_account(value, accounts))
+            .transpose()?,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,89 @@
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    text::Line,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Widget},
+};
+use tui_input::{backend::crossterm::EventHandler, Input};
+
+use super::{App, PopUp};
+use crate::{
+    query,
+    types::{AppEvent, TrnFilter},
+    widgets::utils,
+};
+
+const POPUP_TITLE: &str = "Filter";
+const POPUP_TITLE_BOTTOM: &str = " <Enter> apply, empty to clear, <Esc> cancel ";
+const POPUP_HINT: &str = r#" words, "a phrase", amount>100, date:2025-01..2025-03, acct:2 "#;
+
+impl App {
+    pub(super) fn open_filter_popup(&mut self) {
+        self.filter_input = Input::new(self.trn_query.clone());
+        self.popup = Some(PopUp::Filter);
+    }
+
+    /// Query line over the bottom of the screen
+    pub(super) fn draw_filter_popup(&self, area: Rect, buf: &mut Buffer) {
+        let height = 3.min(area.height);
+        let area = Rect {
+            y: area.bottom() - height,
+            height,
+            ..area
+        };
+        let block = Block::default()
+            .title(POPUP_TITLE)
+            .title(Line::from(POPUP_HINT).right_aligned())
+            .title_bottom(Line::from(POPUP_TITLE_BOTTOM).right_aligned())
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded);
+        let inner = block.inner(area);
+
+        Clear.render(area, buf);
+        block.render(area, buf);
+        if inner.width > 0 {
+            Paragraph::new(utils::to_text_with_cursor(&self.filter_input, inner.width))
+                .render(inner, buf);
+        }
+    }
+
+    pub(super) fn input_filter_popup(&mut self, key_event: &KeyEvent) {
+        match key_event.code {
+            KeyCode::Esc => self.popup = None,
+            KeyCode::Enter => self.apply_filter(),
+            _ => {
+                self.filter_input.handle_event(&Event::Key(*key_event));
+            }
+        }
+    }
+
+    /// Shows transactions matching the query, keeps the pop-up open when it is invalid
+    fn apply_filter(&mut self) {
+        let query = self.filter_input.value().trim().to_string();
+        let result = self
+            .service
+            .get_accounts()
+            .and_then(|accounts| query::parse_query(&query, &accounts));
+
+        match result {
+            Ok(filter) => {
+                self.trn_query = query;
+                // the ledger tab stays unless the query names an account
+                self.trn_filter = TrnFilter {
+                    account_id: filter.account_id.or(self.trn_filter.account_id),
+                    sort: self.trn_filter.sort,
+                    ..filter
+                };
+                self.popup = None;
+                if let Err(report) = self.sync_account_screen() {
+                    self.events
+                        .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+                }
+            }
+            Err(report) => self
+                .events
+                .push_back(AppEvent::Notifiction(format!("Error: {report}"))),
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use std::path::Path;
+
+use color_eyre::eyre::{self, eyre};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{buffer::Buffer, layout::Rect, style::Stylize, text::Line, widgets::StatefulWidget};
+
+use super::{App, PopUp};
+use crate::{
+    import,
+    parsing::parse_account,
+    types::{AppEvent, CsvProfile},
+    widgets::form::{Form, FormState},
+};
+
+const IMPORT_TITLE: &str = "Import statement";
+const IMPORT_FIELDS: [&str; 3] = ["File", "Account", "Profile"];
+
+const FILE: usize = 0;
+const ACCOUNT: usize = 1;
+const PROFILE: usize = 2;
+
+const PROFILE_TITLE: &str = "CSV mapping profile";
+const PROFILE_FIELDS: [&str; 11] = [
+    "Name",
+    "Delimiter",
+    "Skip rows",
+    "Date column",
+    "Date format",
+    "Amount column",
+    "Outflow column",
+    "Description column",
+    "Category column",
+    "Decimal separator",
+    "Invert sign",
+];
+
+const DEFAULT_PROFILE: &str = "default";
+
+impl App {
+    pub(super) fn open_import_popup(&mut self) -> eyre::Result<()> {
+        self.accounts = self.service.get_accounts()?;
+        self.sync_profile_names()?;
+        self.import_form.replace(
+            FormState::new(&IMPORT_FIELDS)
+                .with_value(
+                    ACCOUNT,
+                    self.config.default_account.clone().unwrap_or_default(),
+                )
+                .with_value(PROFILE, String::from(DEFAULT_PROFILE)),
+        );
+        self.popup = Some(PopUp::Import);
+        Ok(())
+    }
+
+    pub(super) fn draw_import_popup(&self, area: Rect, buf: &mut Buffer) {
+        let accounts = self
+            .accounts
+            .iter()
+            .filter_map(|acc| Some(format!("{} {}", acc.account_id?, acc.name)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let profiles = self.profile_names.join(", ");
+
+        let form = Form::new(IMPORT_TITLE).hints(vec![
+            Line::default(),
+            Line::from(format!("Accounts: {accounts}")).dark_gray(),
+            Line::from(format!("Profiles of CSV files: {profiles}")).dark_gray(),
+            Line::from("OFX, QFX and QIF files need no profile").dark_gray(),
+            Line::from("<F2> to edit the profile").dark_gray(),
+        ]);
+        StatefulWidget::render(form, area, buf, &mut self.import_form.borrow_mut());
+    }
+
+    pub(super) fn input_import_popup(&mut self, key_event: &KeyEvent) {
+        let mut form = self.import_form.borrow_mut();
+        match key_event.code {
+            KeyCode::Esc => self.popup = None,
+            KeyCode::Tab | KeyCode::Down => form.next_field(),
+            KeyCode::BackTab | KeyCode::Up => form.previous_field(),
+            KeyCode::F(2) => {
+                let name = form.value(PROFILE).trim().to_string();
+                drop(form);
+                self.open_profile_popup(&name);
+            }
+            KeyCode::Enter => {
+                drop(form);
+                self.submit_import();
+            }
+            _ => form.handle_input(key_event),
+        }
+    }
+
+    fn submit_import(&mut self) {
+        let form = self.import_form.borrow();
+        let path = form.value(FILE).trim().to_string();
+        let profile = form.value(PROFILE).trim().to_string();
+        let account = parse_account(form.value(ACCOUNT), &self.accounts);
+        drop(form);
+
+        let result = account.and_then(|account_id| {
+            import::import_file(&mut self.service, Path::new(&path), &profile, account_id)
+        });
+
+        match result {
+            Ok(mut report) => {
+                self.popup = None;
+                for error in &report.errors {
+                    self.events
+                        .push_back(AppEvent::Notifiction(format!("Import error, {error}")));
+                }
+                self.events
+                    .push_back(AppEvent::Notifiction(format!("Import of {path}: {report}")));
+                if let Err(report) = self.screen_sync() {
+                    self.events
+                        .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+                }
+                if !report.duplicates.is_empty() {
+                    self.open_duplicates_popup(std::mem::take(&mut report.duplicates));
+                }
+            }
+            Err(report) => self
+                .events
+                .push_back(AppEvent::Notifiction(format!("Error: {report}"))),
+        }
+    }
+
+    fn sync_profile_names(&mut self) -> eyre::Result<()> {
+        self.profile_names = self
+            .service
+            .get_import_profiles()?
+            .into_iter()
+            .map(|profile| profile.name)
+            .collect();
+        Ok(())
+    }
+
+    /// Opens editor for the named profile, based on the default one if it is new
+    fn open_profile_popup(&mut self, name: &str) {
+        let profile = match self.service.get_import_profile(name) {
+            Ok(profile) => profile,
+            Err(_) => match self.service.get_import_profile(DEFAULT_PROFILE) {
+                Ok(default) => CsvProfile {
+                    name: name.to_string(),
+                    ..default
+                },
+                Err(report) => {
+                    self.events
+                        .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+                    return;
+                }
+            },
+        };
+
+        let column = |column: Option<usize>| column.map(|c| c.to_string()).unwrap_or_default();
+        let values = [
+            profile.name,
+            profile.delimiter.to_string(),
+            profile.skip_rows.to_string(),
+            profile.date_column.to_string(),
+            profile.date_format,
+            profile.amount_column.to_string(),
+            column(profile.outflow_column),
+            profile.description_column.to_string(),
+            column(profile.category_column),
+            profile.decimal_separator.to_string(),
+            String::from(if profile.invert_sign { "yes" } else { "no" }),
+        ];
+
+        let form = values
+            .into_iter()
+            .enumerate()
+            .fold(FormState::new(&PROFILE_FIELDS), |form, (idx, value)| {
+                form.with_value(idx, value)
+            });
+        self.profile_form.replace(form);
+        self.popup = Some(PopUp::ImportProfile);
+    }
+
+    pub(super) fn draw_profile_popup(&self, area: Rect, buf: &mut Buffer) {
+        let form = Form::new(PROFILE_TITLE).hints(vec![
+            Line::default(),
+            Line::from("Columns are numbered from 1, leave optional ones empty").dark_gray(),
+        ]);
+        StatefulWidget::render(form, area, buf, &mut self.profile_form.borrow_mut());
+    }
+
+    pub(super) fn input_profile_popup(&mut self, key_event: &KeyEvent) {
+        let mut form = self.profile_form.borrow_mut();
+        match key_event.code {
+            KeyCode::Esc => self.popup = Some(PopUp::Import),
+            KeyCode::Tab | KeyCode::Down => form.next_field(),
+            KeyCode::BackTab | KeyCode::Up => form.previous_field(),
+            KeyCode::Enter => {
+                drop(form);
+                self.submit_profile();
+            }
+            _ => form.handle_input(key_event),
+        }
+    }
+
+    fn submit_profile(&mut self) {
+        let parsed = parse_profile(&self.profile_form.borrow());
+        let profile = match parsed {
+            Ok(profile) => profile,
+            Err((field, report)) => {
+                self.profile_form.borrow_mut().focus(field);
+                self.events
+                    .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+                return;
+            }
+        };
+
+        let result = self
+            .service
+            .put_import_profile(&profile)
+            .and_then(|_| self.sync_profile_names());
+        match result {
+            Ok(()) => {
+                self.import_form
+                    .borrow_mut()
+                    .set_value(PROFILE, profile.name.clone());
+                self.popup = Some(PopUp::Import);
+                self.events.push_back(AppEvent::Notifiction(format!(
+                    "Saved import profile '{}'",
+                    profile.name
+                )));
+            }
+            Err(report) => self
+                .events
+                .push_back(AppEvent::Notifiction(format!("Error: {report}"))),
+        }
+    }
+}
+
+/// Builds profile from form values. Errors carry the index of the invalid field
+fn parse_profile(form: &FormState) -> Result<CsvProfile, (usize, eyre::Report)> {
+    let text = |idx: usize| form.value(idx).trim();
+    let symbol = |idx: usize| match text(idx).chars().collect::<Vec<_>>()[..] {
+        [c] => Ok(c),
+        _ => Err((
+            idx,
+            eyre!("{} must be a single character", PROFILE_FIELDS[idx]),
+        )),
+    };
+    let number = |idx: usize| {
+        text(idx)
+            .parse::<usize>()
+            .map_err(|_| (idx, eyre!("{} must be a number", PROFILE_FIELDS[idx])))
+    };
+    let column = |idx: usize| match number(idx)? {
+        0 => Err((idx, eyre!("columns are numbered from 1"))),
+        column => Ok(column),
+    };
+    let optional_column = |idx: usize| match text(idx) {
+        "" => Ok(None),
+        _ => column(idx).map(Some),
+    };
+
+    let name = match text(0) {
+        "" => return Err((0, eyre!("profile name can not be empty"))),
+        name => name.to_string(),
+    };
+    let invert_sign = match text(10).to_lowercase().as_str() {
+        "yes" | "y" | "true" => true,
+        "no" | "n" | "false" | "" => false,
+        _ => return Err((10, eyre!("Invert sign must be yes or no"))),
+    };
+
+    Ok(CsvProfile {
+        name,
+        delimiter: symbol(1)?,
+        skip_rows: number(2)?,
+        date_column: column(3)?,
+        date_format: text(4).to_string(),
+        amount_column: column(5)?,
+        outflow_column: optional_column(6)?,
+        description_column: column(7)?,
+        category_column: optional_column(8)?,
+        decimal_separator: symbol(9)?,
+        invert_sign,
+    })
+}
@@ -1,26 +1,46 @@
 use std::cell::RefCell;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::{sync::mpsc, thread};
 
-use chrono::{Local, TimeZone};
+use chrono::{Local, NaiveDate, TimeZone};
 use color_eyre::eyre;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Layout, Rect},
-    style::{Color, Style},
+    style::Style,
     text::Line,
     widgets::Widget,
 };
 
 use crate::{
+    config::Config,
     service::BudgetService,
-    types::{AppEvent, NavEvent, Transaction},
-    widgets::transactions::TransactionsTableState,
+    types::{
+        Account, AccountType, AppEvent, BudgetStatus, Category, CategorySpending, Duplicate,
+        MonthlyTotals, NavEvent, Schedule, ScreenMode, Transaction, TrialBalance, TrnFilter,
+        TrnStatus,
+    },
+    widgets::{editable::EditableTableState, form::FormState, theme::Theme},
 };
 
 mod account;
+mod accounts;
+mod budgets;
+mod categories;
+mod duplicates;
+mod export;
+mod filter;
+mod import;
+mod new_transaction;
 mod notifications;
+mod reconcile;
+mod reports;
+mod schedules;
+mod trial_balance;
+
+use reconcile::Reconciliation;
+use reports::ReportSpan;
 
 #[derive(Debug, PartialEq, Default)]
 enum AppState {
@@ -33,9 +53,24 @@ enum AppState {
 enum Screen {
     #[default]
     Account,
+    Accounts,
+    Budgets,
+    Categories,
+    TrialBalance,
+    Schedules,
+    Reports,
 }
 enum PopUp {
     Notifications,
+    NewTransaction,
+    Import,
+    ImportProfile,
+    Export,
+    Budget,
+    Filter,
+    Schedule,
+    Reconcile,
+    Duplicates,
 }
 
 pub struct App {
@@ -45,12 +80,73 @@ pub struct App {
     screen: Screen,
     popup: Option<PopUp>,
 
-    // budget database service
+    // budget database service and user settings
     service: BudgetService,
+    config: Config,
 
-    // account screen
+    // account screen and its filter pop-up, `transactions` is a window of
+    // `trn_count` matching ones starting at `trn_window`
     transactions: Vec<Transaction>,
-    account_state: RefCell<TransactionsTableState>,
+    trn_window: usize,
+    trn_count: usize,
+    trn_balances: Option<HashMap<isize, i64>>,
+    expanded_trns: HashSet<isize>,
+    account_state: RefCell<EditableTableState>,
+    trn_query: String,
+    trn_filter: TrnFilter,
+    filter_input: tui_input::Input,
+
+    // reconciliation of the shown ledger against a bank statement and its pop-up
+    reconciliation: Option<Reconciliation>,
+    reconcile_form: RefCell<FormState>,
+
+    // accounts screen
+    accounts: Vec<Account>,
+    balances: HashMap<u8, i64>,
+    accounts_state: RefCell<EditableTableState>,
+
+    // categories screen, categories are also offered when editing transactions
+    categories: Vec<Category>,
+    category_usage: HashMap<isize, i64>,
+    categories_state: RefCell<EditableTableState>,
+
+    // trial balance screen
+    trial_balance: TrialBalance,
+    trial_balance_offset: usize,
+
+    // scheduled transactions screen and pop-up, `schedule_edited` is the one in the form
+    schedules: Vec<Schedule>,
+    schedules_selected: usize,
+    schedule_form: RefCell<FormState>,
+    schedule_edited: Option<Schedule>,
+
+    // reports screen, spending of the period starting at `report_start`
+    spending: Vec<CategorySpending>,
+    monthly_totals: Vec<MonthlyTotals>,
+    report_start: NaiveDate,
+    report_span: ReportSpan,
+    spending_offset: usize,
+
+    // budgets screen and pop-up
+    budgets: Vec<BudgetStatus>,
+    budgets_month: NaiveDate,
+    budgets_selected: usize,
+    budget_form: RefCell<FormState>,
+
+    // new transaction pop-up
+    trn_form: RefCell<FormState>,
+
+    // CSV import pop-ups
+    import_form: RefCell<FormState>,
+    profile_form: RefCell<FormState>,
+    profile_names: Vec<String>,
+
+    // review of new transactions that look like stored ones
+    duplicates: Vec<Duplicate>,
+    duplicates_selected: usize,
+
+    // export pop-up
+    export_form: RefCell<FormState>,
 
     // misc
     frames_count: u32,
@@ -59,9 +155,7 @@ pub struct App {
 }
 
 impl App {
-    pub fn new() -> Self {
-        let service = BudgetService::new("budget.db");
-
+    pub fn new(service: BudgetService, config: Config) -> Self {
         Self {
             state: AppState::default(),
 
@@ -69,9 +163,59 @@ impl App {
             popup: None,
 
             service,
+            config,
 
             transactions: vec![],
-            account_state: RefCell::new(TransactionsTableState::default()),
+            trn_window: 0,
+            trn_count: 0,
+            trn_balances: None,
+            expanded_trns: HashSet::new(),
+            account_state: RefCell::new(EditableTableState::default()),
+            trn_query: String::new(),
+            trn_filter: TrnFilter::default(),
+            filter_input: tui_input::Input::default(),
+
+            reconciliation: None,
+            reconcile_form: RefCell::new(FormState::default()),
+
+            accounts: vec![],
+            balances: HashMap::new(),
+            accounts_state: RefCell::new(EditableTableState::default()),
+
+            categories: vec![],
+            category_usage: HashMap::new(),
+            categories_state: RefCell::new(EditableTableState::default()),
+
+            trial_balance: TrialBalance::default(),
+            trial_balance_offset: 0,
+
+            schedules: vec![],
+            schedules_selected: 0,
+            schedule_form: RefCell::new(FormState::default()),
+            schedule_edited: None,
+
+            spending: vec![],
+            monthly_totals: vec![],
+            report_start: budgets::current_month(),
+            report_span: ReportSpan::default(),
+            spending_offset: 0,
+
+            budgets: vec![],
+            budgets_month: budgets::current_month(),
+            budgets_selected: 0,
+            budget_form: RefCell::new(FormState::default()),
+
+            trn_form: RefCell::new(FormState::default()),
+
+            import_form: RefCell::new(FormState::default()),
+            profile_form: RefCell::new(FormState::default()),
+            profile_names: vec![],
+
+            duplicates: vec![],
+            duplicates_selected: 0,
+
+            export_form: RefCell::new(FormState::default()),
+
             frames_count: 0,
 
             notifications: vec![],
@@ -90,6 +234,11 @@ impl App {
             }
         });
 
+        match self.service.run_schedules(Local::now().date_naive()) {
+            Ok(0) => {}
+            Ok(added) => self.notify(format!("Added {added} scheduled transactions")),
+            Err(report) => self.notify(format!("Error: {report}")),
+        }
         if let Err(report) = self.screen_sync() {
             self.notify(format!("Error: {report}"));
         }
@@ -120,7 +269,12 @@ impl App {
 
         if let Some(text) = self.notifications.last() {
             Line::from(text.as_str())
-                .style(Style::default().fg(Color::Red))
+                .style(Style::default().fg(self.theme().error_fg))
+                .render(footer, frame.buffer_mut());
+        }
+        if self.screen == Screen::Account && !self.trn_query.is_empty() {
+            Line::from(format!(" {} matching ", self.trn_count))
+                .right_aligned()
                 .render(footer, frame.buffer_mut());
         }
     }
@@ -131,9 +285,13 @@ impl App {
                 // Global key handler
 
                 if self.popup.is_some() {
+                    self.popup_input(&key_event);
+                } else if self.screen_mode() == ScreenMode::Editing {
+                    // Everything except accept/cancel goes to the editor
                     match key_event.code {
-                        KeyCode::Char('q' | 'Q') | KeyCode::Esc => self.popup = None,
-                        _ => {}
+                        KeyCode::Enter => self.screen_nav(NavEvent::Interact),
+                        KeyCode::Esc => self.screen_nav(NavEvent::Cancel),
+                        _ => self.screen_input(&key_event),
                     }
                 } else {
                     match key_event.code {
@@ -152,6 +310,15 @@ impl App {
                         KeyCode::Enter => self.screen_nav(NavEvent::Interact),
                         KeyCode::Esc => self.screen_nav(NavEvent::Cancel),
 
+                        // Screens
+                        KeyCode::Char('1') => self.switch_screen(Screen::Account)?,
+                        KeyCode::Char('2') => self.switch_screen(Screen::Accounts)?,
+                        KeyCode::Char('3') => self.switch_screen(Screen::Budgets)?,
+                        KeyCode::Char('4') => self.switch_screen(Screen::Categories)?,
+                        KeyCode::Char('5') => self.switch_screen(Screen::TrialBalance)?,
+                        KeyCode::Char('6') => self.switch_screen(Screen::Schedules)?,
+                        KeyCode::Char('7') => self.switch_screen(Screen::Reports)?,
+
                         // Pop-ups
                         KeyCode::Char('n' | 'N') => self.popup = Some(PopUp::Notifications),
 
@@ -159,12 +326,17 @@ impl App {
                         KeyCode::Char('g' | 'G') => {
                             self.notifications
                                 .push(String::from("Generating 5 fake transactions"));
-                            self.service.put_trns(&gen_fake_trancations(5))?;
+                            let (credit_acc_id, debit_acc_id) = self.fake_accounts()?;
+                            self.service
+                                .put_trns_with_categories(&gen_fake_trancations(
+                                    5,
+                                    credit_acc_id,
+                                    debit_acc_id,
+                                ))?;
                             self.screen_sync()?;
                         }
-                        _ => {}
+                        _ => self.screen_input(&key_event),
                     }
-                    self.screen_input(&key_event);
                 }
             }
         };
@@ -177,24 +349,73 @@ impl App {
         Ok(())
     }
 
+    fn theme(&self) -> Theme {
+        Theme::from(self.config.theme)
+    }
+
     fn notify(&mut self, msg: String) {
         self.notifications.push(msg);
     }
 
+    fn switch_screen(&mut self, screen: Screen) -> eyre::Result<()> {
+        self.screen = screen;
+        self.screen_sync()
+    }
+
     fn screen_sync(&mut self) -> eyre::Result<()> {
         match &self.screen {
             Screen::Account => self.sync_account_screen(),
+            Screen::Accounts => self.sync_accounts_screen(),
+            Screen::Budgets => self.sync_budgets_screen(),
+            Screen::Categories => self.sync_categories_screen(),
+            Screen::TrialBalance => self.sync_trial_balance_screen(),
+            Screen::Schedules => self.sync_schedules_screen(),
+            Screen::Reports => self.sync_reports_screen(),
         }
     }
 
     fn screen_draw(&self, area: Rect, buf: &mut Buffer) {
         match &self.screen {
             Screen::Account => self.draw_account_screen(area, buf),
+            Screen::Accounts => self.draw_accounts_screen(area, buf),
+            Screen::Budgets => self.draw_budgets_screen(area, buf),
+            Screen::Categories => self.draw_categories_screen(area, buf),
+            Screen::TrialBalance => self.draw_trial_balance_screen(area, buf),
+            Screen::Schedules => self.draw_schedules_screen(area, buf),
+            Screen::Reports => self.draw_reports_screen(area, buf),
         }
     }
     fn popup_draw(&self, area: Rect, buf: &mut Buffer) {
         match &self.popup {
             Some(PopUp::Notifications) => self.draw_notifications_popup(area, buf),
+            Some(PopUp::NewTransaction) => self.draw_new_trn_popup(area, buf),
+            Some(PopUp::Import) => self.draw_import_popup(area, buf),
+            Some(PopUp::ImportProfile) => self.draw_profile_popup(area, buf),
+            Some(PopUp::Export) => self.draw_export_popup(area, buf),
+            Some(PopUp::Budget) => self.draw_budget_popup(area, buf),
+            Some(PopUp::Filter) => self.draw_filter_popup(area, buf),
+            Some(PopUp::Schedule) => self.draw_schedule_popup(area, buf),
+            Some(PopUp::Reconcile) => self.draw_reconcile_popup(area, buf),
+            Some(PopUp::Duplicates) => self.draw_duplicates_popup(area, buf),
+            None => {}
+        }
+    }
+
+    fn popup_input(&mut self, event: &KeyEvent) {
+        match &self.popup {
+            Some(PopUp::Notifications) => match event.code {
+                KeyCode::Char('q' | 'Q') | KeyCode::Esc => self.popup = None,
+                _ => {}
+            },
+            Some(PopUp::NewTransaction) => self.input_new_trn_popup(event),
+            Some(PopUp::Import) => self.input_import_popup(event),
+            Some(PopUp::ImportProfile) => self.input_profile_popup(event),
+            Some(PopUp::Export) => self.input_export_popup(event),
+            Some(PopUp::Budget) => self.input_budget_popup(event),
+            Some(PopUp::Filter) => self.input_filter_popup(event),
+            Some(PopUp::Schedule) => self.input_schedule_popup(event),
+            Some(PopUp::Reconcile) => self.input_reconcile_popup(event),
+            Some(PopUp::Duplicates) => self.input_duplicates_popup(event),
             None => {}
         }
     }
@@ -202,17 +423,73 @@ impl App {
     fn screen_input(&mut self, event: &KeyEvent) {
         match &self.screen {
             Screen::Account => self.input_account_screen(event),
+            Screen::Accounts => self.input_accounts_screen(event),
+            Screen::Budgets => self.input_budgets_screen(event),
+            Screen::Categories => self.input_categories_screen(event),
+            Screen::TrialBalance => self.input_trial_balance_screen(event),
+            Screen::Schedules => self.input_schedules_screen(event),
+            Screen::Reports => self.input_reports_screen(event),
+        }
+    }
+
+    fn screen_mode(&self) -> ScreenMode {
+        match &self.screen {
+            Screen::Account => self.account_state.borrow().mode,
+            Screen::Accounts => self.accounts_state.borrow().mode,
+            Screen::Budgets => ScreenMode::Browsing,
+            Screen::Categories => self.categories_state.borrow().mode,
+            Screen::TrialBalance => ScreenMode::Browsing,
+            Screen::Schedules => ScreenMode::Browsing,
+            Screen::Reports => ScreenMode::Browsing,
         }
     }
 
-    fn screen_nav(&self, nav_event: NavEvent) {
+    fn screen_nav(&mut self, nav_event: NavEvent) {
         match &self.screen {
             Screen::Account => self.nav_account_screen(nav_event),
+            Screen::Accounts => self.nav_accounts_screen(nav_event),
+            Screen::Budgets => self.nav_budgets_screen(nav_event),
+            Screen::Categories => self.nav_categories_screen(nav_event),
+            Screen::TrialBalance => self.nav_trial_balance_screen(nav_event),
+            Screen::Schedules => self.nav_schedules_screen(nav_event),
+            Screen::Reports => self.nav_reports_screen(nav_event),
+        }
+    }
+
+    /// Returns ids of the first two accounts, creating fake ones if there are not enough
+    fn fake_accounts(&mut self) -> eyre::Result<(u8, u8)> {
+        let mut accounts = self.service.get_accounts()?;
+        if accounts.len() < 2 {
+            for account in gen_fake_accounts(&self.config.currency) {
+                self.service.put_account(&account)?;
+            }
+            accounts = self.service.get_accounts()?;
+        }
+
+        match (accounts[0].account_id, accounts[1].account_id) {
+            (Some(credit_acc_id), Some(debit_acc_id)) => Ok((credit_acc_id, debit_acc_id)),
+            _ => Err(eyre::eyre!("accounts are not saved")),
         }
     }
 }
 
-fn gen_fake_trancations(size: u32) -> Vec<Transaction> {
+fn gen_fake_accounts(currency: &str) -> Vec<Account> {
+    [
+        ("Fake cash", AccountType::Asset),
+        ("Fake expenses", AccountType::Expense),
+    ]
+    .into_iter()
+    .map(|(name, acc_type)| Account {
+        account_id: None,
+        name: String::from(name),
+        acc_type,
+        currency: currency.to_string(),
+        opening_balance: 0,
+    })
+    .collect()
+}
+
+fn gen_fake_trancations(size: u32, credit_acc_id: u8, debit_acc_id: u8) -> Vec<Transaction> {
     (0..size)
         .map(|num| {
             let timestamp = Local
@@ -221,12 +498,15 @@ fn gen_fake_trancations(size: u32) -> Vec<Transaction> {
 
             Transaction {
                 transaction_id: None,
-                credit_acc_id: Some(1),
-                debit_acc_id: Some(2),
+                credit_acc_id: Some(credit_acc_id),
+                debit_acc_id: Some(debit_acc_id),
                 timestamp,
                 amount: num as i64 * 100,
                 category: Some(String::from(&format!("Category #{}", num + 1))),
                 description: Some(String::from(&format!("Desctiption #{}", num + 1))),
+                splits: vec![],
+                status: TrnStatus::Uncleared,
+                external_id: None,
             }
         })
         .collect()
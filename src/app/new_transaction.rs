@@ -0,0 +1,172 @@
+use chrono::{Local, NaiveTime};
+use color_eyre::eyre::{self, eyre};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{buffer::Buffer, layout::Rect, style::Stylize, text::Line, widgets::StatefulWidget};
+
+use super::{App, PopUp};
+use crate::{
+    currency::account_exponents,
+    parsing::{format_money, local_datetime, parse_account, parse_date, parse_money, parse_splits},
+    types::{Account, AppEvent, Split, Transaction, TrnStatus},
+    widgets::form::{Form, FormState},
+};
+
+const FORM_TITLE: &str = "New transaction";
+const FORM_FIELDS: [&str; 7] = [
+    "Date",
+    "Debit account",
+    "Credit account",
+    "Category",
+    "Amount",
+    "Description",
+    "Splits",
+];
+
+const DATE: usize = 0;
+const DEBIT: usize = 1;
+const CREDIT: usize = 2;
+const CATEGORY: usize = 3;
+const AMOUNT: usize = 4;
+const DESCRIPTION: usize = 5;
+const SPLITS: usize = 6;
+const SPLITS_HINT: &str = "Splits: category amount; category amount";
+
+impl App {
+    pub(super) fn open_new_trn_popup(&mut self) -> eyre::Result<()> {
+        self.accounts = self.service.get_accounts()?;
+        let today = Local::now().date_naive();
+        self.trn_form.replace(
+            FormState::new(&FORM_FIELDS)
+                .with_value(DATE, today.format(&self.config.date_format).to_string())
+                .with_value(
+                    CREDIT,
+                    self.config.default_account.clone().unwrap_or_default(),
+                ),
+        );
+        self.popup = Some(PopUp::NewTransaction);
+        Ok(())
+    }
+
+    pub(super) fn draw_new_trn_popup(&self, area: Rect, buf: &mut Buffer) {
+        let accounts = self
+            .accounts
+            .iter()
+            .filter_map(|acc| Some(format!("{} {}", acc.account_id?, acc.name)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let form = Form::new(FORM_TITLE).hints(vec![
+            Line::default(),
+            Line::from(format!("Accounts: {accounts}")).dark_gray(),
+            Line::from(SPLITS_HINT).dark_gray(),
+        ]);
+        StatefulWidget::render(form, area, buf, &mut self.trn_form.borrow_mut());
+    }
+
+    pub(super) fn input_new_trn_popup(&mut self, key_event: &KeyEvent) {
+        let mut form = self.trn_form.borrow_mut();
+        match key_event.code {
+            KeyCode::Esc => self.popup = None,
+            KeyCode::Tab | KeyCode::Down => form.next_field(),
+            KeyCode::BackTab | KeyCode::Up => form.previous_field(),
+            KeyCode::Enter => {
+                drop(form);
+                self.submit_new_trn();
+            }
+            _ => form.handle_input(key_event),
+        }
+    }
+
+    /// Saves the transaction and closes the form, or focuses the invalid field.
+    /// A transaction that looks like a stored one goes to the duplicates review.
+    fn submit_new_trn(&mut self) {
+        let parsed = parse_form(
+            &self.trn_form.borrow(),
+            &self.accounts,
+            &self.config.date_format,
+        );
+        let trn = match parsed {
+            Ok(trn) => trn,
+            Err((field, report)) => {
+                self.trn_form.borrow_mut().focus(field);
+                self.events
+                    .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+                return;
+            }
+        };
+
+        match self.service.find_duplicates(vec![trn.clone()]) {
+            Ok((_, duplicates)) if !duplicates.is_empty() => {
+                self.open_duplicates_popup(duplicates);
+                return;
+            }
+            Ok(_) => {}
+            Err(report) => {
+                self.events
+                    .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+                return;
+            }
+        }
+
+        let result = self.service.put_trn(&trn).and_then(|trn_id| {
+            self.screen_sync()?;
+            Ok(trn_id)
+        });
+        let msg = match result {
+            Ok(trn_id) => {
+                self.popup = None;
+                format!("Added transaction {trn_id}")
+            }
+            Err(report) => format!("Error: {report}"),
+        };
+        self.events.push_back(AppEvent::Notifiction(msg));
+    }
+}
+
+/// Builds transaction from form values. Errors carry the index of the invalid field
+fn parse_form(
+    form: &FormState,
+    accounts: &[Account],
+    date_format: &str,
+) -> Result<Transaction, (usize, eyre::Report)> {
+    let field = |idx: usize| move |report| (idx, report);
+    let text = |idx: usize| match form.value(idx).trim() {
+        "" => None,
+        value => Some(value.to_string()),
+    };
+
+    let date = parse_date(form.value(DATE), date_format).map_err(field(DATE))?;
+    let timestamp = local_datetime(date, NaiveTime::MIN).map_err(field(DATE))?;
+    let debit_acc_id = parse_account(form.value(DEBIT), accounts).map_err(field(DEBIT))?;
+    let credit_acc_id = parse_account(form.value(CREDIT), accounts).map_err(field(CREDIT))?;
+    if debit_acc_id == credit_acc_id {
+        return Err((CREDIT, eyre!("debit and credit accounts must be different")));
+    }
+    let exponent = account_exponents(accounts)[&debit_acc_id];
+    let amount = parse_money(form.value(AMOUNT), exponent).map_err(field(AMOUNT))?;
+    let splits = parse_splits(form.value(SPLITS), exponent).map_err(field(SPLITS))?;
+    let split_total = Split::total(&splits).map_err(field(SPLITS))?;
+    if !splits.is_empty() && split_total != amount {
+        return Err((
+            SPLITS,
+            eyre!(
+                "splits add up to {}, not to the amount {}",
+                format_money(split_total, exponent),
+                format_money(amount, exponent)
+            ),
+        ));
+    }
+
+    Ok(Transaction {
+        transaction_id: None,
+        credit_acc_id: Some(credit_acc_id),
+        debit_acc_id: Some(debit_acc_id),
+        timestamp,
+        category: text(CATEGORY),
+        amount,
+        description: text(DESCRIPTION),
+        splits,
+        status: TrnStatus::Uncleared,
+        external_id: None,
+    })
+}
@@ -1,7 +1,7 @@
 use ratatui::{
     buffer::Buffer,
     layout::{Margin, Rect},
-    style::{Color, Stylize},
+    style::Stylize,
     text::Line,
     widgets::{Block, BorderType, Borders, Clear, List, Padding, Widget},
 };
@@ -23,7 +23,7 @@ impl App {
                 .iter()
                 .rev()
                 .take(area.height as usize)
-                .map(|line| Line::from(line.as_str().fg(Color::Red))),
+                .map(|line| Line::from(line.as_str().fg(self.theme().error_fg))),
         )
         .block(block);
 
@@ -0,0 +1,260 @@
+use chrono::{Local, NaiveDate};
+use color_eyre::eyre::{self, eyre};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{StatefulWidget, Widget},
+};
+
+use super::{App, PopUp};
+use crate::{
+    currency::exponent,
+    parsing::{format_money, parse_date, parse_money},
+    types::{AppEvent, TrnStatus},
+    widgets::form::{Form, FormState},
+};
+
+const FORM_TITLE: &str = "Reconcile";
+const FORM_FIELDS: [&str; 2] = ["Statement date", "Statement balance"];
+
+const DATE: usize = 0;
+const BALANCE: usize = 1;
+
+const RECONCILE_HINT: &str = " <c> clear, <f> finish, <Esc> stop ";
+
+/// Bank statement the ledger of an account is being reconciled against
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Reconciliation {
+    pub account_id: u8,
+    pub date: NaiveDate,
+    pub balance: i64,
+    /// Balance of the cleared transactions up to the statement date
+    pub cleared: i64,
+}
+
+impl Reconciliation {
+    /// Left to clear until the ledger matches the statement
+    fn difference(&self) -> i64 {
+        self.balance - self.cleared
+    }
+}
+
+impl App {
+    /// Status line of the reconciliation, drawn under the transactions
+    pub(super) fn draw_reconciliation(&self, area: Rect, buf: &mut Buffer) {
+        let Some(reconciliation) = &self.reconciliation else {
+            return;
+        };
+        let theme = self.theme();
+        let format =
+            |amount| format_money(amount, self.account_exponent(reconciliation.account_id));
+        let difference_style = match reconciliation.difference() {
+            0 => Style::default()
+                .fg(theme.gauge_fg)
+                .add_modifier(Modifier::BOLD),
+            _ => Style::default().fg(theme.error_fg),
+        };
+
+        Line::from(vec![
+            Span::from(format!(
+                " Statement of {}: {}, cleared {}, difference ",
+                reconciliation.date.format(&self.config.date_format),
+                format(reconciliation.balance),
+                format(reconciliation.cleared),
+            )),
+            Span::styled(format(reconciliation.difference()), difference_style),
+        ])
+        .render(area, buf);
+        Line::from(RECONCILE_HINT)
+            .dark_gray()
+            .right_aligned()
+            .render(area, buf);
+    }
+
+    /// Minor unit digits of the account currency
+    fn account_exponent(&self, account_id: u8) -> u32 {
+        let currency = self
+            .accounts
+            .iter()
+            .find(|account| account.account_id == Some(account_id))
+            .map_or("", |account| account.currency.as_str());
+        exponent(currency)
+    }
+
+    /// Recomputes the cleared balance after transactions changed
+    pub(super) fn sync_reconciliation(&mut self) -> eyre::Result<()> {
+        if let Some(reconciliation) = &mut self.reconciliation {
+            reconciliation.cleared = self
+                .service
+                .get_cleared_balance(reconciliation.account_id, reconciliation.date)?;
+        }
+        Ok(())
+    }
+
+    /// Opens the statement form for the account of the shown ledger
+    pub(super) fn open_reconcile_popup(&mut self) -> eyre::Result<()> {
+        let account_id = self
+            .trn_filter
+            .account_id
+            .ok_or_else(|| eyre!("select an account tab to reconcile it"))?;
+
+        let mut form = FormState::new(&FORM_FIELDS).with_value(
+            DATE,
+            Local::now()
+                .date_naive()
+                .format(&self.config.date_format)
+                .to_string(),
+        );
+        if let Some(reconciliation) = self
+            .reconciliation
+            .as_ref()
+            .filter(|reconciliation| reconciliation.account_id == account_id)
+        {
+            form.set_value(
+                DATE,
+                reconciliation
+                    .date
+                    .format(&self.config.date_format)
+                    .to_string(),
+            );
+            form.set_value(
+                BALANCE,
+                format_money(reconciliation.balance, self.account_exponent(account_id)),
+            );
+        }
+        form.focus(BALANCE);
+        self.reconcile_form.replace(form);
+        self.popup = Some(PopUp::Reconcile);
+        Ok(())
+    }
+
+    pub(super) fn draw_reconcile_popup(&self, area: Rect, buf: &mut Buffer) {
+        let form = Form::new(FORM_TITLE).hints(vec![
+            Line::default(),
+            Line::from("Clear the rows found on the statement until the difference is zero")
+                .dark_gray(),
+        ]);
+        StatefulWidget::render(form, area, buf, &mut self.reconcile_form.borrow_mut());
+    }
+
+    pub(super) fn input_reconcile_popup(&mut self, key_event: &KeyEvent) {
+        let mut form = self.reconcile_form.borrow_mut();
+        match key_event.code {
+            KeyCode::Esc => self.popup = None,
+            KeyCode::Tab | KeyCode::Down => form.next_field(),
+            KeyCode::BackTab | KeyCode::Up => form.previous_field(),
+            KeyCode::Enter => {
+                drop(form);
+                self.start_reconciliation();
+            }
+            _ => form.handle_input(key_event),
+        }
+    }
+
+    fn start_reconciliation(&mut self) {
+        let Some(account_id) = self.trn_filter.account_id else {
+            self.popup = None;
+            return;
+        };
+        let parsed = {
+            let form = self.reconcile_form.borrow();
+            parse_date(form.value(DATE), &self.config.date_format)
+                .map_err(|report| (DATE, report))
+                .and_then(|date| {
+                    let balance =
+                        parse_money(form.value(BALANCE), self.account_exponent(account_id))
+                            .map_err(|report| (BALANCE, report))?;
+                    Ok((date, balance))
+                })
+        };
+        let (date, balance) = match parsed {
+            Ok(statement) => statement,
+            Err((field, report)) => {
+                self.reconcile_form.borrow_mut().focus(field);
+                self.events
+                    .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+                return;
+            }
+        };
+
+        self.reconciliation = Some(Reconciliation {
+            account_id,
+            date,
+            balance,
+            cleared: 0,
+        });
+        self.popup = None;
+        if let Err(report) = self.sync_reconciliation() {
+            self.reconciliation = None;
+            self.events
+                .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+        }
+    }
+
+    /// Marks the selected transaction cleared, or uncleared when it already is,
+    /// while the shown account is being reconciled
+    pub(super) fn toggle_cleared(&mut self) {
+        let reconciling = self.reconciliation.as_ref().is_some_and(|reconciliation| {
+            self.trn_filter.account_id == Some(reconciliation.account_id)
+        });
+        if !reconciling {
+            return;
+        }
+        let (Some(row), _) = self.account_state.borrow().selected() else {
+            return;
+        };
+        let Some(trn) = self.trn_at(row) else {
+            return;
+        };
+        let Some(trn_id) = trn.transaction_id else {
+            return;
+        };
+        let status = match trn.status {
+            TrnStatus::Cleared => TrnStatus::Uncleared,
+            _ => TrnStatus::Cleared,
+        };
+
+        let result = self
+            .service
+            .set_trn_status(&[trn_id], status)
+            .and_then(|_| self.sync_account_screen());
+        if let Err(report) = result {
+            self.events
+                .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+        }
+    }
+
+    /// Locks the cleared transactions once the difference is zero
+    pub(super) fn finish_reconciliation(&mut self) {
+        let Some(reconciliation) = self.reconciliation.clone() else {
+            return;
+        };
+        if reconciliation.difference() != 0 {
+            self.events.push_back(AppEvent::Notifiction(String::from(
+                "Error: the difference must be zero to finish reconciling",
+            )));
+            return;
+        }
+
+        let result = self
+            .service
+            .reconcile(
+                reconciliation.account_id,
+                reconciliation.date,
+                reconciliation.balance,
+            )
+            .and_then(|count| {
+                self.reconciliation = None;
+                self.sync_account_screen()?;
+                Ok(count)
+            });
+        let msg = match result {
+            Ok(count) => format!("Reconciled {count} transactions, press <u> to undo"),
+            Err(report) => format!("Error: {report}"),
+        };
+        self.events.push_back(AppEvent::Notifiction(msg));
+    }
+}
@@ -0,0 +1,291 @@
+use chrono::{Datelike, Months, NaiveDate};
+use color_eyre::eyre::{self, eyre};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Style, Stylize},
+    symbols::Marker,
+    text::Line,
+    widgets::{
+        Axis, Bar, BarChart, BarGroup, Block, BorderType, Borders, Chart, Clear, Dataset,
+        GraphType, Widget,
+    },
+};
+
+use super::App;
+use crate::{
+    currency::exponent,
+    parsing::format_money,
+    types::{AppEvent, NavEvent},
+};
+
+const SCREEN_TITLE_BOTTOM: &str = " ← → period, <p> month/quarter/year, ↑ ↓ scroll ";
+const TREND_TITLE: &str = "Income and expenses by month";
+const TREND_MONTHS: u32 = 12;
+const UNCATEGORIZED: &str = "(none)";
+
+/// Length of the period the spending report covers
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ReportSpan {
+    #[default]
+    Month,
+    Quarter,
+    Year,
+}
+
+impl ReportSpan {
+    fn months(&self) -> u32 {
+        match self {
+            ReportSpan::Month => 1,
+            ReportSpan::Quarter => 3,
+            ReportSpan::Year => 12,
+        }
+    }
+
+    fn next(&self) -> ReportSpan {
+        match self {
+            ReportSpan::Month => ReportSpan::Quarter,
+            ReportSpan::Quarter => ReportSpan::Year,
+            ReportSpan::Year => ReportSpan::Month,
+        }
+    }
+
+    /// First day of the period containing `date`
+    fn start(&self, date: NaiveDate) -> NaiveDate {
+        let month0 = date.month0() - date.month0() % self.months();
+        NaiveDate::from_ymd_opt(date.year(), month0 + 1, 1).unwrap_or(date)
+    }
+
+    fn label(&self, start: NaiveDate) -> String {
+        match self {
+            ReportSpan::Month => start.format("%B %Y").to_string(),
+            ReportSpan::Quarter => format!("Q{} {}", start.month0() / 3 + 1, start.year()),
+            ReportSpan::Year => start.format("%Y").to_string(),
+        }
+    }
+}
+
+impl App {
+    pub(super) fn draw_reports_screen(&self, area: Rect, buf: &mut Buffer) {
+        let spent: i64 = self.spending.iter().map(|line| line.amount).sum();
+        let block = Block::default()
+            .title(format!(
+                "Spending {} in {}",
+                self.report_span.label(self.report_start),
+                self.config.currency
+            ))
+            .title(Line::from(format!(" {} ", self.format_report(spent))).right_aligned())
+            .title_bottom(Line::from(SCREEN_TITLE_BOTTOM).right_aligned())
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded);
+        let inner = block.inner(area);
+
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let [bars, trend] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(14)]).areas(inner);
+        self.draw_spending_bars(bars, buf);
+        self.draw_trend_chart(trend, buf);
+    }
+
+    /// One horizontal bar per category, scrolled by `spending_offset`
+    fn draw_spending_bars(&self, area: Rect, buf: &mut Buffer) {
+        let theme = self.theme();
+        if self.spending.is_empty() {
+            Line::from(" Nothing spent in this period")
+                .dark_gray()
+                .render(area, buf);
+            return;
+        }
+
+        let label_width = self
+            .spending
+            .iter()
+            .map(|line| line.category.as_deref().unwrap_or(UNCATEGORIZED).len())
+            .max()
+            .unwrap_or_default()
+            .min(area.width as usize / 3);
+        let bars: Vec<Bar> = self
+            .spending
+            .iter()
+            .skip(self.spending_offset)
+            .map(|line| {
+                let category = line.category.as_deref().unwrap_or(UNCATEGORIZED);
+                Bar::default()
+                    .label(Line::from(format!("{category:>label_width$}")))
+                    .value(line.amount.max(0) as u64)
+                    .text_value(self.format_report(line.amount))
+                    .style(Style::default().fg(theme.gauge_fg))
+                    .value_style(Style::default().reversed())
+            })
+            .collect();
+        let max = self.spending.iter().map(|line| line.amount).max();
+
+        BarChart::default()
+            .direction(Direction::Horizontal)
+            .data(BarGroup::default().bars(&bars))
+            .max(max.unwrap_or_default().max(1) as u64)
+            .bar_width(1)
+            .bar_gap(0)
+            .render(area, buf);
+    }
+
+    /// Income and expense lines over the months up to the end of the period
+    fn draw_trend_chart(&self, area: Rect, buf: &mut Buffer) {
+        let theme = self.theme();
+        let scale = 10_f64.powi(exponent(&self.config.currency) as i32);
+        let (income, expenses): (Vec<_>, Vec<_>) = self
+            .monthly_totals
+            .iter()
+            .enumerate()
+            .map(|(idx, totals)| {
+                let x = idx as f64;
+                (
+                    (x, totals.income as f64 / scale),
+                    (x, totals.expenses as f64 / scale),
+                )
+            })
+            .unzip();
+
+        let top = income
+            .iter()
+            .chain(&expenses)
+            .map(|&(_, value)| value)
+            .fold(0.0, f64::max);
+        let bottom = income
+            .iter()
+            .chain(&expenses)
+            .map(|&(_, value)| value)
+            .fold(0.0, f64::min);
+        let months = self.monthly_totals.len().saturating_sub(1).max(1) as f64;
+        let month_labels: Vec<Line> = [self.monthly_totals.first(), self.monthly_totals.last()]
+            .into_iter()
+            .flatten()
+            .map(|totals| Line::from(totals.month.format("%b %Y").to_string()))
+            .collect();
+
+        let datasets = vec![
+            Dataset::default()
+                .name("Income")
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(theme.gauge_fg))
+                .data(&income),
+            Dataset::default()
+                .name("Expenses")
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(theme.error_fg))
+                .data(&expenses),
+        ];
+        Chart::new(datasets)
+            .block(
+                Block::default()
+                    .title(TREND_TITLE)
+                    .borders(Borders::TOP)
+                    .border_type(BorderType::Rounded),
+            )
+            .x_axis(
+                Axis::default()
+                    .bounds([0.0, months])
+                    .labels(month_labels)
+                    .dark_gray(),
+            )
+            .y_axis(
+                Axis::default()
+                    .bounds([bottom, top.max(bottom + 1.0)])
+                    .labels([format!("{bottom:.0}"), format!("{top:.0}")])
+                    .dark_gray(),
+            )
+            .render(area, buf);
+    }
+
+    /// Formats an amount in the configured currency
+    fn format_report(&self, amount: i64) -> String {
+        format_money(amount, exponent(&self.config.currency))
+    }
+
+    fn report_end(&self) -> eyre::Result<NaiveDate> {
+        self.report_start
+            .checked_add_months(Months::new(self.report_span.months()))
+            .ok_or_else(|| eyre!("period is out of range"))
+    }
+
+    pub(super) fn sync_reports_screen(&mut self) -> eyre::Result<()> {
+        let base = self.config.currency.clone();
+        let end = self.report_end()?;
+        self.spending = self.service.get_spending(&base, self.report_start, end)?;
+
+        let trend_start = end
+            .checked_sub_months(Months::new(TREND_MONTHS))
+            .ok_or_else(|| eyre!("period is out of range"))?;
+        self.monthly_totals = self.service.get_monthly_totals(&base, trend_start, end)?;
+        self.spending_offset = self
+            .spending_offset
+            .min(self.spending.len().saturating_sub(1));
+        Ok(())
+    }
+
+    pub(super) fn input_reports_screen(&mut self, key_event: &KeyEvent) {
+        match key_event.code {
+            KeyCode::Char('q' | 'Q') => self.exit(),
+            KeyCode::Char('p' | 'P') => {
+                self.report_span = self.report_span.next();
+                self.show_report(Some(self.report_span.start(self.report_start)));
+            }
+            _ => {}
+        }
+    }
+
+    pub(super) fn nav_reports_screen(&mut self, event: NavEvent) {
+        let months = Months::new(self.report_span.months());
+        match event {
+            NavEvent::Up => self.spending_offset = self.spending_offset.saturating_sub(1),
+            NavEvent::Down => {
+                if self.spending_offset + 1 < self.spending.len() {
+                    self.spending_offset += 1;
+                }
+            }
+            NavEvent::Left => self.show_report(self.report_start.checked_sub_months(months)),
+            NavEvent::Rigth => self.show_report(self.report_start.checked_add_months(months)),
+            NavEvent::Interact | NavEvent::Cancel => {}
+        }
+    }
+
+    fn show_report(&mut self, start: Option<NaiveDate>) {
+        let result = start
+            .ok_or_else(|| eyre!("period is out of range"))
+            .and_then(|start| {
+                self.report_start = start;
+                self.spending_offset = 0;
+                self.sync_reports_screen()
+            });
+        if let Err(report) = result {
+            self.events
+                .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn period_spans() {
+        let day = date(2025, 8, 17);
+        assert_eq!(ReportSpan::Month.start(day), date(2025, 8, 1));
+        assert_eq!(ReportSpan::Quarter.start(day), date(2025, 7, 1));
+        assert_eq!(ReportSpan::Year.start(day), date(2025, 1, 1));
+
+        assert_eq!(ReportSpan::Month.label(date(2025, 8, 1)), "August 2025");
+        assert_eq!(ReportSpan::Quarter.label(date(2025, 7, 1)), "Q3 2025");
+        assert_eq!(ReportSpan::Year.label(date(2025, 1, 1)), "2025");
+    }
+}
@@ -0,0 +1,415 @@
+use chrono::{Datelike, Local};
+use color_eyre::eyre::{self, eyre};
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Margin, Rect},
+    style::{Modifier, Style, Stylize},
+    text::{Line, Text},
+    widgets::{
+        Block, BorderType, Borders, Cell, Clear, HighlightSpacing, List, Row, StatefulWidget,
+        Table, TableState, Widget,
+    },
+};
+
+use super::{App, PopUp};
+use crate::{
+    currency::{account_exponents, DEFAULT_EXPONENT},
+    parsing::{format_money, parse_account, parse_date, parse_money},
+    types::{Account, AppEvent, Frequency, NavEvent, Schedule},
+    widgets::form::{Form, FormState},
+};
+
+const SCREEN_TITLE: &str = "Scheduled transactions";
+const SCREEN_TITLE_BOTTOM: &str = " ↑ ↓ select, <a> add, <Enter> edit, <p> pause, <d> delete ";
+const TABLE_HEADER: [&str; 6] = [
+    "Next",
+    "Repeats",
+    "From → To",
+    "Category",
+    "Amount",
+    "Status",
+];
+const ROW_HIGHLIGHT_SYMBOL: &str = " > ";
+
+const UPCOMING_TITLE: &str = "Upcoming";
+const UPCOMING_COUNT: usize = 12;
+
+const FORM_TITLE: &str = "Scheduled transaction";
+const FORM_FIELDS: [&str; 9] = [
+    "Next date",
+    "Repeats",
+    "Day of month",
+    "End date",
+    "Debit account",
+    "Credit account",
+    "Category",
+    "Amount",
+    "Description",
+];
+
+const NEXT_DATE: usize = 0;
+const FREQUENCY: usize = 1;
+const DAY_OF_MONTH: usize = 2;
+const END_DATE: usize = 3;
+const DEBIT: usize = 4;
+const CREDIT: usize = 5;
+const CATEGORY: usize = 6;
+const AMOUNT: usize = 7;
+const DESCRIPTION: usize = 8;
+
+impl App {
+    pub(super) fn draw_schedules_screen(&self, area: Rect, buf: &mut Buffer) {
+        let [table_area, upcoming_area] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Length(18)]).areas(area);
+        let date_format = &self.config.date_format;
+
+        let header = TABLE_HEADER
+            .iter()
+            .enumerate()
+            .map(|(col, &title)| match col {
+                4 => Text::from(title).right_aligned(),
+                _ => Text::from(title),
+            })
+            .collect::<Row>()
+            .style(Style::default().add_modifier(Modifier::REVERSED));
+        let rows = self.schedules.iter().map(|schedule| {
+            let status = match (schedule.paused, schedule.is_finished()) {
+                (_, true) => "Finished",
+                (true, false) => "Paused",
+                (false, false) => "Active",
+            };
+            let row = Row::new([
+                Cell::from(schedule.next_date.format(date_format).to_string()),
+                Cell::from(schedule.rule()),
+                Cell::from(format!(
+                    "{} → {}",
+                    self.account_name(schedule.credit_acc_id),
+                    self.account_name(schedule.debit_acc_id)
+                )),
+                Cell::from(schedule.category.clone().unwrap_or_default()),
+                Cell::from(Text::from(self.format_schedule_amount(schedule)).right_aligned()),
+                Cell::from(status),
+            ]);
+            match status {
+                "Active" => row,
+                _ => row.dark_gray(),
+            }
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(12),
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+                Constraint::Fill(1),
+                Constraint::Min(13),
+                Constraint::Length(8),
+            ],
+        )
+        .header(header)
+        .column_spacing(1)
+        .row_highlight_style(Style::default().bg(self.theme().selected_bg))
+        .highlight_symbol(ROW_HIGHLIGHT_SYMBOL)
+        .highlight_spacing(HighlightSpacing::Always)
+        .block(
+            Block::default()
+                .title(SCREEN_TITLE)
+                .title_bottom(Line::from(SCREEN_TITLE_BOTTOM).right_aligned())
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded),
+        );
+        let mut state = TableState::default().with_selected(Some(self.schedules_selected));
+
+        // next occurrences of the selected schedule
+        let upcoming: Vec<String> = self
+            .schedules
+            .get(self.schedules_selected)
+            .map(|schedule| {
+                schedule
+                    .occurrences()
+                    .take(UPCOMING_COUNT)
+                    .map(|date| date.format(date_format).to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let upcoming = List::new(upcoming).block(
+            Block::default()
+                .title(UPCOMING_TITLE)
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded),
+        );
+
+        Clear.render(area, buf);
+        StatefulWidget::render(table, table_area, buf, &mut state);
+        Widget::render(upcoming, upcoming_area, buf);
+        if self.schedules.is_empty() {
+            let hint = Rect {
+                y: table_area.y + 2,
+                height: 1,
+                ..table_area.inner(Margin::new(1, 0))
+            };
+            Line::from(" Nothing is scheduled, press <a> to add a transaction")
+                .dark_gray()
+                .render(hint, buf);
+        }
+    }
+
+    /// Account name for the table, or its id when the account is missing
+    fn account_name(&self, account_id: u8) -> String {
+        self.accounts
+            .iter()
+            .find(|account| account.account_id == Some(account_id))
+            .map_or_else(|| account_id.to_string(), |account| account.name.clone())
+    }
+
+    /// Amount in the currency of the accounts of the schedule
+    fn format_schedule_amount(&self, schedule: &Schedule) -> String {
+        let exponent = account_exponents(&self.accounts)
+            .get(&schedule.debit_acc_id)
+            .copied()
+            .unwrap_or(DEFAULT_EXPONENT);
+        format_money(schedule.amount, exponent)
+    }
+
+    pub(super) fn sync_schedules_screen(&mut self) -> eyre::Result<()> {
+        self.accounts = self.service.get_accounts()?;
+        self.schedules = self.service.get_schedules()?;
+        self.schedules_selected = self
+            .schedules_selected
+            .min(self.schedules.len().saturating_sub(1));
+        Ok(())
+    }
+
+    pub(super) fn input_schedules_screen(&mut self, key_event: &KeyEvent) {
+        match key_event.code {
+            KeyCode::Char('q' | 'Q') => self.exit(),
+            KeyCode::Char('a' | 'A') => self.open_schedule_popup(None),
+            KeyCode::Char('p' | 'P') => self.pause_selected_schedule(),
+            KeyCode::Char('d' | 'D') => self.delete_selected_schedule(),
+            _ => {}
+        }
+    }
+
+    pub(super) fn nav_schedules_screen(&mut self, event: NavEvent) {
+        match event {
+            NavEvent::Up => self.schedules_selected = self.schedules_selected.saturating_sub(1),
+            NavEvent::Down if self.schedules_selected + 1 < self.schedules.len() => {
+                self.schedules_selected += 1;
+            }
+            NavEvent::Interact => {
+                let schedule = self.schedules.get(self.schedules_selected).cloned();
+                if schedule.is_some() {
+                    self.open_schedule_popup(schedule);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Pauses the selected schedule, or resumes it when paused
+    fn pause_selected_schedule(&mut self) {
+        let Some(mut schedule) = self.schedules.get(self.schedules_selected).cloned() else {
+            return;
+        };
+        schedule.paused = !schedule.paused;
+
+        let result = self
+            .service
+            .put_schedule(&schedule)
+            .and_then(|_| self.sync_schedules_screen());
+        let msg = match (result, schedule.paused) {
+            (Ok(()), true) => String::from("Paused the scheduled transaction"),
+            (Ok(()), false) => String::from("Resumed the scheduled transaction"),
+            (Err(report), _) => format!("Error: {report}"),
+        };
+        self.events.push_back(AppEvent::Notifiction(msg));
+    }
+
+    fn delete_selected_schedule(&mut self) {
+        let Some(schedule_id) = self
+            .schedules
+            .get(self.schedules_selected)
+            .and_then(|schedule| schedule.schedule_id)
+        else {
+            return;
+        };
+
+        let result = self
+            .service
+            .del_schedule(schedule_id)
+            .and_then(|_| self.sync_schedules_screen());
+        let msg = match result {
+            Ok(()) => String::from("Deleted the scheduled transaction"),
+            Err(report) => format!("Error: {report}"),
+        };
+        self.events.push_back(AppEvent::Notifiction(msg));
+    }
+
+    /// Opens the schedule form, filled with `schedule` values when editing
+    fn open_schedule_popup(&mut self, schedule: Option<Schedule>) {
+        let date_format = &self.config.date_format;
+        let form = match &schedule {
+            Some(schedule) => FormState::new(&FORM_FIELDS)
+                .with_value(
+                    NEXT_DATE,
+                    schedule.next_date.format(date_format).to_string(),
+                )
+                .with_value(FREQUENCY, schedule.frequency.as_str().to_string())
+                .with_value(
+                    DAY_OF_MONTH,
+                    schedule
+                        .day_of_month
+                        .map(|day| day.to_string())
+                        .unwrap_or_default(),
+                )
+                .with_value(
+                    END_DATE,
+                    schedule
+                        .end_date
+                        .map(|date| date.format(date_format).to_string())
+                        .unwrap_or_default(),
+                )
+                .with_value(DEBIT, self.account_name(schedule.debit_acc_id))
+                .with_value(CREDIT, self.account_name(schedule.credit_acc_id))
+                .with_value(CATEGORY, schedule.category.clone().unwrap_or_default())
+                .with_value(AMOUNT, self.format_schedule_amount(schedule))
+                .with_value(
+                    DESCRIPTION,
+                    schedule.description.clone().unwrap_or_default(),
+                ),
+            None => FormState::new(&FORM_FIELDS)
+                .with_value(
+                    NEXT_DATE,
+                    Local::now().date_naive().format(date_format).to_string(),
+                )
+                .with_value(FREQUENCY, Frequency::default().as_str().to_string())
+                .with_value(
+                    CREDIT,
+                    self.config.default_account.clone().unwrap_or_default(),
+                ),
+        };
+        self.schedule_form.replace(form);
+        self.schedule_edited = schedule;
+        self.popup = Some(PopUp::Schedule);
+    }
+
+    pub(super) fn draw_schedule_popup(&self, area: Rect, buf: &mut Buffer) {
+        let frequencies: Vec<_> = Frequency::ALL.iter().map(Frequency::as_str).collect();
+        let form = Form::new(FORM_TITLE).hints(vec![
+            Line::default(),
+            Line::from(format!("Repeats: {}", frequencies.join(", "))).dark_gray(),
+            Line::from("Day of month: of monthly and yearly ones, the last day when shorter")
+                .dark_gray(),
+        ]);
+        StatefulWidget::render(form, area, buf, &mut self.schedule_form.borrow_mut());
+    }
+
+    pub(super) fn input_schedule_popup(&mut self, key_event: &KeyEvent) {
+        let mut form = self.schedule_form.borrow_mut();
+        match key_event.code {
+            KeyCode::Esc => self.popup = None,
+            KeyCode::Tab | KeyCode::Down => form.next_field(),
+            KeyCode::BackTab | KeyCode::Up => form.previous_field(),
+            KeyCode::Enter => {
+                drop(form);
+                self.submit_schedule();
+            }
+            _ => form.handle_input(key_event),
+        }
+    }
+
+    fn submit_schedule(&mut self) {
+        let parsed = parse_form(
+            &self.schedule_form.borrow(),
+            &self.accounts,
+            &self.config.date_format,
+        );
+        let mut schedule = match parsed {
+            Ok(schedule) => schedule,
+            Err((field, report)) => {
+                self.schedule_form.borrow_mut().focus(field);
+                self.events
+                    .push_back(AppEvent::Notifiction(format!("Error: {report}")));
+                return;
+            }
+        };
+        if let Some(edited) = &self.schedule_edited {
+            schedule.schedule_id = edited.schedule_id;
+            schedule.paused = edited.paused;
+        }
+
+        let result = self
+            .service
+            .put_schedule(&schedule)
+            .and_then(|_| self.sync_schedules_screen());
+        let msg = match result {
+            Ok(()) => {
+                self.popup = None;
+                format!(
+                    "Scheduled {} {}, next on {}",
+                    self.format_schedule_amount(&schedule),
+                    schedule.rule().to_lowercase(),
+                    schedule.next_date.format(&self.config.date_format)
+                )
+            }
+            Err(report) => format!("Error: {report}"),
+        };
+        self.events.push_back(AppEvent::Notifiction(msg));
+    }
+}
+
+/// Builds schedule from form values. Errors carry the index of the invalid field
+fn parse_form(
+    form: &FormState,
+    accounts: &[Account],
+    date_format: &str,
+) -> Result<Schedule, (usize, eyre::Report)> {
+    let field = |idx: usize| move |report| (idx, report);
+    let text = |idx: usize| match form.value(idx).trim() {
+        "" => None,
+        value => Some(value.to_string()),
+    };
+
+    let next_date = parse_date(form.value(NEXT_DATE), date_format).map_err(field(NEXT_DATE))?;
+    let frequency = form.value(FREQUENCY).parse().map_err(field(FREQUENCY))?;
+    let day_of_month = text(DAY_OF_MONTH)
+        .map(|day| {
+            day.parse()
+                .ok()
+                .filter(|day| (1..=31).contains(day))
+                .ok_or_else(|| (DAY_OF_MONTH, eyre!("invalid day of month '{day}'")))
+        })
+        .transpose()?
+        .or(match frequency {
+            Frequency::Monthly | Frequency::Yearly => Some(next_date.day()),
+            Frequency::Daily | Frequency::Weekly => None,
+        });
+    let end_date = text(END_DATE)
+        .map(|date| parse_date(&date, date_format).map_err(field(END_DATE)))
+        .transpose()?;
+    if end_date.is_some_and(|end| end < next_date) {
+        return Err((END_DATE, eyre!("end date is before the next date")));
+    }
+    let debit_acc_id = parse_account(form.value(DEBIT), accounts).map_err(field(DEBIT))?;
+    let credit_acc_id = parse_account(form.value(CREDIT), accounts).map_err(field(CREDIT))?;
+    if debit_acc_id == credit_acc_id {
+        return Err((CREDIT, eyre!("debit and credit accounts must be different")));
+    }
+    let exponent = account_exponents(accounts)[&debit_acc_id];
+    let amount = parse_money(form.value(AMOUNT), exponent).map_err(field(AMOUNT))?;
+
+    Ok(Schedule {
+        schedule_id: None,
+        frequency,
+        day_of_month,
+        next_date,
+        end_date,
+        paused: false,
+        credit_acc_id,
+        debit_acc_id,
+        amount,
+        category: text(CATEGORY),
+        description: text(DESCRIPTION),
+    })
+}
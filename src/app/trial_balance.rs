@@ -0,0 +1,162 @@
+use chrono::Local;
+use color_eyre::eyre;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Modifier, Style, Stylize},
+    text::{Line, Text},
+    widgets::{Block, BorderType, Borders, Cell, Clear, Row, Table, Widget},
+};
+
+use super::App;
+use crate::{currency::exponent, parsing::format_money, types::NavEvent};
+
+const SCREEN_TITLE: &str = "Trial balance";
+const SCREEN_TITLE_BOTTOM: &str = " ↑ ↓ scroll ";
+const TABLE_HEADER: [&str; 4] = ["Account", "Debits", "Credits", "Balance"];
+
+impl App {
+    pub(super) fn draw_trial_balance_screen(&self, area: Rect, buf: &mut Buffer) {
+        let theme = self.theme();
+        let status = match self.trial_balance.debits() - self.trial_balance.credits() {
+            0 => Line::from(" Balanced "),
+            difference => Line::from(format!(
+                " Out of balance by {} ",
+                self.format_base(difference)
+            ))
+            .style(
+                Style::default()
+                    .fg(theme.error_fg)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        };
+        let block = Block::default()
+            .title(format!("{SCREEN_TITLE} in {}", self.config.currency))
+            .title(status.right_aligned())
+            .title_bottom(Line::from(SCREEN_TITLE_BOTTOM).right_aligned())
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded);
+
+        let rows = self.trial_balance_rows();
+        let offset = self.trial_balance_offset.min(rows.len().saturating_sub(1));
+        let header = Row::new(
+            TABLE_HEADER
+                .iter()
+                .enumerate()
+                .map(|(col, &title)| match col {
+                    0 => Text::from(title),
+                    _ => Text::from(title).right_aligned(),
+                }),
+        )
+        .style(Style::default().add_modifier(Modifier::REVERSED));
+        let table = Table::new(
+            rows.into_iter().skip(offset),
+            [
+                Constraint::Fill(1),
+                Constraint::Min(14),
+                Constraint::Min(14),
+                Constraint::Min(14),
+            ],
+        )
+        .header(header)
+        .column_spacing(1)
+        .block(block);
+
+        Clear.render(area, buf);
+        Widget::render(table, area, buf);
+    }
+
+    /// Accounts grouped by type, each group followed by its totals, then the rounding
+    /// of converted amounts and the grand total
+    fn trial_balance_rows(&self) -> Vec<Row<'_>> {
+        let theme = self.theme();
+        let amount = |value: i64| Cell::from(Text::from(self.format_base(value)).right_aligned());
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let mut rows = vec![];
+        for group in self
+            .trial_balance
+            .lines
+            .chunk_by(|a, b| a.account.acc_type == b.account.acc_type)
+        {
+            let acc_type = group[0].account.acc_type.as_str();
+            rows.push(Row::new([Cell::from(acc_type)]).style(bold));
+            for (idx, line) in group.iter().enumerate() {
+                let row = Row::new([
+                    Cell::from(format!("  {}", line.account.name)),
+                    amount(line.debits),
+                    amount(line.credits),
+                    amount(line.balance()),
+                ]);
+                rows.push(match idx % 2 {
+                    0 => row,
+                    _ => row.bg(theme.alt_row_bg),
+                });
+            }
+            let debits = group.iter().map(|line| line.debits).sum();
+            let credits = group.iter().map(|line| line.credits).sum();
+            rows.push(
+                Row::new([
+                    Cell::from(format!("  Total {acc_type}")),
+                    amount(debits),
+                    amount(credits),
+                    amount(debits - credits),
+                ])
+                .style(bold),
+            );
+        }
+        let rounding = self.trial_balance.rounding;
+        if rounding != 0 {
+            rows.push(Row::new([
+                Cell::from("Exchange rate rounding"),
+                amount(rounding.max(0)),
+                amount((-rounding).max(0)),
+                amount(rounding),
+            ]));
+        }
+        let debits = self.trial_balance.debits();
+        let credits = self.trial_balance.credits();
+        rows.push(
+            Row::new([
+                Cell::from("Total"),
+                amount(debits),
+                amount(credits),
+                amount(debits - credits),
+            ])
+            .style(bold),
+        );
+        rows
+    }
+
+    /// Formats an amount in the configured currency
+    fn format_base(&self, amount: i64) -> String {
+        format_money(amount, exponent(&self.config.currency))
+    }
+
+    pub(super) fn sync_trial_balance_screen(&mut self) -> eyre::Result<()> {
+        let today = Local::now().date_naive();
+        self.trial_balance = self
+            .service
+            .get_trial_balance(&self.config.currency, today)?;
+        Ok(())
+    }
+
+    pub(super) fn input_trial_balance_screen(&mut self, key_event: &KeyEvent) {
+        if let KeyCode::Char('q' | 'Q') = key_event.code {
+            self.exit()
+        }
+    }
+
+    pub(super) fn nav_trial_balance_screen(&mut self, event: NavEvent) {
+        match event {
+            NavEvent::Up => {
+                self.trial_balance_offset = self.trial_balance_offset.saturating_sub(1);
+            }
+            NavEvent::Down => {
+                let last = self.trial_balance_rows().len().saturating_sub(1);
+                self.trial_balance_offset = (self.trial_balance_offset + 1).min(last);
+            }
+            _ => {}
+        }
+    }
+}
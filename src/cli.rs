@@ -0,0 +1,524 @@
+use std::{io::Write, path::PathBuf};
+
+use chrono::{Local, NaiveDate, NaiveTime};
+use clap::{Args, Parser, Subcommand};
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    config::Config,
+    currency::{account_exponents, exponent, parse_currency, DEFAULT_EXPONENT},
+    export, import,
+    parsing::{format_money, local_datetime, parse_account, parse_money},
+    service::BudgetService,
+    types::{Transaction, TrnFilter, TrnStatus},
+};
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Budget database file, overrides the config file
+    #[arg(long, global = true, value_name = "PATH")]
+    pub db: Option<PathBuf>,
+
+    /// Config file, $XDG_CONFIG_HOME/mybudget/config.toml by default
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Start the terminal UI, the default when no command is given
+    Tui,
+
+    /// Add a transaction
+    Add {
+        /// Transaction date, YYYY-MM-DD, today by default
+        #[arg(long)]
+        date: Option<NaiveDate>,
+
+        /// Account receiving the money, id or name
+        #[arg(long)]
+        debit: String,
+
+        /// Account the money comes from, id or name, the default account if omitted
+        #[arg(long)]
+        credit: Option<String>,
+
+        /// Amount like 12.50
+        #[arg(long, allow_hyphen_values = true)]
+        amount: String,
+
+        #[arg(long)]
+        category: Option<String>,
+
+        /// Add the category when it does not exist yet
+        #[arg(long)]
+        new_category: bool,
+
+        #[arg(long)]
+        description: Option<String>,
+    },
+
+    /// Print transactions
+    List {
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+
+    /// Delete transactions by id
+    Delete {
+        #[arg(required = true)]
+        ids: Vec<isize>,
+    },
+
+    /// Import a CSV, OFX, QFX or QIF bank statement into an account
+    Import {
+        file: PathBuf,
+
+        /// Account of the statement, id or name, the default account if omitted
+        #[arg(long)]
+        account: Option<String>,
+
+        /// Column mapping profile of CSV statements
+        #[arg(long, default_value = "default")]
+        profile: String,
+
+        /// Save records that look like stored transactions too
+        #[arg(long)]
+        keep_duplicates: bool,
+    },
+
+    /// Import exchange rates from a CSV file with a `date,currency,quote,rate` header
+    ImportRates { file: PathBuf },
+
+    /// Export transactions to a .csv, .json or .qif file, QIF needs an account
+    Export {
+        file: PathBuf,
+
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+
+    /// Print account balances
+    Report,
+
+    /// Print debits and credits of every account grouped by account type
+    TrialBalance {
+        /// Currency to convert the amounts to, the configured currency by default
+        #[arg(long)]
+        currency: Option<String>,
+
+        /// Date of the exchange rates, YYYY-MM-DD, today by default
+        #[arg(long)]
+        date: Option<NaiveDate>,
+    },
+
+    /// Add the transactions of scheduled occurrences due by today,
+    /// the terminal UI does the same on start
+    RunSchedules,
+}
+
+#[derive(Debug, Args)]
+pub struct FilterArgs {
+    /// First date, YYYY-MM-DD
+    #[arg(long)]
+    from: Option<NaiveDate>,
+
+    /// Last date inclusive, YYYY-MM-DD
+    #[arg(long)]
+    to: Option<NaiveDate>,
+
+    /// Only transactions of this account, id or name
+    #[arg(long)]
+    account: Option<String>,
+}
+
+impl FilterArgs {
+    fn to_filter(&self, service: &BudgetService) -> Result<TrnFilter> {
+        let account_id = match &self.account {
+            Some(account) => Some(parse_account(account, &service.get_accounts()?)?),
+            None => None,
+        };
+
+        Ok(TrnFilter {
+            from: self.from,
+            to: self.to,
+            account_id,
+            ..Default::default()
+        })
+    }
+}
+
+/// Executes a non-interactive command, writing its output to `out`
+pub fn run(
+    service: &mut BudgetService,
+    config: &Config,
+    command: Command,
+    out: &mut impl Write,
+) -> Result<()> {
+    let or_default_account = |account: Option<String>, arg: &str| {
+        account
+            .or_else(|| config.default_account.clone())
+            .ok_or_else(|| eyre!("--{arg} is required when no default account is configured"))
+    };
+
+    match command {
+        Command::Tui => {}
+        Command::Add {
+            date,
+            debit,
+            credit,
+            amount,
+            category,
+            new_category,
+            description,
+        } => {
+            let accounts = service.get_accounts()?;
+            let date = date.unwrap_or_else(|| Local::now().date_naive());
+            let debit_acc_id = parse_account(&debit, &accounts)?;
+            let trn = Transaction {
+                transaction_id: None,
+                credit_acc_id: Some(parse_account(
+                    &or_default_account(credit, "credit")?,
+                    &accounts,
+                )?),
+                debit_acc_id: Some(debit_acc_id),
+                timestamp: local_datetime(date, NaiveTime::MIN)?,
+                category,
+                amount: parse_money(&amount, account_exponents(&accounts)[&debit_acc_id])?,
+                description,
+                splits: vec![],
+                status: TrnStatus::Uncleared,
+                external_id: None,
+            };
+            let trn_id = match new_category {
+                true => service.put_trns_with_categories(&[trn])?[0],
+                false => service.put_trn(&trn)?,
+            };
+            writeln!(out, "Added transaction {trn_id}")?;
+        }
+        Command::List { filter } => {
+            let exponents = account_exponents(&service.get_accounts()?);
+            for trn in service.find_trns(&filter.to_filter(service)?)? {
+                let exponent = trn
+                    .debit_acc_id
+                    .and_then(|id| exponents.get(&id).copied())
+                    .unwrap_or(DEFAULT_EXPONENT);
+                let account = |id: Option<u8>| id.map(|id| id.to_string()).unwrap_or_default();
+                writeln!(
+                    out,
+                    "{:>6}  {}  {:>4} <- {:<4} {:>14}  {}  {}",
+                    trn.transaction_id.unwrap_or_default(),
+                    trn.timestamp.format(&config.date_format),
+                    account(trn.debit_acc_id),
+                    account(trn.credit_acc_id),
+                    format_money(trn.amount, exponent),
+                    trn.category.unwrap_or_default(),
+                    trn.description.unwrap_or_default(),
+                )?;
+            }
+        }
+        Command::Delete { ids } => {
+            let deleted = service.del_trns(&ids)?;
+            writeln!(out, "Deleted {deleted} transactions")?;
+        }
+        Command::Import {
+            file,
+            account,
+            profile,
+            keep_duplicates,
+        } => {
+            let account = or_default_account(account, "account")?;
+            let account_id = parse_account(&account, &service.get_accounts()?)?;
+            let mut report = import::import_file(service, &file, &profile, account_id)?;
+            for error in &report.errors {
+                writeln!(out, "{}: {error}", file.display())?;
+            }
+            if keep_duplicates {
+                let duplicates: Vec<Transaction> = report
+                    .duplicates
+                    .drain(..)
+                    .map(|duplicate| duplicate.candidate)
+                    .collect();
+                service.put_trns_with_categories(&duplicates)?;
+                report.imported += duplicates.len();
+            }
+            for duplicate in &report.duplicates {
+                writeln!(
+                    out,
+                    "{}: {} {} looks like transaction {}",
+                    file.display(),
+                    duplicate.candidate.timestamp.format("%Y-%m-%d"),
+                    duplicate
+                        .candidate
+                        .description
+                        .as_deref()
+                        .unwrap_or_default(),
+                    duplicate.existing.transaction_id.unwrap_or_default()
+                )?;
+            }
+            writeln!(out, "{}: {report}", file.display())?;
+        }
+        Command::ImportRates { file } => {
+            let count = import::rates::import_file(service, &file)?;
+            writeln!(out, "{}: imported {count} exchange rates", file.display())?;
+        }
+        Command::Export { file, filter } => {
+            let count = export::export_file(service, &file, &filter.to_filter(service)?)?;
+            writeln!(out, "Exported {count} transactions to {}", file.display())?;
+        }
+        Command::Report => {
+            let balances = service.get_balances()?;
+            for account in service.get_accounts()? {
+                let balance = account
+                    .account_id
+                    .and_then(|id| balances.get(&id))
+                    .copied()
+                    .unwrap_or(account.opening_balance);
+                writeln!(
+                    out,
+                    "{:>4}  {:<24} {:<10} {:>14} {}",
+                    account.account_id.unwrap_or_default(),
+                    account.name,
+                    account.acc_type.as_str(),
+                    format_money(balance, exponent(&account.currency)),
+                    account.currency,
+                )?;
+            }
+        }
+        Command::TrialBalance { currency, date } => {
+            let base = parse_currency(currency.as_deref().unwrap_or(&config.currency))?;
+            let date = date.unwrap_or_else(|| Local::now().date_naive());
+            let balance = service.get_trial_balance(&base, date)?;
+            let format = |amount| format_money(amount, exponent(&base));
+            let row = |out: &mut dyn Write, label: &str, debits: i64, credits: i64| {
+                writeln!(
+                    out,
+                    "{label:<30} {:>14} {:>14}",
+                    format(debits),
+                    format(credits)
+                )
+            };
+
+            writeln!(out, "Amounts in {base} at the rates of {date}")?;
+            for group in balance
+                .lines
+                .chunk_by(|a, b| a.account.acc_type == b.account.acc_type)
+            {
+                let acc_type = group[0].account.acc_type.as_str();
+                writeln!(out, "{acc_type}")?;
+                for line in group {
+                    row(
+                        out,
+                        &format!("  {}", line.account.name),
+                        line.debits,
+                        line.credits,
+                    )?;
+                }
+                let debits = group.iter().map(|line| line.debits).sum();
+                let credits = group.iter().map(|line| line.credits).sum();
+                row(out, &format!("  Total {acc_type}"), debits, credits)?;
+            }
+
+            if balance.rounding != 0 {
+                row(
+                    out,
+                    "Exchange rate rounding",
+                    balance.rounding.max(0),
+                    (-balance.rounding).max(0),
+                )?;
+            }
+            let (debits, credits) = (balance.debits(), balance.credits());
+            row(out, "Total", debits, credits)?;
+            if debits != credits {
+                writeln!(out, "Out of balance by {}", format(debits - credits))?;
+            }
+        }
+        Command::RunSchedules => {
+            let count = service.run_schedules(Local::now().date_naive())?;
+            writeln!(out, "Added {count} scheduled transactions")?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{Account, AccountType, Frequency, Schedule};
+    use clap::CommandFactory;
+
+    fn service() -> Result<BudgetService> {
+        let mut service = BudgetService::new(":memory:");
+        for (name, acc_type) in [("Cash", AccountType::Asset), ("Food", AccountType::Expense)] {
+            service.put_account(&Account {
+                account_id: None,
+                name: String::from(name),
+                acc_type,
+                currency: String::from("USD"),
+                opening_balance: 0,
+            })?;
+        }
+        Ok(service)
+    }
+
+    fn exec(service: &mut BudgetService, args: &[&str]) -> Result<String> {
+        exec_with(service, &Config::default(), args)
+    }
+
+    fn exec_with(service: &mut BudgetService, config: &Config, args: &[&str]) -> Result<String> {
+        let cli = Cli::try_parse_from(["mybudget"].iter().chain(args))?;
+        let mut out = vec![];
+        run(
+            service,
+            config,
+            cli.command.unwrap_or(Command::Tui),
+            &mut out,
+        )?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    #[test]
+    fn verify_cli() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn add_list_delete() -> Result<()> {
+        let mut service = service()?;
+
+        let args = [
+            "add",
+            "--date",
+            "2025-01-02",
+            "--debit",
+            "food",
+            "--credit",
+            "1",
+            "--amount",
+            "12.5",
+            "--category",
+            "Lunch",
+        ];
+        assert!(exec(&mut service, &args).is_err());
+        let added = exec(&mut service, &[&args[..], &["--new-category"]].concat())?;
+        assert_eq!(added, "Added transaction 1\n");
+
+        let listed = exec(&mut service, &["list", "--account", "Food"])?;
+        assert!(listed.contains("2025-01-02"));
+        assert!(listed.contains("12.50"));
+        assert!(listed.contains("Lunch"));
+        assert_eq!(exec(&mut service, &["list", "--from", "2025-01-03"])?, "");
+
+        let report = exec(&mut service, &["report"])?;
+        assert!(report.contains("-12.50"));
+
+        assert_eq!(
+            exec(&mut service, &["delete", "1", "2"])?,
+            "Deleted 1 transactions\n"
+        );
+        assert_eq!(exec(&mut service, &["list"])?, "");
+        Ok(())
+    }
+
+    #[test]
+    fn trial_balance_report() -> Result<()> {
+        let mut service = service()?;
+        let args = [
+            "add", "--debit", "Food", "--credit", "Cash", "--amount", "7.25",
+        ];
+        exec(&mut service, &args)?;
+
+        let report = exec(&mut service, &["trial-balance", "--date", "2025-01-31"])?;
+        let lines: Vec<Vec<&str>> = report
+            .lines()
+            .map(|line| line.split_whitespace().collect())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                vec![
+                    "Amounts",
+                    "in",
+                    "USD",
+                    "at",
+                    "the",
+                    "rates",
+                    "of",
+                    "2025-01-31"
+                ],
+                vec!["Asset"],
+                vec!["Cash", "0.00", "7.25"],
+                vec!["Total", "Asset", "0.00", "7.25"],
+                vec!["Expense"],
+                vec!["Food", "7.25", "0.00"],
+                vec!["Total", "Expense", "7.25", "0.00"],
+                vec!["Total", "7.25", "7.25"],
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reject_unknown_account() -> Result<()> {
+        let mut service = service()?;
+        let args = ["add", "--debit", "Rent", "--credit", "1", "--amount", "-5"];
+
+        assert!(exec(&mut service, &args).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn use_configured_defaults() -> Result<()> {
+        let mut service = service()?;
+        let config = Config {
+            default_account: Some(String::from("Cash")),
+            date_format: String::from("%d.%m.%Y"),
+            ..Config::default()
+        };
+        let args = [
+            "add",
+            "--date",
+            "2025-01-02",
+            "--debit",
+            "2",
+            "--amount",
+            "3",
+        ];
+
+        assert!(exec(&mut service, &args).is_err());
+        exec_with(&mut service, &config, &args)?;
+
+        let listed = exec_with(&mut service, &config, &["list"])?;
+        assert!(listed.contains("02.01.2025"));
+        assert!(listed.contains("2 <- 1"));
+        Ok(())
+    }
+
+    #[test]
+    fn schedules_run_on_request() -> Result<()> {
+        let mut service = service()?;
+        service.put_schedule(&Schedule {
+            schedule_id: None,
+            frequency: Frequency::Monthly,
+            day_of_month: None,
+            next_date: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            end_date: Some(NaiveDate::from_ymd_opt(2025, 2, 1).unwrap()),
+            paused: false,
+            credit_acc_id: 1,
+            debit_acc_id: 2,
+            amount: 100,
+            category: None,
+            description: None,
+        })?;
+
+        assert_eq!(exec(&mut service, &["list"])?, "");
+        let added = exec(&mut service, &["run-schedules"])?;
+        assert_eq!(added, "Added 2 scheduled transactions\n");
+        assert_eq!(exec(&mut service, &["list"])?.lines().count(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use chrono::format::{Item, StrftimeItems};
+use color_eyre::eyre::{eyre, Result};
+use serde::Deserialize;
+
+use crate::widgets::theme::ThemeName;
+
+const APP_NAME: &str = "mybudget";
+const CONFIG_FILE: &str = "config.toml";
+const DB_FILE: &str = "budget.db";
+
+/// Environment variables overriding the config file
+const ENV_CONFIG: &str = "MYBUDGET_CONFIG";
+const ENV_DB: &str = "MYBUDGET_DB";
+const ENV_DEFAULT_ACCOUNT: &str = "MYBUDGET_DEFAULT_ACCOUNT";
+const ENV_CURRENCY: &str = "MYBUDGET_CURRENCY";
+const ENV_DATE_FORMAT: &str = "MYBUDGET_DATE_FORMAT";
+const ENV_THEME: &str = "MYBUDGET_THEME";
+
+/// User settings. Every value comes from, in order of priority: command line,
+/// `MYBUDGET_*` environment variables, config file, built-in defaults.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Budget database file, kept in the user data directory when not set,
+    /// so it does not depend on where the application is started from.
+    /// An existing `budget.db` in the working directory is used instead.
+    pub db: PathBuf,
+    /// Account, id or name, preselected in forms
+    pub default_account: Option<String>,
+    /// Currency of new accounts
+    pub currency: String,
+    /// `chrono` format used to show and enter dates
+    pub date_format: String,
+    pub theme: ThemeName,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            db: PathBuf::new(),
+            default_account: None,
+            currency: String::from("USD"),
+            date_format: String::from("%Y-%m-%d"),
+            theme: ThemeName::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads config from `path`, or from the default location when it is not given.
+    /// Missing file at the default location is not an error. `is_file` tells
+    /// whether a database file is kept in the working directory.
+    pub fn load(
+        path: Option<&Path>,
+        env: impl Fn(&str) -> Option<String>,
+        is_file: impl Fn(&Path) -> bool,
+    ) -> Result<Self> {
+        let explicit = path
+            .map(Path::to_path_buf)
+            .or_else(|| env(ENV_CONFIG).map(PathBuf::from));
+        let path = explicit
+            .clone()
+            .or_else(|| Some(config_home(&env)?.join(APP_NAME).join(CONFIG_FILE)));
+
+        let mut config = match path {
+            Some(path) => match fs::read_to_string(&path) {
+                Ok(text) => toml::from_str(&text)
+                    .map_err(|err| eyre!("invalid config {}: {err}", path.display()))?,
+                Err(err) if err.kind() == ErrorKind::NotFound && explicit.is_none() => {
+                    Self::default()
+                }
+                Err(err) => return Err(eyre!("can not read {}: {err}", path.display())),
+            },
+            None => Self::default(),
+        };
+
+        if config.db.as_os_str().is_empty() {
+            config.db = default_db(&env, is_file);
+        }
+
+        if let Some(db) = env(ENV_DB) {
+            config.db = PathBuf::from(db);
+        }
+        if let Some(account) = env(ENV_DEFAULT_ACCOUNT) {
+            config.default_account = Some(account);
+        }
+        if let Some(currency) = env(ENV_CURRENCY) {
+            config.currency = currency;
+        }
+        if let Some(date_format) = env(ENV_DATE_FORMAT) {
+            config.date_format = date_format;
+        }
+        if let Some(theme) = env(ENV_THEME) {
+            config.theme = theme.parse()?;
+        }
+        check_date_format(&config.date_format)?;
+
+        Ok(config)
+    }
+
+    /// Creates the directory of the database file if needed
+    pub fn prepare_db_dir(&self) -> Result<()> {
+        match self.db.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => fs::create_dir_all(dir)
+                .map_err(|err| eyre!("can not create {}: {err}", dir.display())),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Database in the user data directory, or the one in the working directory
+/// when it exists, as earlier versions kept it there
+fn default_db(env: impl Fn(&str) -> Option<String>, is_file: impl Fn(&Path) -> bool) -> PathBuf {
+    let legacy = Path::new(DB_FILE);
+    if is_file(legacy) {
+        return legacy.to_path_buf();
+    }
+    data_home(env)
+        .map(|dir| dir.join(APP_NAME).join(DB_FILE))
+        .unwrap_or_else(|| PathBuf::from(DB_FILE))
+}
+
+/// Rejects formats `chrono` can not show dates with, instead of panicking later
+fn check_date_format(format: &str) -> Result<()> {
+    match StrftimeItems::new(format).any(|item| item == Item::Error) {
+        true => Err(eyre!("invalid date_format '{format}'")),
+        false => Ok(()),
+    }
+}
+
+fn config_home(env: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    xdg_dir(env, "XDG_CONFIG_HOME", ".config")
+}
+
+fn data_home(env: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    xdg_dir(env, "XDG_DATA_HOME", ".local/share")
+}
+
+fn xdg_dir(env: impl Fn(&str) -> Option<String>, var: &str, fallback: &str) -> Option<PathBuf> {
+    env(var)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env("HOME").map(|home| Path::new(&home).join(fallback)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    fn no_files(_: &Path) -> bool {
+        false
+    }
+
+    fn temp_config(name: &str, text: &str) -> Result<PathBuf> {
+        let dir = std::env::temp_dir().join(format!("mybudget-test-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(name);
+        fs::write(&path, text)?;
+        Ok(path)
+    }
+
+    #[test]
+    fn defaults_without_file() -> Result<()> {
+        let config = Config::load(None, env(&[("HOME", "/nonexistent")]), no_files)?;
+
+        assert_eq!(
+            config.db,
+            PathBuf::from("/nonexistent/.local/share/mybudget/budget.db")
+        );
+        assert_eq!(config.currency, "USD");
+        Ok(())
+    }
+
+    #[test]
+    fn file_then_env_override() -> Result<()> {
+        let path = temp_config(
+            "override.toml",
+            "db = \"/tmp/ledger.db\"\ncurrency = \"EUR\"\ntheme = \"light\"\n",
+        )?;
+
+        let config = Config::load(Some(&path), env(&[]), no_files)?;
+        assert_eq!(config.db, PathBuf::from("/tmp/ledger.db"));
+        assert_eq!(config.currency, "EUR");
+        assert_eq!(config.theme, ThemeName::Light);
+        assert_eq!(config.date_format, "%Y-%m-%d");
+
+        let config = Config::load(
+            Some(&path),
+            env(&[(ENV_DB, "other.db"), (ENV_DATE_FORMAT, "%d.%m.%Y")]),
+            no_files,
+        )?;
+        assert_eq!(config.db, PathBuf::from("other.db"));
+        assert_eq!(config.date_format, "%d.%m.%Y");
+        Ok(())
+    }
+
+    #[test]
+    fn config_location_from_env() -> Result<()> {
+        let path = temp_config("from_env.toml", "default_account = \"Cash\"\n")?;
+        let path = path.to_str().unwrap();
+
+        let config = Config::load(None, env(&[(ENV_CONFIG, path)]), no_files)?;
+        assert_eq!(config.default_account.as_deref(), Some("Cash"));
+        assert_eq!(config.db, PathBuf::from(DB_FILE));
+        Ok(())
+    }
+
+    #[test]
+    fn reject_bad_config() -> Result<()> {
+        let path = temp_config("bad.toml", "colour = \"red\"\n")?;
+
+        assert!(Config::load(Some(&path), env(&[]), no_files).is_err());
+        assert!(Config::load(Some(Path::new("/nonexistent.toml")), env(&[]), no_files).is_err());
+
+        let path = temp_config("bad_date.toml", "date_format = \"%d.%m.%\"\n")?;
+        assert!(Config::load(Some(&path), env(&[]), no_files).is_err());
+        assert!(Config::load(None, env(&[(ENV_DATE_FORMAT, "%Q")]), no_files).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn keep_database_of_working_directory() -> Result<()> {
+        let env = env(&[("HOME", "/nonexistent")]);
+
+        let config = Config::load(None, &env, |path| path == Path::new(DB_FILE))?;
+        assert_eq!(config.db, PathBuf::from(DB_FILE));
+        assert_eq!(
+            Config::load(None, &env, no_files)?.db,
+            PathBuf::from("/nonexistent/.local/share/mybudget/budget.db")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::{eyre, Result};
+
+use crate::types::Account;
+
+/// ISO 4217 codes with the number of digits after the decimal point of their minor unit
+const CURRENCIES: [(&str, u32); 63] = [
+    ("AED", 2),
+    ("ARS", 2),
+    ("AUD", 2),
+    ("BGN", 2),
+    ("BHD", 3),
+    ("BRL", 2),
+    ("BYN", 2),
+    ("CAD", 2),
+    ("CHF", 2),
+    ("CLP", 0),
+    ("CNY", 2),
+    ("COP", 2),
+    ("CZK", 2),
+    ("DKK", 2),
+    ("EGP", 2),
+    ("EUR", 2),
+    ("GBP", 2),
+    ("GEL", 2),
+    ("HKD", 2),
+    ("HUF", 2),
+    ("IDR", 2),
+    ("ILS", 2),
+    ("INR", 2),
+    ("IQD", 3),
+    ("ISK", 0),
+    ("JOD", 3),
+    ("JPY", 0),
+    ("KES", 2),
+    ("KRW", 0),
+    ("KWD", 3),
+    ("KZT", 2),
+    ("LYD", 3),
+    ("MAD", 2),
+    ("MDL", 2),
+    ("MXN", 2),
+    ("MYR", 2),
+    ("NGN", 2),
+    ("NOK", 2),
+    ("NZD", 2),
+    ("OMR", 3),
+    ("PEN", 2),
+    ("PHP", 2),
+    ("PKR", 2),
+    ("PLN", 2),
+    ("PYG", 0),
+    ("QAR", 2),
+    ("RON", 2),
+    ("RSD", 2),
+    ("RUB", 2),
+    ("SAR", 2),
+    ("SEK", 2),
+    ("SGD", 2),
+    ("THB", 2),
+    ("TND", 3),
+    ("TRY", 2),
+    ("TWD", 2),
+    ("UAH", 2),
+    ("UGX", 0),
+    ("USD", 2),
+    ("UYU", 2),
+    ("VND", 0),
+    ("XOF", 0),
+    ("ZAR", 2),
+];
+
+/// Minor unit digits assumed for amounts whose currency is not known
+pub const DEFAULT_EXPONENT: u32 = 2;
+
+/// Number of digits after the decimal point in amounts of the currency
+pub fn minor_units(code: &str) -> Option<u32> {
+    CURRENCIES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(code))
+        .map(|&(_, exponent)| exponent)
+}
+
+/// Like `minor_units`, falling back to cents for unknown currencies
+pub fn exponent(code: &str) -> u32 {
+    minor_units(code).unwrap_or(DEFAULT_EXPONENT)
+}
+
+/// Normalized ISO 4217 code, an error for unknown currencies
+pub fn parse_currency(value: &str) -> Result<String> {
+    let code = value.trim().to_ascii_uppercase();
+    match minor_units(&code) {
+        Some(_) => Ok(code),
+        None => Err(eyre!(
+            "unknown currency '{}', expected ISO 4217 code",
+            value.trim()
+        )),
+    }
+}
+
+/// Minor unit digits of the currency of every account, by account id
+pub fn account_exponents(accounts: &[Account]) -> HashMap<u8, u32> {
+    accounts
+        .iter()
+        .filter_map(|account| Some((account.account_id?, exponent(&account.currency))))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn known_currencies() {
+        assert_eq!(minor_units("usd"), Some(2));
+        assert_eq!(minor_units("JPY"), Some(0));
+        assert_eq!(minor_units("KWD"), Some(3));
+        assert_eq!(minor_units("XXX"), None);
+        assert_eq!(exponent("XXX"), DEFAULT_EXPONENT);
+
+        assert_eq!(parse_currency(" eur ").ok().as_deref(), Some("EUR"));
+        assert!(parse_currency("EU").is_err());
+        assert!(CURRENCIES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+}
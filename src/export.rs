@@ -0,0 +1,325 @@
+use std::{fs::File, io::Write, path::Path};
+
+use color_eyre::eyre::{eyre, Result};
+use serde::Serialize;
+
+use crate::{
+    currency::{exponent, DEFAULT_EXPONENT},
+    import::qif,
+    parsing::format_money,
+    service::BudgetService,
+    types::{Account, Transaction, TrnFilter, TrnStatus},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    /// Quicken file of a single account
+    Qif,
+}
+
+/// Exported transaction. Field names are part of the file format, do not rename them.
+#[derive(Debug, Serialize)]
+struct ExportRecord<'a> {
+    transaction_id: Option<isize>,
+    timestamp: String,
+    debit_account_id: Option<u8>,
+    credit_account_id: Option<u8>,
+    amount: String,
+    /// Currency of the accounts, the amount has as many decimals as it has
+    currency: Option<&'a str>,
+    category: Option<&'a str>,
+    description: Option<&'a str>,
+    status: TrnStatus,
+    /// Id the bank gave the transaction in an imported statement
+    external_id: Option<&'a str>,
+    splits: ExportSplits<'a>,
+}
+
+/// Splits as a list in JSON, and in CSV as text like `Food 12.50; Household 3.00`,
+/// the way the splits field of the transaction form takes them
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum ExportSplits<'a> {
+    List(Vec<ExportSplit<'a>>),
+    Text(String),
+}
+
+#[derive(Debug, Serialize)]
+struct ExportSplit<'a> {
+    category: Option<&'a str>,
+    amount: String,
+    description: Option<&'a str>,
+}
+
+impl ExportFormat {
+    /// Picks format by file extension
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Ok(ExportFormat::Csv),
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Ok(ExportFormat::Json),
+            Some(ext) if ext.eq_ignore_ascii_case("qif") => Ok(ExportFormat::Qif),
+            _ => Err(eyre!(
+                "can not guess export format of {}, use .csv, .json or .qif",
+                path.display()
+            )),
+        }
+    }
+}
+
+impl<'a> ExportRecord<'a> {
+    fn new(trn: &'a Transaction, accounts: &'a [Account]) -> Self {
+        let currency = trn
+            .debit_acc_id
+            .or(trn.credit_acc_id)
+            .and_then(|id| accounts.iter().find(|acc| acc.account_id == Some(id)))
+            .map(|acc| acc.currency.as_str());
+        let exponent = currency.map_or(DEFAULT_EXPONENT, exponent);
+        let splits = trn
+            .splits
+            .iter()
+            .map(|split| ExportSplit {
+                category: split.category.as_deref(),
+                amount: format_money(split.amount, exponent),
+                description: split.description.as_deref(),
+            })
+            .collect();
+        Self {
+            transaction_id: trn.transaction_id,
+            timestamp: trn.timestamp.to_rfc3339(),
+            debit_account_id: trn.debit_acc_id,
+            credit_account_id: trn.credit_acc_id,
+            amount: format_money(trn.amount, exponent),
+            currency,
+            category: trn.category.as_deref(),
+            description: trn.description.as_deref(),
+            status: trn.status,
+            external_id: trn.external_id.as_deref(),
+            splits: ExportSplits::List(splits),
+        }
+    }
+
+    /// Record with splits in a single text field, as CSV has no lists
+    fn flat(mut self) -> Self {
+        if let ExportSplits::List(splits) = &self.splits {
+            let parts: Vec<String> = splits
+                .iter()
+                .map(|split| match split.category {
+                    Some(category) => format!("{category} {}", split.amount),
+                    None => split.amount.clone(),
+                })
+                .collect();
+            self.splits = ExportSplits::Text(parts.join("; "));
+        }
+        self
+    }
+}
+
+/// Writes transactions matching the filter to a file, returns number of exported transactions
+pub fn export_file(service: &BudgetService, path: &Path, filter: &TrnFilter) -> Result<usize> {
+    let format = ExportFormat::from_path(path)?;
+    if format == ExportFormat::Qif && filter.account_id.is_none() {
+        return Err(eyre!("choose an account to export a QIF file"));
+    }
+    let transactions = service.find_trns(filter)?;
+    let accounts = service.get_accounts()?;
+
+    let file =
+        File::create(path).map_err(|err| eyre!("can not create {}: {err}", path.display()))?;
+    match (format, filter.account_id) {
+        (ExportFormat::Qif, Some(account_id)) => {
+            qif::write(file, account_id, &accounts, &transactions)?
+        }
+        _ => write(file, format, &accounts, &transactions)?,
+    }
+    Ok(transactions.len())
+}
+
+/// Writes transactions with amounts in the currency of their accounts
+pub fn write(
+    writer: impl Write,
+    format: ExportFormat,
+    accounts: &[Account],
+    transactions: &[Transaction],
+) -> Result<()> {
+    let records = transactions
+        .iter()
+        .map(|trn| ExportRecord::new(trn, accounts));
+
+    match format {
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(writer);
+            for record in records {
+                writer.serialize(record.flat())?;
+            }
+            writer.flush()?;
+        }
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(writer, &records.collect::<Vec<_>>())?;
+        }
+        ExportFormat::Qif => return Err(eyre!("QIF files are written per account")),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{AccountType, Split};
+    use chrono::{Local, TimeZone};
+
+    fn accounts(currency: &str) -> Vec<Account> {
+        vec![Account {
+            account_id: Some(1),
+            name: String::from("Cash"),
+            acc_type: AccountType::Asset,
+            currency: currency.to_string(),
+            opening_balance: 0,
+        }]
+    }
+
+    fn transactions() -> Vec<Transaction> {
+        vec![Transaction {
+            transaction_id: Some(7),
+            credit_acc_id: Some(1),
+            debit_acc_id: None,
+            timestamp: Local.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap(),
+            category: Some(String::from("Food")),
+            amount: -1250,
+            description: None,
+            splits: vec![],
+            status: TrnStatus::Uncleared,
+            external_id: None,
+        }]
+    }
+
+    #[test]
+    fn csv_format() -> Result<()> {
+        let mut out = vec![];
+        write(
+            &mut out,
+            ExportFormat::Csv,
+            &accounts("USD"),
+            &transactions(),
+        )?;
+
+        let timestamp = transactions()[0].timestamp.to_rfc3339();
+        assert_eq!(
+            String::from_utf8(out)?,
+            format!(
+                "transaction_id,timestamp,debit_account_id,credit_account_id,amount,currency,\
+                 category,description,status,external_id,splits\n\
+                 7,{timestamp},,1,-12.50,USD,Food,,Uncleared,,\n"
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn json_format() -> Result<()> {
+        let mut out = vec![];
+        write(
+            &mut out,
+            ExportFormat::Json,
+            &accounts("USD"),
+            &transactions(),
+        )?;
+
+        let value: serde_json::Value = serde_json::from_slice(&out)?;
+        assert_eq!(value[0]["transaction_id"], 7);
+        assert_eq!(value[0]["amount"], "-12.50");
+        assert_eq!(value[0]["currency"], "USD");
+        assert_eq!(value[0]["debit_account_id"], serde_json::Value::Null);
+        assert_eq!(
+            value[0]["timestamp"],
+            transactions()[0].timestamp.to_rfc3339()
+        );
+        Ok(())
+    }
+
+    fn split_trn() -> Vec<Transaction> {
+        let split = |category: Option<&str>, amount, description: Option<&str>| Split {
+            category: category.map(String::from),
+            amount,
+            description: description.map(String::from),
+        };
+        vec![Transaction {
+            transaction_id: Some(8),
+            debit_acc_id: Some(1),
+            category: None,
+            amount: 1550,
+            splits: vec![
+                split(Some("Food"), 1250, Some("Bread")),
+                split(None, 300, None),
+            ],
+            status: TrnStatus::Cleared,
+            external_id: Some(String::from("FIT42")),
+            ..transactions().remove(0)
+        }]
+    }
+
+    #[test]
+    fn csv_splits() -> Result<()> {
+        let mut out = vec![];
+        write(&mut out, ExportFormat::Csv, &accounts("USD"), &split_trn())?;
+
+        let text = String::from_utf8(out)?;
+        let row = text.lines().nth(1).unwrap_or_default();
+        assert!(row.ends_with(",15.50,USD,,,Cleared,FIT42,Food 12.50; 3.00"));
+        Ok(())
+    }
+
+    #[test]
+    fn json_splits() -> Result<()> {
+        let mut out = vec![];
+        write(&mut out, ExportFormat::Json, &accounts("USD"), &split_trn())?;
+
+        let value: serde_json::Value = serde_json::from_slice(&out)?;
+        assert_eq!(value[0]["status"], "Cleared");
+        assert_eq!(value[0]["external_id"], "FIT42");
+        assert_eq!(
+            value[0]["splits"],
+            serde_json::json!([
+                {"category": "Food", "amount": "12.50", "description": "Bread"},
+                {"category": null, "amount": "3.00", "description": null},
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn amounts_in_account_currency() -> Result<()> {
+        for (currency, amount) in [("JPY", "-1250"), ("KWD", "-1.250")] {
+            let mut out = vec![];
+            write(
+                &mut out,
+                ExportFormat::Json,
+                &accounts(currency),
+                &transactions(),
+            )?;
+
+            let value: serde_json::Value = serde_json::from_slice(&out)?;
+            assert_eq!(value[0]["amount"], amount);
+            assert_eq!(value[0]["currency"], currency);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn format_from_extension() {
+        assert_eq!(
+            ExportFormat::from_path(Path::new("ledger.JSON")).ok(),
+            Some(ExportFormat::Json)
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a/b.csv")).ok(),
+            Some(ExportFormat::Csv)
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("bank.qif")).ok(),
+            Some(ExportFormat::Qif)
+        );
+        assert!(ExportFormat::from_path(Path::new("ledger")).is_err());
+    }
+}
@@ -0,0 +1,304 @@
+use std::{fs::File, io::Read, path::Path};
+
+use chrono::{NaiveDate, NaiveTime};
+use color_eyre::eyre::{eyre, Result};
+
+use super::{account_currency, save_statement, ImportReport, RowError};
+use crate::{
+    currency::exponent,
+    parsing::{local_datetime, parse_money},
+    service::BudgetService,
+    types::{CsvProfile, Transaction, TrnStatus},
+};
+
+/// Imports statement file into account using the named mapping profile.
+/// Rows that fail to parse are reported and skipped, likely duplicates of stored
+/// transactions are held back in the report, the rest are saved at once.
+pub fn import_file(
+    service: &mut BudgetService,
+    path: &Path,
+    profile: &str,
+    account_id: u8,
+) -> Result<ImportReport> {
+    let profile = service.get_import_profile(profile)?;
+    let file = File::open(path).map_err(|err| eyre!("can not open {}: {err}", path.display()))?;
+    import(service, file, &profile, account_id)
+}
+
+pub fn import(
+    service: &mut BudgetService,
+    reader: impl Read,
+    profile: &CsvProfile,
+    account_id: u8,
+) -> Result<ImportReport> {
+    let currency = account_currency(service, account_id)?;
+    let (transactions, errors) = read_statement(reader, profile, account_id, exponent(&currency))?;
+    save_statement(service, &currency, transactions, errors)
+}
+
+/// Parses statement records into transactions of the account: money coming in
+/// debits the account, money going out credits it. Amounts have `exponent`
+/// decimals, as in the currency of the account.
+pub fn read_statement(
+    reader: impl Read,
+    profile: &CsvProfile,
+    account_id: u8,
+    exponent: u32,
+) -> Result<(Vec<Transaction>, Vec<RowError>)> {
+    if !profile.delimiter.is_ascii() {
+        return Err(eyre!("delimiter '{}' is not supported", profile.delimiter));
+    }
+
+    let mut reader = ::csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(profile.delimiter as u8)
+        .from_reader(reader);
+
+    let mut transactions = vec![];
+    let mut errors = vec![];
+    for record in reader.records().skip(profile.skip_rows) {
+        let (line, result) = match record {
+            Ok(record) => (
+                record.position().map_or(0, |pos| pos.line()),
+                to_transaction(&record, profile, account_id, exponent),
+            ),
+            Err(err) => (err.position().map_or(0, |pos| pos.line()), Err(err.into())),
+        };
+
+        match result {
+            Ok(trn) => transactions.push(trn),
+            Err(report) => errors.push(RowError {
+                line,
+                message: report.to_string(),
+            }),
+        }
+    }
+
+    Ok((transactions, errors))
+}
+
+fn to_transaction(
+    record: &::csv::StringRecord,
+    profile: &CsvProfile,
+    account_id: u8,
+    exponent: u32,
+) -> Result<Transaction> {
+    let field = |column: usize| -> Result<&str> {
+        column
+            .checked_sub(1)
+            .and_then(|idx| record.get(idx))
+            .map(str::trim)
+            .ok_or_else(|| eyre!("column {column} is missing"))
+    };
+    let optional = |column: Option<usize>| -> Result<Option<String>> {
+        Ok(match column.map(field).transpose()? {
+            None | Some("") => None,
+            Some(value) => Some(value.to_string()),
+        })
+    };
+    let amount = |column: usize| -> Result<i64> {
+        match field(column)? {
+            "" => Ok(0),
+            value => parse_statement_amount(value, profile.decimal_separator, exponent),
+        }
+    };
+
+    let date = field(profile.date_column)?;
+    let date = NaiveDate::parse_from_str(date, &profile.date_format)
+        .map_err(|_| eyre!("invalid date '{date}', expected {}", profile.date_format))?;
+
+    let out_of_range = || eyre!("amount is out of range");
+    let mut net = amount(profile.amount_column)?;
+    if let Some(outflow) = profile.outflow_column {
+        net = amount(outflow)?
+            .checked_abs()
+            .and_then(|outflow| net.checked_sub(outflow))
+            .ok_or_else(out_of_range)?;
+    }
+    if profile.invert_sign {
+        net = net.checked_neg().ok_or_else(out_of_range)?;
+    }
+    let (debit_acc_id, credit_acc_id) = match net {
+        ..0 => (None, Some(account_id)),
+        _ => (Some(account_id), None),
+    };
+
+    Ok(Transaction {
+        transaction_id: None,
+        credit_acc_id,
+        debit_acc_id,
+        timestamp: local_datetime(date, NaiveTime::MIN)?,
+        category: optional(profile.category_column)?,
+        amount: net.checked_abs().ok_or_else(out_of_range)?,
+        description: optional(Some(profile.description_column))?,
+        splits: vec![],
+        status: TrnStatus::Uncleared,
+        external_id: None,
+    })
+}
+
+/// Parses amount written with the given decimal separator, ignoring spaces
+/// and thousands separators, e.g. `-1 234,50` or `1,234.50`
+pub(super) fn parse_statement_amount(
+    value: &str,
+    decimal_separator: char,
+    exponent: u32,
+) -> Result<i64> {
+    let thousands_separator = match decimal_separator {
+        ',' => '.',
+        _ => ',',
+    };
+
+    let normalized: String = value
+        .chars()
+        .filter(|&c| !c.is_whitespace() && c != '\'' && c != thousands_separator)
+        .map(|c| if c == decimal_separator { '.' } else { c })
+        .collect();
+
+    parse_money(&normalized, exponent).map_err(|_| eyre!("invalid amount '{value}'"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::currency::DEFAULT_EXPONENT;
+
+    fn profile() -> CsvProfile {
+        CsvProfile {
+            name: String::from("test"),
+            delimiter: ',',
+            skip_rows: 1,
+            date_column: 1,
+            date_format: String::from("%Y-%m-%d"),
+            amount_column: 2,
+            outflow_column: None,
+            description_column: 3,
+            category_column: None,
+            decimal_separator: '.',
+            invert_sign: false,
+        }
+    }
+
+    fn amounts(transactions: &[Transaction]) -> Vec<(Option<u8>, Option<u8>, i64)> {
+        transactions
+            .iter()
+            .map(|trn| (trn.debit_acc_id, trn.credit_acc_id, trn.amount))
+            .collect()
+    }
+
+    #[test]
+    fn signed_amounts() -> Result<()> {
+        let data = "Date,Amount,Description\n\
+                    2025-01-02,\"1,200.50\",Salary\n\
+                    2025-01-03,-30,Groceries\n";
+
+        let (transactions, errors) =
+            read_statement(data.as_bytes(), &profile(), 7, DEFAULT_EXPONENT)?;
+        assert_eq!(errors, vec![]);
+        assert_eq!(
+            amounts(&transactions),
+            vec![(Some(7), None, 120050), (None, Some(7), 3000)]
+        );
+        assert_eq!(transactions[1].description.as_deref(), Some("Groceries"));
+        assert_eq!(
+            transactions[0].timestamp.date_naive(),
+            NaiveDate::from_ymd_opt(2025, 1, 2).unwrap()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn separate_outflow_column() -> Result<()> {
+        let profile = CsvProfile {
+            delimiter: ';',
+            skip_rows: 0,
+            date_format: String::from("%d.%m.%Y"),
+            outflow_column: Some(3),
+            description_column: 4,
+            category_column: Some(5),
+            decimal_separator: ',',
+            ..profile()
+        };
+        let data = "02.01.2025;1.000,00;;Salary;Income\n\
+                    03.01.2025;;12,5;Coffee;\n";
+
+        let (transactions, errors) =
+            read_statement(data.as_bytes(), &profile, 1, DEFAULT_EXPONENT)?;
+        assert_eq!(errors, vec![]);
+        assert_eq!(
+            amounts(&transactions),
+            vec![(Some(1), None, 100000), (None, Some(1), 1250)]
+        );
+        assert_eq!(transactions[0].category.as_deref(), Some("Income"));
+        assert_eq!(transactions[1].category, None);
+
+        let data = "02.01.2025;-90000000000000000;90000000000000000;Overflow;\n";
+        let (transactions, errors) =
+            read_statement(data.as_bytes(), &profile, 1, DEFAULT_EXPONENT)?;
+        assert_eq!(transactions, vec![]);
+        assert_eq!(errors[0].message, "amount is out of range");
+        Ok(())
+    }
+
+    #[test]
+    fn inverted_sign() -> Result<()> {
+        let profile = CsvProfile {
+            invert_sign: true,
+            skip_rows: 0,
+            ..profile()
+        };
+        let (transactions, _) = read_statement(
+            "2025-01-02,25.00,Cinema".as_bytes(),
+            &profile,
+            1,
+            DEFAULT_EXPONENT,
+        )?;
+        assert_eq!(amounts(&transactions), vec![(None, Some(1), 2500)]);
+        Ok(())
+    }
+
+    #[test]
+    fn row_errors() -> Result<()> {
+        let data = "Date,Amount,Description\n\
+                    2025-01-02,10,Ok\n\
+                    2025-13-02,10,Bad date\n\
+                    2025-01-04,ten,Bad amount\n\
+                    2025-01-05\n";
+
+        let (transactions, errors) =
+            read_statement(data.as_bytes(), &profile(), 1, DEFAULT_EXPONENT)?;
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(
+            errors.iter().map(|err| err.line).collect::<Vec<_>>(),
+            vec![3, 4, 5]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn import_saves_valid_rows() -> Result<()> {
+        let mut service = BudgetService::new(":memory:");
+        service.put_account(&crate::types::Account {
+            account_id: None,
+            name: String::from("Bank"),
+            acc_type: Default::default(),
+            currency: String::from("USD"),
+            opening_balance: 0,
+        })?;
+
+        let data = "Date,Amount,Description\n2025-01-02,10,Ok\n2025-01-03,x,Bad\n";
+        let profile = service.get_import_profile("default")?;
+        let report = import(&mut service, data.as_bytes(), &profile, 1)?;
+
+        assert_eq!(report.imported, 1);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(service.get_trns()?.len(), 1);
+
+        let report = import(&mut service, data.as_bytes(), &profile, 1)?;
+        assert_eq!(report.imported, 0);
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(service.get_trns()?.len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+use std::{fmt, path::Path};
+
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    service::BudgetService,
+    types::{Duplicate, Transaction},
+};
+
+pub mod csv;
+pub mod ofx;
+pub mod qif;
+pub mod rates;
+
+/// Statement record that could not be imported
+#[derive(Debug, PartialEq)]
+pub struct RowError {
+    pub line: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportReport {
+    pub imported: usize,
+    /// Records with the bank id of a stored transaction
+    pub already_imported: usize,
+    pub errors: Vec<RowError>,
+    /// Records that look like stored transactions, held back for review
+    pub duplicates: Vec<Duplicate>,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "imported {} transactions", self.imported)?;
+        if self.already_imported > 0 {
+            write!(f, ", {} were imported before", self.already_imported)?;
+        }
+        if !self.errors.is_empty() {
+            write!(f, ", skipped {} rows with errors", self.errors.len())?;
+        }
+        if !self.duplicates.is_empty() {
+            write!(f, ", held back {} likely duplicates", self.duplicates.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// Imports statement file into account, reading `.ofx` and `.qfx` files as OFX,
+/// `.qif` files as QIF and the rest as CSV with the named mapping profile
+pub fn import_file(
+    service: &mut BudgetService,
+    path: &Path,
+    profile: &str,
+    account_id: u8,
+) -> Result<ImportReport> {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("ofx" | "qfx") => ofx::import_file(service, path, account_id),
+        Some("qif") => qif::import_file(service, path, account_id),
+        _ => csv::import_file(service, path, profile, account_id),
+    }
+}
+
+/// Currency of the account statements are imported into
+fn account_currency(service: &BudgetService, account_id: u8) -> Result<String> {
+    service
+        .get_accounts()?
+        .into_iter()
+        .find(|account| account.account_id == Some(account_id))
+        .map(|account| account.currency)
+        .ok_or_else(|| eyre!("account {account_id} does not exist"))
+}
+
+/// Description of a statement record from its payee and memo
+fn describe(payee: Option<&str>, memo: Option<&str>) -> Option<String> {
+    match (payee, memo) {
+        (Some(payee), Some(memo)) if payee != memo => Some(format!("{payee} - {memo}")),
+        (Some(text), _) | (None, Some(text)) => Some(text.to_string()),
+        (None, None) => None,
+    }
+}
+
+/// Saves parsed statement transactions at once. The open side of each goes to
+/// the suspense account of the currency, records with the bank id of a stored
+/// transaction are skipped and other likely duplicates are held back.
+fn save_statement(
+    service: &mut BudgetService,
+    currency: &str,
+    mut transactions: Vec<Transaction>,
+    errors: Vec<RowError>,
+) -> Result<ImportReport> {
+    // the other side stays in suspense until the transactions are categorized
+    let suspense = Some(service.suspense_account(currency)?);
+    for trn in &mut transactions {
+        trn.credit_acc_id = trn.credit_acc_id.or(suspense);
+        trn.debit_acc_id = trn.debit_acc_id.or(suspense);
+    }
+    let (transactions, mut duplicates) = service.find_duplicates(transactions)?;
+    service.put_trns_with_categories(&transactions)?;
+
+    let count = duplicates.len();
+    duplicates.retain(|duplicate| {
+        duplicate.candidate.external_id.is_none()
+            || duplicate.candidate.external_id != duplicate.existing.external_id
+    });
+    Ok(ImportReport {
+        imported: transactions.len(),
+        already_imported: count - duplicates.len(),
+        errors,
+        duplicates,
+    })
+}
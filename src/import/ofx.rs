@@ -0,0 +1,397 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use chrono::{NaiveDate, NaiveTime};
+use color_eyre::eyre::{eyre, Result};
+
+use super::{
+    account_currency, csv::parse_statement_amount, describe, save_statement, ImportReport, RowError,
+};
+use crate::{
+    currency::exponent,
+    parsing::local_datetime,
+    service::BudgetService,
+    types::{Transaction, TrnStatus},
+};
+
+const TRANSACTION_TAG: &str = "STMTTRN";
+
+/// Imports OFX or QFX statement file into account. Records carry the bank id,
+/// so importing the same statement again adds nothing.
+pub fn import_file(
+    service: &mut BudgetService,
+    path: &Path,
+    account_id: u8,
+) -> Result<ImportReport> {
+    let data = fs::read(path).map_err(|err| eyre!("can not open {}: {err}", path.display()))?;
+    import(service, &decode(&data), account_id)
+}
+
+pub fn import(service: &mut BudgetService, data: &str, account_id: u8) -> Result<ImportReport> {
+    let currency = account_currency(service, account_id)?;
+    let (transactions, errors) = read_statement(data, account_id, exponent(&currency))?;
+    save_statement(service, &currency, transactions, errors)
+}
+
+/// Text of the document in the character set declared by its header, OFX 1.x
+/// files are mostly Windows-1252 or Latin-1 rather than UTF-8
+fn decode(data: &[u8]) -> String {
+    let header_end = data
+        .windows(4)
+        .position(|window| window.eq_ignore_ascii_case(b"<OFX"))
+        .unwrap_or(data.len());
+    let header = String::from_utf8_lossy(&data[..header_end]).to_uppercase();
+    // SGML header line `CHARSET:1252` or XML declaration `encoding="windows-1252"`
+    let charset = header
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("CHARSET:"))
+        .or_else(|| header.split("ENCODING=").nth(1))
+        .map(|value| value.trim().trim_matches(['"', '\'']))
+        .and_then(|value| value.split(['"', '\'', ' ', '?']).next())
+        .unwrap_or_default();
+
+    match charset {
+        "1252" | "WINDOWS-1252" | "CP1252" => data.iter().map(|&byte| windows_1252(byte)).collect(),
+        "ISOLATIN1" | "ISO-8859-1" | "LATIN1" => {
+            data.iter().map(|&byte| char::from(byte)).collect()
+        }
+        _ => String::from_utf8_lossy(data).into_owned(),
+    }
+}
+
+/// Character of a Windows-1252 byte, which is Latin-1 except for 0x80 to 0x9F.
+/// Bytes unused by Windows-1252 keep their Latin-1 control character.
+fn windows_1252(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}',
+        '\u{2021}', '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}',
+        '\u{8F}', '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}',
+        '\u{2014}', '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}',
+        '\u{178}',
+    ];
+    match byte {
+        0x80..=0x9F => HIGH[usize::from(byte - 0x80)],
+        _ => char::from(byte),
+    }
+}
+
+/// Parses the `STMTTRN` records of an OFX 1.x (SGML) or 2.x (XML) document into
+/// transactions of the account, signed the same way as CSV statements.
+/// Amounts have `exponent` decimals, as in the currency of the account.
+pub fn read_statement(
+    data: &str,
+    account_id: u8,
+    exponent: u32,
+) -> Result<(Vec<Transaction>, Vec<RowError>)> {
+    let tags = tags(data);
+    if !tags.iter().any(|tag| tag.name.eq_ignore_ascii_case("OFX")) {
+        return Err(eyre!("not an OFX document"));
+    }
+
+    let mut transactions = vec![];
+    let mut errors = vec![];
+    let mut finish = |record: Option<Record>| {
+        let Some(record) = record else {
+            return;
+        };
+        match to_transaction(&record.fields, account_id, exponent) {
+            Ok(trn) => transactions.push(trn),
+            Err(report) => errors.push(RowError {
+                line: record.line,
+                message: report.to_string(),
+            }),
+        }
+    };
+
+    let mut record: Option<Record> = None;
+    for tag in tags {
+        let name = tag.name.to_uppercase();
+        match (name.as_str(), tag.closing) {
+            (TRANSACTION_TAG, false) => {
+                // SGML allows a new record without closing the last one
+                finish(record.replace(Record {
+                    line: tag.line,
+                    fields: HashMap::new(),
+                }));
+            }
+            (TRANSACTION_TAG, true) => finish(record.take()),
+            (_, false) if !tag.text.is_empty() => {
+                if let Some(record) = &mut record {
+                    record.fields.insert(name, unescape(tag.text));
+                }
+            }
+            _ => {}
+        }
+    }
+    finish(record);
+
+    Ok((transactions, errors))
+}
+
+/// Values of a transaction record by upper case tag name
+struct Record {
+    line: u64,
+    fields: HashMap<String, String>,
+}
+
+/// Opening or closing tag with the text up to the next tag
+struct Tag<'a> {
+    name: &'a str,
+    closing: bool,
+    text: &'a str,
+    line: u64,
+}
+
+/// Tags of the document, skipping the SGML header, processing instructions
+/// and declarations
+fn tags(data: &str) -> Vec<Tag<'_>> {
+    let newlines = |text: &str| text.matches('\n').count() as u64;
+    let mut pieces = data.split('<');
+    let mut line = 1 + pieces.next().map_or(0, newlines);
+
+    let mut tags = vec![];
+    for piece in pieces {
+        let tag_line = line;
+        line += newlines(piece);
+        let Some((markup, text)) = piece.split_once('>') else {
+            continue;
+        };
+        if markup.starts_with(['?', '!']) {
+            continue;
+        }
+        let (closing, markup) = match markup.strip_prefix('/') {
+            Some(markup) => (true, markup),
+            None => (false, markup),
+        };
+        let name = markup
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches('/');
+        tags.push(Tag {
+            name,
+            closing,
+            text: text.trim(),
+            line: tag_line,
+        });
+    }
+    tags
+}
+
+fn to_transaction(
+    fields: &HashMap<String, String>,
+    account_id: u8,
+    exponent: u32,
+) -> Result<Transaction> {
+    let field = |name: &str| -> Result<&str> {
+        fields
+            .get(name)
+            .map(String::as_str)
+            .ok_or_else(|| eyre!("{name} is missing"))
+    };
+
+    let amount = field("TRNAMT")?;
+    // European banks write decimal commas
+    let decimal_separator = match amount.contains(',') && !amount.contains('.') {
+        true => ',',
+        false => '.',
+    };
+    let net = parse_statement_amount(amount, decimal_separator, exponent)?;
+    let (debit_acc_id, credit_acc_id) = match net {
+        ..0 => (None, Some(account_id)),
+        _ => (Some(account_id), None),
+    };
+
+    Ok(Transaction {
+        transaction_id: None,
+        credit_acc_id,
+        debit_acc_id,
+        timestamp: local_datetime(parse_ofx_date(field("DTPOSTED")?)?, NaiveTime::MIN)?,
+        category: None,
+        amount: net.abs(),
+        description: describe(
+            fields.get("NAME").map(String::as_str),
+            fields.get("MEMO").map(String::as_str),
+        ),
+        splits: vec![],
+        status: TrnStatus::Uncleared,
+        external_id: fields.get("FITID").cloned(),
+    })
+}
+
+/// Date part of an OFX datetime, e.g. `20250102`, `20250102120000.000[-5:EST]`
+fn parse_ofx_date(value: &str) -> Result<NaiveDate> {
+    value
+        .get(..8)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+        .ok_or_else(|| eyre!("invalid date '{value}', expected YYYYMMDD"))
+}
+
+/// Replaces the XML character entities
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{currency::DEFAULT_EXPONENT, types::Account};
+
+    const SGML: &str = "OFXHEADER:100
+DATA:OFXSGML
+VERSION:102
+CHARSET:1252
+
+<OFX>
+<BANKMSGSRSV1><STMTTRNRS><STMTRS>
+<CURDEF>USD
+<BANKTRANLIST>
+<DTSTART>20250101
+<STMTTRN>
+<TRNTYPE>CREDIT
+<DTPOSTED>20250102120000.000[-5:EST]
+<TRNAMT>1200.50
+<FITID>2025010201
+<NAME>ACME PAYROLL
+<MEMO>Salary
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20250103
+<TRNAMT>-30.00
+<FITID>2025010302
+<NAME>Corner Shop
+</STMTTRN>
+</BANKTRANLIST>
+</STMTRS></STMTTRNRS></BANKMSGSRSV1>
+</OFX>
+";
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<?OFX OFXHEADER="200" VERSION="220" SECURITY="NONE" OLDFILEUID="NONE" NEWFILEUID="NONE"?>
+<OFX><CREDITCARDMSGSRSV1><CCSTMTTRNRS><CCSTMTRS><BANKTRANLIST>
+<STMTTRN><TRNTYPE>DEBIT</TRNTYPE><DTPOSTED>20250105</DTPOSTED><TRNAMT>-12,5</TRNAMT><FITID>cc-1</FITID><NAME>Fish &amp; Chips</NAME></STMTTRN>
+<STMTTRN><TRNTYPE>DEBIT</TRNTYPE><DTPOSTED>2025</DTPOSTED><TRNAMT>-1</TRNAMT><FITID>cc-2</FITID></STMTTRN>
+<STMTTRN><TRNTYPE>DEBIT</TRNTYPE><DTPOSTED>20250106</DTPOSTED><FITID>cc-3</FITID></STMTTRN>
+</BANKTRANLIST></CCSTMTRS></CCSTMTTRNRS></CREDITCARDMSGSRSV1></OFX>
+"#;
+
+    #[test]
+    fn sgml_statement() -> Result<()> {
+        let (transactions, errors) = read_statement(SGML, 7, DEFAULT_EXPONENT)?;
+        assert_eq!(errors, vec![]);
+        assert_eq!(
+            transactions
+                .iter()
+                .map(|trn| (trn.debit_acc_id, trn.credit_acc_id, trn.amount))
+                .collect::<Vec<_>>(),
+            vec![(Some(7), None, 120050), (None, Some(7), 3000)]
+        );
+        assert_eq!(
+            transactions[0].timestamp.date_naive(),
+            NaiveDate::from_ymd_opt(2025, 1, 2).unwrap()
+        );
+        assert_eq!(
+            transactions[0].description.as_deref(),
+            Some("ACME PAYROLL - Salary")
+        );
+        assert_eq!(transactions[1].description.as_deref(), Some("Corner Shop"));
+        assert_eq!(transactions[1].external_id.as_deref(), Some("2025010302"));
+        Ok(())
+    }
+
+    #[test]
+    fn xml_statement() -> Result<()> {
+        let (transactions, errors) = read_statement(XML, 1, DEFAULT_EXPONENT)?;
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].amount, 1250);
+        assert_eq!(transactions[0].credit_acc_id, Some(1));
+        assert_eq!(transactions[0].description.as_deref(), Some("Fish & Chips"));
+        assert_eq!(transactions[0].external_id.as_deref(), Some("cc-1"));
+        assert_eq!(
+            errors
+                .iter()
+                .map(|err| (err.line, err.message.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (5, "invalid date '2025', expected YYYYMMDD"),
+                (6, "TRNAMT is missing")
+            ]
+        );
+
+        assert!(read_statement("Date,Amount\n", 1, DEFAULT_EXPONENT).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn decode_declared_charset() {
+        let record = b"<OFX><NAME>Caf\xe9 \x80 \x93A\x94</NAME></OFX>";
+        let sgml = [
+            b"OFXHEADER:100\r\nENCODING:USASCII\r\nCHARSET:1252\r\n\r\n",
+            &record[..],
+        ]
+        .concat();
+        assert!(decode(&sgml).ends_with("<NAME>Caf\u{e9} \u{20AC} \u{201C}A\u{201D}</NAME></OFX>"));
+
+        let latin1 = [b"OFXHEADER:100\nCHARSET:ISOLATIN1\n\n", &record[..]].concat();
+        assert!(decode(&latin1).contains("Caf\u{e9} \u{80} \u{93}A\u{94}"));
+
+        let xml = [
+            &b"<?xml version=\"1.0\" encoding=\"windows-1252\"?>\n"[..],
+            &record[..],
+        ]
+        .concat();
+        assert!(decode(&xml).contains("Caf\u{e9} \u{20AC}"));
+
+        let utf8 =
+            "OFXHEADER:100\nENCODING:UTF-8\nCHARSET:NONE\n\n<OFX><NAME>Caf\u{e9}</NAME></OFX>";
+        assert_eq!(decode(utf8.as_bytes()), utf8);
+    }
+
+    #[test]
+    fn reimport_adds_nothing() -> Result<()> {
+        let mut service = BudgetService::new(":memory:");
+        service.put_account(&Account {
+            account_id: None,
+            name: String::from("Bank"),
+            acc_type: Default::default(),
+            currency: String::from("USD"),
+            opening_balance: 0,
+        })?;
+
+        let report = import(&mut service, SGML, 1)?;
+        assert_eq!(report.imported, 2);
+
+        let report = import(&mut service, SGML, 1)?;
+        assert_eq!(report.imported, 0);
+        assert_eq!(report.already_imported, 2);
+        assert_eq!(report.duplicates, vec![]);
+        assert_eq!(service.get_trns()?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn bank_ids_belong_to_one_account() -> Result<()> {
+        let mut service = BudgetService::new(":memory:");
+        for name in ["Checking", "Savings"] {
+            service.put_account(&Account {
+                account_id: None,
+                name: String::from(name),
+                acc_type: Default::default(),
+                currency: String::from("USD"),
+                opening_balance: 0,
+            })?;
+        }
+
+        assert_eq!(import(&mut service, SGML, 1)?.imported, 2);
+        let report = import(&mut service, SGML, 2)?;
+        assert_eq!(report.imported, 2);
+        assert_eq!(report.already_imported, 0);
+        assert_eq!(service.get_trns()?.len(), 4);
+        Ok(())
+    }
+}
@@ -0,0 +1,499 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+};
+
+use chrono::{NaiveDate, NaiveTime};
+use color_eyre::eyre::{eyre, Result};
+
+use super::{
+    account_currency, csv::parse_statement_amount, describe, save_statement, ImportReport, RowError,
+};
+use crate::{
+    currency::exponent,
+    parsing::{format_money, local_datetime},
+    service::BudgetService,
+    types::{Account, AccountType, Split, Transaction, TrnStatus},
+};
+
+const BANK_HEADER: &str = "!Type:Bank";
+const CCARD_HEADER: &str = "!Type:CCard";
+const DATE_FORMAT: &str = "%m/%d/%Y";
+
+/// Imports QIF file into account. Transfers name the other account in brackets,
+/// the other side of the rest stays in suspense.
+pub fn import_file(
+    service: &mut BudgetService,
+    path: &Path,
+    account_id: u8,
+) -> Result<ImportReport> {
+    let file = File::open(path).map_err(|err| eyre!("can not open {}: {err}", path.display()))?;
+    import(service, file, account_id)
+}
+
+pub fn import(
+    service: &mut BudgetService,
+    reader: impl Read,
+    account_id: u8,
+) -> Result<ImportReport> {
+    let currency = account_currency(service, account_id)?;
+    let accounts = service.get_accounts()?;
+    let (transactions, errors) =
+        read_statement(reader, account_id, &accounts, exponent(&currency))?;
+    save_statement(service, &currency, transactions, errors)
+}
+
+/// Parses the records of the `!Type:Bank` and `!Type:CCard` sections into
+/// transactions of the account, signed the same way as CSV statements.
+/// Records of other sections are skipped, reporting the unsupported ones.
+/// Amounts have `exponent` decimals, as in the currency of the account.
+pub fn read_statement(
+    mut reader: impl Read,
+    account_id: u8,
+    accounts: &[Account],
+    exponent: u32,
+) -> Result<(Vec<Transaction>, Vec<RowError>)> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+
+    let mut records = vec![];
+    let mut errors = vec![];
+    let mut supported = false;
+    let mut record: Option<Record> = None;
+    for (idx, line) in String::from_utf8_lossy(&data).lines().enumerate() {
+        let line_number = idx as u64 + 1;
+        let line = line.trim();
+        if line.starts_with('!') {
+            record = None;
+            supported = [BANK_HEADER, CCARD_HEADER]
+                .iter()
+                .any(|header| line.eq_ignore_ascii_case(header));
+            if !supported
+                && line
+                    .get(..6)
+                    .is_some_and(|s| s.eq_ignore_ascii_case("!Type:"))
+            {
+                errors.push(RowError {
+                    line: line_number,
+                    message: format!("section {line} is not supported"),
+                });
+            }
+            continue;
+        }
+        if !supported || line.is_empty() {
+            continue;
+        }
+        if line == "^" {
+            records.extend(record.take());
+            continue;
+        }
+
+        let record = record.get_or_insert_with(|| Record {
+            line: line_number,
+            ..Record::default()
+        });
+        let mut chars = line.chars();
+        let code = chars.next().unwrap_or_default();
+        let value = Some(chars.as_str().trim())
+            .filter(|value| !value.is_empty())
+            .map(String::from);
+        match code {
+            'D' => record.date = value,
+            'T' => record.amount = value,
+            'U' => record.amount = record.amount.take().or(value),
+            'C' => record.cleared = value,
+            'P' => record.payee = value,
+            'M' => record.memo = value,
+            'L' => record.category = value,
+            'S' => record.splits.push(RawSplit {
+                category: value,
+                ..RawSplit::default()
+            }),
+            'E' => record.last_split().memo = value,
+            '$' => record.last_split().amount = value,
+            _ => {}
+        }
+    }
+    records.extend(record);
+
+    let mut transactions = vec![];
+    for record in records {
+        match to_transaction(&record, account_id, accounts, exponent) {
+            Ok(trn) => transactions.push(trn),
+            Err(report) => errors.push(RowError {
+                line: record.line,
+                message: report.to_string(),
+            }),
+        }
+    }
+    errors.sort_by_key(|err| err.line);
+    Ok((transactions, errors))
+}
+
+/// Writes transactions of the account as a QIF file, a `!Type:CCard` one for
+/// liability accounts. Transfers to asset and liability accounts name the
+/// account instead of the category.
+pub fn write(
+    mut writer: impl Write,
+    account_id: u8,
+    accounts: &[Account],
+    transactions: &[Transaction],
+) -> Result<()> {
+    let account_by_id = |id: Option<u8>| accounts.iter().find(|acc| acc.account_id == id);
+    let account = account_by_id(Some(account_id))
+        .ok_or_else(|| eyre!("account {account_id} does not exist"))?;
+    let exponent = exponent(&account.currency);
+    let header = match account.acc_type {
+        AccountType::Liability => CCARD_HEADER,
+        _ => BANK_HEADER,
+    };
+
+    writeln!(writer, "{header}")?;
+    for trn in transactions {
+        let (sign, other) = if trn.debit_acc_id == Some(account_id) {
+            (1, trn.credit_acc_id)
+        } else if trn.credit_acc_id == Some(account_id) {
+            (-1, trn.debit_acc_id)
+        } else {
+            return Err(eyre!(
+                "transaction {} is not in account {account_id}",
+                trn.transaction_id.unwrap_or_default()
+            ));
+        };
+
+        writeln!(writer, "D{}", trn.timestamp.format(DATE_FORMAT))?;
+        writeln!(writer, "T{}", format_money(sign * trn.amount, exponent))?;
+        match trn.status {
+            TrnStatus::Uncleared => {}
+            TrnStatus::Cleared => writeln!(writer, "C*")?,
+            TrnStatus::Reconciled => writeln!(writer, "CX")?,
+        }
+        if let Some(description) = &trn.description {
+            writeln!(writer, "P{description}")?;
+        }
+        let transfer = account_by_id(other)
+            .filter(|acc| matches!(acc.acc_type, AccountType::Asset | AccountType::Liability));
+        match (transfer, &trn.category) {
+            (Some(acc), _) => writeln!(writer, "L[{}]", acc.name)?,
+            (None, Some(category)) => writeln!(writer, "L{category}")?,
+            (None, None) => {}
+        }
+        for split in &trn.splits {
+            writeln!(writer, "S{}", split.category.as_deref().unwrap_or_default())?;
+            if let Some(description) = &split.description {
+                writeln!(writer, "E{description}")?;
+            }
+            writeln!(writer, "${}", format_money(sign * split.amount, exponent))?;
+        }
+        writeln!(writer, "^")?;
+    }
+    Ok(())
+}
+
+/// Field values of a record as written in the file
+#[derive(Debug, Default)]
+struct Record {
+    line: u64,
+    date: Option<String>,
+    amount: Option<String>,
+    cleared: Option<String>,
+    payee: Option<String>,
+    memo: Option<String>,
+    category: Option<String>,
+    splits: Vec<RawSplit>,
+}
+
+#[derive(Debug, Default)]
+struct RawSplit {
+    category: Option<String>,
+    memo: Option<String>,
+    amount: Option<String>,
+}
+
+impl Record {
+    /// Split the `E` and `$` lines belong to, a new one when there is none yet
+    fn last_split(&mut self) -> &mut RawSplit {
+        if self.splits.is_empty() {
+            self.splits.push(RawSplit::default());
+        }
+        self.splits.last_mut().unwrap()
+    }
+}
+
+fn to_transaction(
+    record: &Record,
+    account_id: u8,
+    accounts: &[Account],
+    exponent: u32,
+) -> Result<Transaction> {
+    let amount = |value: &Option<String>| -> Result<i64> {
+        let value = value.as_deref().ok_or_else(|| eyre!("amount is missing"))?;
+        parse_statement_amount(value, '.', exponent)
+    };
+
+    let date = record
+        .date
+        .as_deref()
+        .ok_or_else(|| eyre!("date is missing"))?;
+    let net = amount(&record.amount)?;
+
+    let (other, category) = match record.category.as_deref() {
+        Some(name) if name.starts_with('[') && name.ends_with(']') => {
+            let name = &name[1..name.len() - 1];
+            let other = accounts
+                .iter()
+                .find(|acc| acc.name.eq_ignore_ascii_case(name))
+                .ok_or_else(|| eyre!("account '{name}' does not exist"))?;
+            let own = accounts
+                .iter()
+                .find(|acc| acc.account_id == Some(account_id))
+                .ok_or_else(|| eyre!("account {account_id} does not exist"))?;
+            if other.account_id == own.account_id {
+                return Err(eyre!("transfer from account '{name}' to itself"));
+            }
+            // reported for the record, saving it would refuse the whole statement
+            if other.currency != own.currency {
+                return Err(eyre!(
+                    "account '{name}' is in {}, not in {}, \
+                     transfers between currencies are not supported",
+                    other.currency,
+                    own.currency
+                ));
+            }
+            (other.account_id, None)
+        }
+        category => (None, category.map(String::from)),
+    };
+    let (debit_acc_id, credit_acc_id) = match net {
+        ..0 => (other, Some(account_id)),
+        _ => (Some(account_id), other),
+    };
+
+    let splits = record
+        .splits
+        .iter()
+        .map(|split| {
+            Ok(Split {
+                category: split.category.clone(),
+                amount: amount(&split.amount)? * net.signum(),
+                description: split.memo.clone(),
+            })
+        })
+        .collect::<Result<Vec<Split>>>()?;
+    let split_total = Split::total(&splits)?;
+    if !splits.is_empty() && split_total != net.abs() {
+        return Err(eyre!(
+            "splits add up to {}, not to the amount {}",
+            format_money(split_total, exponent),
+            format_money(net.abs(), exponent)
+        ));
+    }
+
+    let status = match record.cleared.as_deref() {
+        Some("*" | "c" | "C") => TrnStatus::Cleared,
+        Some("X" | "x" | "R" | "r") => TrnStatus::Reconciled,
+        _ => TrnStatus::Uncleared,
+    };
+
+    Ok(Transaction {
+        transaction_id: None,
+        credit_acc_id,
+        debit_acc_id,
+        timestamp: local_datetime(parse_qif_date(date)?, NaiveTime::MIN)?,
+        category,
+        amount: net.abs(),
+        description: describe(record.payee.as_deref(), record.memo.as_deref()),
+        splits,
+        status,
+        external_id: None,
+    })
+}
+
+/// Parses month first dates, e.g. `01/02/2025`, `1/ 2'25` or `1/2/25`,
+/// and ISO ones. Two digit years after an apostrophe are in the 2000s.
+fn parse_qif_date(value: &str) -> Result<NaiveDate> {
+    let invalid = || eyre!("invalid date '{value}', expected MM/DD/YYYY");
+    let century = if value.contains('\'') { 2000 } else { 1900 };
+    let parts: Vec<i32> = value
+        .split(['/', '\'', '-', '.'])
+        .map(|part| part.trim().parse().map_err(|_| invalid()))
+        .collect::<Result<_>>()?;
+
+    let (year, month, day) = match parts[..] {
+        [year, month, day] if year >= 1000 => (year, month, day),
+        [month, day, year] if year < 100 => (century + year, month, day),
+        [month, day, year] => (year, month, day),
+        _ => return Err(invalid()),
+    };
+    let month = u32::try_from(month).map_err(|_| invalid())?;
+    let day = u32::try_from(day).map_err(|_| invalid())?;
+    NaiveDate::from_ymd_opt(year, month, day).ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{currency::DEFAULT_EXPONENT, types::TrnFilter};
+
+    const BANK: &str = include_str!("../assets/qif/bank.qif");
+    const CCARD: &str = include_str!("../assets/qif/ccard.qif");
+
+    fn accounts() -> Vec<Account> {
+        [
+            ("Bank", AccountType::Asset),
+            ("Savings", AccountType::Asset),
+            ("Visa", AccountType::Liability),
+        ]
+        .into_iter()
+        .zip(1..)
+        .map(|((name, acc_type), id)| Account {
+            account_id: Some(id),
+            name: name.to_string(),
+            acc_type,
+            currency: String::from("USD"),
+            opening_balance: 0,
+        })
+        .collect()
+    }
+
+    fn write_string(account_id: u8, transactions: &[Transaction]) -> Result<String> {
+        let mut out = vec![];
+        write(&mut out, account_id, &accounts(), transactions)?;
+        Ok(String::from_utf8(out)?)
+    }
+
+    #[test]
+    fn bank_records() -> Result<()> {
+        let (transactions, errors) =
+            read_statement(BANK.as_bytes(), 1, &accounts(), DEFAULT_EXPONENT)?;
+        assert_eq!(errors, vec![]);
+        assert_eq!(
+            transactions
+                .iter()
+                .map(|trn| (trn.debit_acc_id, trn.credit_acc_id, trn.amount))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some(1), None, 120050),
+                (None, Some(1), 3000),
+                (Some(2), Some(1), 10000),
+                (None, Some(1), 7500),
+                (Some(1), None, 1500),
+            ]
+        );
+        assert_eq!(transactions[1].category.as_deref(), Some("Food:Groceries"));
+        assert_eq!(transactions[2].category, None);
+        assert_eq!(transactions[2].status, TrnStatus::Reconciled);
+        assert_eq!(
+            transactions[3].splits,
+            vec![
+                Split {
+                    category: Some(String::from("Food")),
+                    amount: 5000,
+                    description: Some(String::from("Weekly shop")),
+                },
+                Split {
+                    category: Some(String::from("Household")),
+                    amount: 2500,
+                    description: None,
+                },
+            ]
+        );
+        assert_eq!(
+            transactions[0].timestamp.date_naive(),
+            NaiveDate::from_ymd_opt(2025, 1, 2).unwrap()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn transfer_needs_same_currency() -> Result<()> {
+        let mut accounts = accounts();
+        accounts[1].currency = String::from("EUR");
+
+        let (transactions, errors) =
+            read_statement(BANK.as_bytes(), 1, &accounts, DEFAULT_EXPONENT)?;
+        assert_eq!(transactions.len(), 4);
+        assert_eq!(
+            errors,
+            vec![RowError {
+                line: 13,
+                message: String::from(
+                    "account 'Savings' is in EUR, not in USD, \
+                     transfers between currencies are not supported"
+                ),
+            }]
+        );
+
+        let (transactions, errors) =
+            read_statement(BANK.as_bytes(), 2, &accounts, DEFAULT_EXPONENT)?;
+        assert_eq!(transactions.len(), 4);
+        assert_eq!(
+            errors[0].message,
+            "transfer from account 'Savings' to itself"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn bank_round_trip() -> Result<()> {
+        let (transactions, _) = read_statement(BANK.as_bytes(), 1, &accounts(), DEFAULT_EXPONENT)?;
+        assert_eq!(write_string(1, &transactions)?, BANK);
+        Ok(())
+    }
+
+    #[test]
+    fn ccard_round_trip() -> Result<()> {
+        let (transactions, errors) =
+            read_statement(CCARD.as_bytes(), 3, &accounts(), DEFAULT_EXPONENT)?;
+        assert_eq!(
+            errors
+                .iter()
+                .map(|err| (err.line, err.message.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (29, "invalid date '13/45'25', expected MM/DD/YYYY"),
+                (32, "splits add up to 8.00, not to the amount 9.00"),
+                (37, "section !Type:Invst is not supported"),
+            ]
+        );
+        assert_eq!(transactions.len(), 3);
+        assert_eq!(transactions[0].amount, 123456);
+        assert_eq!(
+            transactions[0].description.as_deref(),
+            Some("Electronics Store - New laptop")
+        );
+        assert_eq!(transactions[1].status, TrnStatus::Cleared);
+        assert_eq!(transactions[2].splits[0].category, None);
+
+        let written = write_string(3, &transactions)?;
+        assert!(written.starts_with("!Type:CCard\nD01/07/2025\nT-1234.56\n"));
+        let (read_back, errors) =
+            read_statement(written.as_bytes(), 3, &accounts(), DEFAULT_EXPONENT)?;
+        assert_eq!(errors, vec![]);
+        assert_eq!(read_back, transactions);
+        Ok(())
+    }
+
+    #[test]
+    fn stored_round_trip() -> Result<()> {
+        let mut service = BudgetService::new(":memory:");
+        for account in accounts() {
+            service.put_account(&Account {
+                account_id: None,
+                ..account
+            })?;
+        }
+
+        let report = import(&mut service, BANK.as_bytes(), 1)?;
+        assert_eq!(report.imported, 5);
+        assert_eq!(service.get_trn(3)?.unwrap().debit_acc_id, Some(2));
+
+        let stored = service.find_trns(&TrnFilter {
+            account_id: Some(1),
+            ..TrnFilter::default()
+        })?;
+        assert_eq!(write_string(1, &stored)?, BANK);
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use std::{fs::File, io::Read, path::Path};
+
+use chrono::NaiveDate;
+use color_eyre::eyre::{eyre, Result};
+use serde::Deserialize;
+
+use crate::{service::BudgetService, types::ExchangeRate};
+
+/// Row of an exchange rates file, with a `date,currency,quote,rate` header
+#[derive(Debug, Deserialize)]
+struct RateRecord {
+    date: NaiveDate,
+    currency: String,
+    quote: String,
+    rate: f64,
+}
+
+/// Saves all rates of the file or none of them, returns how many were saved
+pub fn import_file(service: &mut BudgetService, path: &Path) -> Result<usize> {
+    let file = File::open(path).map_err(|err| eyre!("can not open {}: {err}", path.display()))?;
+    service.put_rates(&read_rates(file)?)
+}
+
+/// Parses exchange rates, failing on the first invalid row
+pub fn read_rates(reader: impl Read) -> Result<Vec<ExchangeRate>> {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .trim(::csv::Trim::All)
+        .from_reader(reader);
+    reader
+        .deserialize()
+        .map(|record| {
+            let record: RateRecord = record.map_err(|err| eyre!("invalid exchange rate: {err}"))?;
+            Ok(ExchangeRate {
+                date: record.date,
+                currency: record.currency,
+                quote: record.quote,
+                rate: record.rate,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_rates_file() -> Result<()> {
+        let data = "date, currency, quote, rate\n2025-01-02, EUR, USD, 1.035\n";
+        let rates = read_rates(data.as_bytes())?;
+        assert_eq!(
+            rates,
+            [ExchangeRate {
+                date: NaiveDate::from_ymd_opt(2025, 1, 2).unwrap(),
+                currency: String::from("EUR"),
+                quote: String::from("USD"),
+                rate: 1.035,
+            }]
+        );
+
+        assert!(read_rates("date,currency,quote,rate\n2025-01-02,EUR,USD,a\n".as_bytes()).is_err());
+        Ok(())
+    }
+}
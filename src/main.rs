@@ -1,17 +1,46 @@
 use app::App;
+use clap::Parser;
+use cli::{Cli, Command};
 use color_eyre::eyre;
+use config::Config;
+use service::BudgetService;
+use std::path::Path;
 
 mod app;
+mod cli;
+mod config;
+mod currency;
+mod export;
+mod import;
+mod parsing;
+mod query;
 mod service;
 mod types;
 mod widgets;
 
 fn main() -> eyre::Result<()> {
     color_eyre::install()?;
+    let cli = Cli::parse();
 
-    let mut terminal = ratatui::init();
-    let result = App::new().run(&mut terminal);
+    let mut config = Config::load(
+        cli.config.as_deref(),
+        |name| std::env::var(name).ok(),
+        Path::is_file,
+    )?;
+    if let Some(db) = cli.db {
+        config.db = db;
+    }
+    config.prepare_db_dir()?;
+    let mut service = BudgetService::new(&config.db);
 
-    ratatui::restore();
-    result
+    match cli.command {
+        None | Some(Command::Tui) => {
+            let mut terminal = ratatui::init();
+            let result = App::new(service, config).run(&mut terminal);
+
+            ratatui::restore();
+            result
+        }
+        Some(command) => cli::run(&mut service, &config, command, &mut std::io::stdout()),
+    }
 }
@@ -0,0 +1,190 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone};
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    currency::DEFAULT_EXPONENT,
+    types::{Account, Split},
+};
+
+/// Parses date in a `chrono` format, like the one set in the config
+pub fn parse_date(value: &str, format: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), format)
+        .map_err(|_| eyre!("invalid date '{value}', expected format {format}"))
+}
+
+/// Combines date and time in local time zone, picking the earliest one when ambiguous
+pub fn local_datetime(date: NaiveDate, time: NaiveTime) -> Result<DateTime<Local>> {
+    Local
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+        .ok_or_else(|| eyre!("date {date} {time} does not exist in local time zone"))
+}
+
+/// Parses a decimal amount like `-12.5` or `1200,00` into cents
+pub fn parse_amount(value: &str) -> Result<i64> {
+    parse_money(value, DEFAULT_EXPONENT)
+}
+
+/// Parses a decimal amount into minor units of a currency with `exponent` decimals
+pub fn parse_money(value: &str, exponent: u32) -> Result<i64> {
+    let invalid = || eyre!("invalid amount '{value}'");
+    let decimals = exponent as usize;
+
+    let trimmed = value.trim();
+    let (negative, unsigned) = match trimmed.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+
+    let (whole, frac) = match unsigned.split_once(['.', ',']) {
+        Some((whole, frac)) => (whole, frac),
+        None => (unsigned, ""),
+    };
+
+    let is_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if whole.is_empty() || !is_digits(whole) || !is_digits(frac) || frac.len() > decimals {
+        return Err(invalid());
+    }
+
+    let whole: i64 = whole.parse().map_err(|_| invalid())?;
+    let frac: i64 = match decimals {
+        0 => 0,
+        _ => format!("{frac:0<decimals$}")
+            .parse()
+            .map_err(|_| invalid())?,
+    };
+
+    let minor = 10_i64
+        .checked_pow(exponent)
+        .and_then(|scale| whole.checked_mul(scale))
+        .and_then(|value| value.checked_add(frac))
+        .ok_or_else(invalid)?;
+
+    Ok(if negative { -minor } else { minor })
+}
+
+/// Formats minor units of a currency with `exponent` decimals, e.g. `-1250` as `-12.50`
+/// with 2 decimals or as `-1250` with none
+pub fn format_money(amount: i64, exponent: u32) -> String {
+    let sign = if amount < 0 { "-" } else { "" };
+    let scale = 10_u64.pow(exponent);
+    let whole = amount.unsigned_abs() / scale;
+    let frac = amount.unsigned_abs() % scale;
+
+    match exponent {
+        0 => format!("{sign}{whole}"),
+        _ => format!("{sign}{whole}.{frac:0width$}", width = exponent as usize),
+    }
+}
+
+/// Finds account by id or by case-insensitive name
+pub fn parse_account(value: &str, accounts: &[Account]) -> Result<u8> {
+    let value = value.trim();
+    accounts
+        .iter()
+        .find(|acc| {
+            acc.account_id.map(|id| id.to_string()).as_deref() == Some(value)
+                || acc.name.eq_ignore_ascii_case(value)
+        })
+        .and_then(|acc| acc.account_id)
+        .ok_or_else(|| eyre!("unknown account '{value}'"))
+}
+
+/// Parses splits written as `category amount` separated by semicolons,
+/// like `Food 12.50; Household 3`. The category may be omitted.
+pub fn parse_splits(value: &str, exponent: u32) -> Result<Vec<Split>> {
+    value
+        .split(';')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let (category, amount) = match part.rsplit_once(char::is_whitespace) {
+                Some((category, amount)) => (Some(category.trim().to_string()), amount),
+                None => (None, part),
+            };
+            Ok(Split {
+                category,
+                amount: parse_money(amount, exponent)?,
+                description: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn amounts() -> Result<()> {
+        assert_eq!(parse_amount("12")?, 1200);
+        assert_eq!(parse_amount("12.5")?, 1250);
+        assert_eq!(parse_amount(" -0,05 ")?, -5);
+        assert_eq!(parse_amount("+3.10")?, 310);
+
+        assert!(parse_amount("").is_err());
+        assert!(parse_amount(".5").is_err());
+        assert!(parse_amount("1.234").is_err());
+        assert!(parse_amount("12a").is_err());
+        assert!(parse_amount("99999999999999999999").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn amount_round_trip() -> Result<()> {
+        for amount in [0, 5, -5, 1250, -1250, i64::MAX, i64::MIN + 1] {
+            assert_eq!(
+                parse_amount(&format_money(amount, DEFAULT_EXPONENT))?,
+                amount
+            );
+        }
+        assert_eq!(format_money(-150, DEFAULT_EXPONENT), "-1.50");
+        Ok(())
+    }
+
+    #[test]
+    fn splits() -> Result<()> {
+        let splits = parse_splits("Food & drinks 12.50; 3 ;", 2)?;
+        assert_eq!(
+            splits,
+            [
+                Split {
+                    category: Some(String::from("Food & drinks")),
+                    amount: 1250,
+                    description: None,
+                },
+                Split {
+                    category: None,
+                    amount: 300,
+                    description: None,
+                },
+            ]
+        );
+        assert!(parse_splits("Food twelve", 2).is_err());
+        assert!(parse_splits("", 2).is_ok_and(|splits| splits.is_empty()));
+        Ok(())
+    }
+
+    #[test]
+    fn money_by_exponent() -> Result<()> {
+        assert_eq!(parse_money("1250", 0)?, 1250);
+        assert!(parse_money("12.5", 0).is_err());
+        assert_eq!(parse_money("1.5", 3)?, 1500);
+        assert_eq!(format_money(-1250, 0), "-1250");
+        assert_eq!(format_money(1500, 3), "1.500");
+        Ok(())
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(
+            parse_date("2025-01-31", "%Y-%m-%d").ok(),
+            NaiveDate::from_ymd_opt(2025, 1, 31)
+        );
+        assert!(parse_date("31.01.2025", "%Y-%m-%d").is_err());
+        assert_eq!(
+            parse_date("31.01.2025", "%d.%m.%Y").ok(),
+            NaiveDate::from_ymd_opt(2025, 1, 31)
+        );
+    }
+}
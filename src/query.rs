@@ -0,0 +1,206 @@
+use chrono::{Months, NaiveDate};
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    parsing::{parse_account, parse_amount, parse_date},
+    types::{Account, TrnFilter},
+};
+
+/// Comparison operators of `amount` terms, longer ones first
+const OPERATORS: [&str; 5] = [">=", "<=", ">", "<", "="];
+
+/// Parses a transaction search query. Terms are separated by spaces, all must match:
+///
+/// - `word` or `"several words"` appear in the description or the category
+/// - `amount>100`, also `>=`, `<`, `<=`, `=`, compares the amount
+/// - `date:2025-01..2025-03` limits dates, bounds are a year, a month or a day
+///   and either of them may be omitted, `date:2025-02` is a single month
+/// - `acct:2` or `acct:Cash` selects transactions of an account
+pub fn parse_query(query: &str, accounts: &[Account]) -> Result<TrnFilter> {
+    let mut filter = TrnFilter::default();
+
+    for term in terms(query)? {
+        if let Some(condition) = term.strip_prefix("amount") {
+            let (operator, value) = OPERATORS
+                .iter()
+                .find_map(|op| Some((*op, condition.strip_prefix(op)?)))
+                .ok_or_else(|| eyre!("invalid term '{term}', expected e.g. amount>100"))?;
+            let amount = parse_amount(value)?;
+            let (from, to) = match operator {
+                ">=" => (Some(amount), None),
+                ">" => (Some(amount + 1), None),
+                "<=" => (None, Some(amount)),
+                "<" => (None, Some(amount - 1)),
+                _ => (Some(amount), Some(amount)),
+            };
+            filter.amount_from = filter.amount_from.max(from);
+            filter.amount_to = min_bound(filter.amount_to, to);
+        } else if let Some(range) = term.strip_prefix("date:") {
+            let (from, to) = date_range(range)?;
+            filter.from = filter.from.max(from);
+            filter.to = min_bound(filter.to, to);
+        } else if let Some(account) = term.strip_prefix("acct:") {
+            filter.account_id = Some(parse_account(account, accounts)?);
+        } else {
+            filter.text.push(term);
+        }
+    }
+
+    Ok(filter)
+}
+
+/// Splits query by spaces, keeping quoted phrases together
+fn terms(query: &str) -> Result<Vec<String>> {
+    let mut terms = vec![];
+    let mut term = String::new();
+    let mut quoted = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !term.is_empty() {
+                    terms.push(std::mem::take(&mut term));
+                }
+            }
+            c => term.push(c),
+        }
+    }
+    if quoted {
+        return Err(eyre!("unterminated quote in query"));
+    }
+    if !term.is_empty() {
+        terms.push(term);
+    }
+    Ok(terms)
+}
+
+/// Smaller of two upper bounds, where `None` is no bound
+fn min_bound<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// First and last day of `start..end`, or of a single period
+fn date_range(value: &str) -> Result<(Option<NaiveDate>, Option<NaiveDate>)> {
+    let (start, end) = value.split_once("..").unwrap_or((value, value));
+    let bound = |value: &str| match value {
+        "" => Ok(None),
+        value => period(value).map(Some),
+    };
+
+    let from = bound(start)?.map(|(first, _)| first);
+    let to = bound(end)?.map(|(_, last)| last);
+    if from.is_none() && to.is_none() {
+        return Err(eyre!("invalid date range '{value}'"));
+    }
+    Ok((from, to))
+}
+
+/// First and last day of a year `2025`, a month `2025-01` or a day `2025-01-31`
+fn period(value: &str) -> Result<(NaiveDate, NaiveDate)> {
+    let invalid = || eyre!("invalid date '{value}', expected YYYY, YYYY-MM or YYYY-MM-DD");
+
+    let (first, months) = match value.split('-').count() {
+        1 => (format!("{value}-01-01"), 12),
+        2 => (format!("{value}-01"), 1),
+        3 => {
+            let day = parse_date(value, "%Y-%m-%d").map_err(|_| invalid())?;
+            return Ok((day, day));
+        }
+        _ => return Err(invalid()),
+    };
+
+    let first = parse_date(&first, "%Y-%m-%d").map_err(|_| invalid())?;
+    let last = first
+        .checked_add_months(Months::new(months))
+        .and_then(|next| next.pred_opt())
+        .ok_or_else(invalid)?;
+    Ok((first, last))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::AccountType;
+
+    fn date(y: i32, m: u32, d: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(y, m, d)
+    }
+
+    fn accounts() -> Vec<Account> {
+        vec![Account {
+            account_id: Some(2),
+            name: String::from("Cash"),
+            acc_type: AccountType::Asset,
+            currency: String::from("USD"),
+            opening_balance: 0,
+        }]
+    }
+
+    #[test]
+    fn full_query() -> Result<()> {
+        let filter = parse_query(
+            r#"amount>100 "corner shop" date:2025-01..2025-03 acct:cash lunch"#,
+            &accounts(),
+        )?;
+
+        assert_eq!(
+            filter,
+            TrnFilter {
+                from: date(2025, 1, 1),
+                to: date(2025, 3, 31),
+                account_id: Some(2),
+                text: vec![String::from("corner shop"), String::from("lunch")],
+                amount_from: Some(10001),
+                amount_to: None,
+                ..Default::default()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn amount_bounds() -> Result<()> {
+        let filter = parse_query("amount>=-5 amount<12.5 amount<=20", &[])?;
+        assert_eq!(filter.amount_from, Some(-500));
+        assert_eq!(filter.amount_to, Some(1249));
+
+        let filter = parse_query("amount=7", &[])?;
+        assert_eq!(
+            (filter.amount_from, filter.amount_to),
+            (Some(700), Some(700))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn date_ranges() -> Result<()> {
+        let range = |query| parse_query(query, &[]).map(|filter| (filter.from, filter.to));
+
+        assert_eq!(range("date:2024")?, (date(2024, 1, 1), date(2024, 12, 31)));
+        assert_eq!(
+            range("date:2024-02")?,
+            (date(2024, 2, 1), date(2024, 2, 29))
+        );
+        assert_eq!(range("date:2024-02-03..")?, (date(2024, 2, 3), None));
+        assert_eq!(range("date:..2024-12")?, (None, date(2024, 12, 31)));
+        Ok(())
+    }
+
+    #[test]
+    fn reject_invalid_terms() {
+        for query in [
+            "amount~5",
+            "amount>x",
+            "date:..",
+            "date:2024-13",
+            "acct:9",
+            "\"open",
+        ] {
+            assert!(parse_query(query, &accounts()).is_err(), "{query}");
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveTime};
+use color_eyre::eyre::{eyre, Result};
+use rusqlite::{params, types::Type, Row};
+
+use super::BudgetService;
+use crate::{
+    parsing::local_datetime,
+    types::{Budget, BudgetStatus},
+};
+
+const PERIOD_FORMAT: &str = "%Y-%m";
+
+/// Picks the latest budget of each category set no later than month `?1`
+const EFFECTIVE_BUDGETS: &str = "
+    FROM budgets b
+    WHERE b.period = (
+        SELECT MAX(period) FROM budgets WHERE category = b.category AND period <= ?1
+    )";
+
+impl BudgetService {
+    /// Budgets in effect in the month of `date` with the amount spent in their
+    /// categories during that month, converted into `base` currency
+    pub fn get_budget_statuses(&self, base: &str, date: NaiveDate) -> Result<Vec<BudgetStatus>> {
+        let start = month_start(date);
+        let end = start
+            .checked_add_months(Months::new(1))
+            .ok_or_else(|| eyre!("date {date} is out of range"))?;
+
+        let mut stmt = self.statement(&format!(
+            "SELECT b.category, b.period, b.limit_amount {EFFECTIVE_BUDGETS} ORDER BY b.category"
+        ))?;
+        let budgets = stmt
+            .query_map(params![period(date)], budget_from_row)?
+            .collect::<Result<Vec<Budget>, rusqlite::Error>>()?;
+
+        let mut stmt = self.statement(
+            "SELECT c.category, COALESCE(d.currency, cr.currency), SUM(c.amount)
+             FROM category_amounts c
+             JOIN fin_transaction t ON t.transaction_id = c.transaction_id
+             LEFT JOIN accounts d ON d.account_id = t.debit_acc_id
+             LEFT JOIN accounts cr ON cr.account_id = t.credit_acc_id
+             WHERE c.category IS NOT NULL AND c.timestamp >= ?1 AND c.timestamp < ?2
+             GROUP BY 1, 2",
+        )?;
+        let rows = stmt
+            .query_map(
+                params![
+                    local_datetime(start, NaiveTime::MIN)?,
+                    local_datetime(end, NaiveTime::MIN)?,
+                ],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                },
+            )?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        let rate_date = end
+            .checked_sub_days(Days::new(1))
+            .ok_or_else(|| eyre!("date {end} is out of range"))?;
+        let mut spent: HashMap<String, i64> = HashMap::new();
+        for (category, currency, amount) in rows {
+            *spent.entry(category).or_default() +=
+                self.convert(amount, &currency, base, rate_date)?;
+        }
+        Ok(budgets
+            .into_iter()
+            .map(|budget| BudgetStatus {
+                spent: spent.get(&budget.category).copied().unwrap_or_default(),
+                budget,
+            })
+            .collect())
+    }
+
+    /// Sets the limit of the category starting from the month of `budget.period`
+    pub fn put_budget(&mut self, budget: &Budget) -> Result<()> {
+        if budget.category.trim().is_empty() {
+            return Err(eyre!("budget category can not be empty"));
+        }
+        if budget.limit < 0 {
+            return Err(eyre!("budget limit can not be negative"));
+        }
+
+        self.check_category(&budget.category)?;
+        let mut upsert = self.statement(
+            "INSERT OR REPLACE INTO budgets (category, period, limit_amount) VALUES (?1, ?2, ?3)",
+        )?;
+        upsert.execute(params![
+            budget.category,
+            period(budget.period),
+            budget.limit
+        ])?;
+        Ok(())
+    }
+
+    pub fn del_budget(&mut self, category: &str, date: NaiveDate) -> Result<()> {
+        let mut delete =
+            self.statement("DELETE FROM budgets WHERE category = ?1 AND period = ?2")?;
+        delete.execute(params![category, period(date)])?;
+        Ok(())
+    }
+}
+
+fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+fn period(date: NaiveDate) -> String {
+    date.format(PERIOD_FORMAT).to_string()
+}
+
+fn budget_from_row(row: &Row) -> rusqlite::Result<Budget> {
+    let value: String = row.get(1)?;
+    let period = NaiveDate::parse_from_str(&format!("{value}-01"), "%Y-%m-%d")
+        .map_err(|_| rusqlite::Error::InvalidColumnType(1, value, Type::Text))?;
+
+    Ok(Budget {
+        category: row.get(0)?,
+        period,
+        limit: row.get(2)?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        service::fixtures::{self, account},
+        types::{Account, AccountType, ExchangeRate, Transaction},
+    };
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn budget(category: &str, period: NaiveDate, limit: i64) -> Budget {
+        Budget {
+            category: category.to_string(),
+            period,
+            limit,
+        }
+    }
+
+    fn budgets(service: &BudgetService, date: NaiveDate) -> Result<Vec<Budget>> {
+        let statuses = service.get_budget_statuses("USD", date)?;
+        Ok(statuses.into_iter().map(|status| status.budget).collect())
+    }
+
+    fn service() -> Result<BudgetService> {
+        fixtures::service(
+            &[
+                account("Cash", AccountType::Asset),
+                account("Food", AccountType::Expense),
+            ],
+            &["Food", "Fun", "Rent"],
+        )
+    }
+
+    fn spend(
+        service: &mut BudgetService,
+        day: NaiveDate,
+        category: &str,
+        amount: i64,
+    ) -> Result<()> {
+        service.put_trn(&Transaction {
+            timestamp: local_datetime(day, NaiveTime::MIN)?,
+            category: Some(category.to_string()),
+            ..fixtures::trn(Some(1), Some(2), amount)
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn latest_budget_applies() -> Result<()> {
+        let mut service = service()?;
+        service.put_budget(&budget("Food", date(2025, 1, 15), 100))?;
+        service.put_budget(&budget("Food", date(2025, 3, 1), 300))?;
+        service.put_budget(&budget("Rent", date(2025, 2, 1), 900))?;
+
+        assert_eq!(budgets(&service, date(2024, 12, 31))?, vec![]);
+        assert_eq!(
+            budgets(&service, date(2025, 2, 28))?,
+            vec![
+                budget("Food", date(2025, 1, 1), 100),
+                budget("Rent", date(2025, 2, 1), 900)
+            ]
+        );
+        assert_eq!(budgets(&service, date(2025, 7, 4))?[0].limit, 300);
+
+        service.del_budget("Food", date(2025, 3, 1))?;
+        assert_eq!(budgets(&service, date(2025, 7, 4))?[0].limit, 100);
+        Ok(())
+    }
+
+    #[test]
+    fn spent_within_month() -> Result<()> {
+        let mut service = service()?;
+        service.put_budget(&budget("Food", date(2025, 1, 1), 1000))?;
+        spend(&mut service, date(2024, 12, 31), "Food", 1)?;
+        spend(&mut service, date(2025, 1, 1), "Food", 600)?;
+        spend(&mut service, date(2025, 1, 31), "Food", 500)?;
+        spend(&mut service, date(2025, 1, 10), "Fun", 70)?;
+        spend(&mut service, date(2025, 2, 1), "Food", 10)?;
+
+        let statuses = service.get_budget_statuses("USD", date(2025, 1, 20))?;
+        assert_eq!(statuses.len(), 1);
+        assert_eq!(statuses[0].spent, 1100);
+        assert!(statuses[0].is_over());
+
+        let statuses = service.get_budget_statuses("USD", date(2025, 2, 1))?;
+        assert_eq!(statuses[0].spent, 10);
+        assert!(!statuses[0].is_over());
+        Ok(())
+    }
+
+    #[test]
+    fn spent_in_base_currency() -> Result<()> {
+        let mut service = service()?;
+        for (name, acc_type) in [
+            ("Wallet", AccountType::Asset),
+            ("Dining", AccountType::Expense),
+        ] {
+            service.put_account(&Account {
+                currency: String::from("JPY"),
+                ..account(name, acc_type)
+            })?;
+        }
+        service.put_rates(&[ExchangeRate {
+            date: date(2025, 1, 1),
+            currency: String::from("JPY"),
+            quote: String::from("USD"),
+            rate: 0.01,
+        }])?;
+        service.put_budget(&budget("Food", date(2025, 1, 1), 5000))?;
+        spend(&mut service, date(2025, 1, 2), "Food", 600)?;
+        service.put_trn(&Transaction {
+            timestamp: local_datetime(date(2025, 1, 3), NaiveTime::MIN)?,
+            category: Some(String::from("Food")),
+            ..fixtures::trn(Some(3), Some(4), 1500)
+        })?;
+
+        let statuses = service.get_budget_statuses("USD", date(2025, 1, 20))?;
+        assert_eq!(statuses[0].spent, 600 + 1500);
+        Ok(())
+    }
+
+    #[test]
+    fn reject_invalid_budget() -> Result<()> {
+        let mut service = service()?;
+
+        assert!(service
+            .put_budget(&budget(" ", date(2025, 1, 1), 1))
+            .is_err());
+        assert!(service
+            .put_budget(&budget("Food", date(2025, 1, 1), -1))
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,550 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::{eyre, Result};
+use rusqlite::{params, OptionalExtension};
+
+use super::{
+    history::{row_changes, Change},
+    BudgetService,
+};
+use crate::types::{Category, Transaction, TrnStatus};
+
+/// Categories referenced by transactions, either directly or by their splits
+const CATEGORY_USES: &str = "
+    SELECT transaction_id, category FROM fin_transaction
+    UNION ALL
+    SELECT transaction_id, category FROM postings";
+
+impl BudgetService {
+    /// Categories with every parent before its children, siblings sorted by name
+    pub fn get_categories(&self) -> Result<Vec<Category>> {
+        let mut stmt = self.statement(
+            "WITH RECURSIVE tree (category_id, name, parent_id, path) AS (
+                SELECT category_id, name, parent_id, name
+                FROM categories WHERE parent_id IS NULL
+                UNION ALL
+                SELECT c.category_id, c.name, c.parent_id, tree.path || char(31) || c.name
+                FROM categories c JOIN tree ON c.parent_id = tree.category_id
+             )
+             SELECT category_id, name, parent_id FROM tree ORDER BY path COLLATE NOCASE",
+        )?;
+
+        let categories = stmt.query_map([], |row| {
+            Ok(Category {
+                category_id: row.get(0)?,
+                name: row.get(1)?,
+                parent_id: row.get(2)?,
+            })
+        })?;
+        Ok(categories.collect::<Result<Vec<Category>, rusqlite::Error>>()?)
+    }
+
+    /// Number of transactions in every used category, by category id
+    pub fn get_category_usage(&self) -> Result<HashMap<isize, i64>> {
+        let mut stmt = self.statement(&format!(
+            "SELECT c.category_id, COUNT(DISTINCT t.transaction_id)
+                 FROM categories c JOIN ({CATEGORY_USES}) t ON t.category = c.name
+                 GROUP BY c.category_id"
+        ))?;
+
+        let usage = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(usage.collect::<Result<HashMap<isize, i64>, rusqlite::Error>>()?)
+    }
+
+    /// Creates or updates a category. Renaming also renames it in budgets and in
+    /// transactions. Updates are one step of the undo history.
+    pub fn put_category(&mut self, item: &Category) -> Result<isize> {
+        let name = item.name.trim();
+        if name.is_empty() {
+            return Err(eyre!("category name can not be empty"));
+        }
+
+        self.atomically(|service| {
+            let existing = service.category_id(name)?;
+            if existing.is_some() && existing != item.category_id {
+                return Err(eyre!("category '{name}' already exists"));
+            }
+            if let Some(parent_id) = item.parent_id {
+                let mut ancestors = service.ancestors(parent_id)?;
+                ancestors.push(parent_id);
+                if item.category_id.is_some_and(|id| ancestors.contains(&id)) {
+                    return Err(eyre!("category '{name}' can not be nested in itself"));
+                }
+            }
+
+            match item.category_id {
+                Some(category_id) => {
+                    let (old_name, _) = service.category(category_id)?;
+                    let before = service.stored_rows()?;
+                    service
+                        .statement(
+                            "UPDATE categories SET name = ?2, parent_id = ?3
+                             WHERE category_id = ?1",
+                        )?
+                        .execute(params![category_id, name, item.parent_id])?;
+
+                    let (description, changes) = match old_name == name {
+                        true => (format!("edit category {name}"), vec![]),
+                        false => {
+                            service
+                                .statement("UPDATE budgets SET category = ?2 WHERE category = ?1")?
+                                .execute(params![old_name, name])?;
+                            (
+                                format!("rename category {old_name} to {name}"),
+                                service.recategorize_trns(&old_name, name)?,
+                            )
+                        }
+                    };
+                    let rows = row_changes(&before, &service.stored_rows()?);
+                    service.record_with_rows(&description, &changes, &rows)?;
+                    Ok(category_id)
+                }
+                None => Ok(service
+                    .statement(
+                        "INSERT INTO categories (name, parent_id) VALUES (?1, ?2)
+                         RETURNING category_id",
+                    )?
+                    .query_row(params![name, item.parent_id], |row| row.get(0))?),
+            }
+        })
+    }
+
+    /// Moves transactions, budgets and subcategories of category `from` to `into`,
+    /// then deletes `from`, as one step of the undo history.
+    /// Budgets `into` already has for the same month are kept.
+    pub fn merge_categories(&mut self, from: isize, into: isize) -> Result<()> {
+        if from == into {
+            return Err(eyre!("can not merge category into itself"));
+        }
+
+        self.atomically(|service| {
+            let (from_name, from_parent) = service.category(from)?;
+            let (into_name, _) = service.category(into)?;
+            let before = service.stored_rows()?;
+
+            // take `into` out of the subtree before it adopts the children of `from`
+            if service.ancestors(into)?.contains(&from) {
+                service
+                    .statement("UPDATE categories SET parent_id = ?2 WHERE category_id = ?1")?
+                    .execute(params![into, from_parent])?;
+            }
+            service
+                .statement("UPDATE categories SET parent_id = ?2 WHERE parent_id = ?1")?
+                .execute(params![from, into])?;
+
+            let changes = service.recategorize_trns(&from_name, &into_name)?;
+            service
+                .statement("UPDATE OR IGNORE budgets SET category = ?2 WHERE category = ?1")?
+                .execute(params![from_name, into_name])?;
+            service
+                .statement("DELETE FROM budgets WHERE category = ?1")?
+                .execute(params![from_name])?;
+            service
+                .statement("DELETE FROM categories WHERE category_id = ?1")?
+                .execute(params![from])?;
+
+            let rows = row_changes(&before, &service.stored_rows()?);
+            service.record_with_rows(
+                &format!("merge category {from_name} into {into_name}"),
+                &changes,
+                &rows,
+            )
+        })
+    }
+
+    /// Deletes a category not used by transactions together with its budgets,
+    /// as one step of the undo history. Subcategories move to its parent.
+    pub fn del_category(&mut self, category_id: isize) -> Result<()> {
+        self.atomically(|service| {
+            let (name, parent_id) = service.category(category_id)?;
+            let used: i64 = service
+                .statement(&format!(
+                    "SELECT COUNT(DISTINCT transaction_id) FROM ({CATEGORY_USES}) WHERE category = ?1"
+                ))?
+                .query_row(params![name], |row| row.get(0))?;
+            if used > 0 {
+                return Err(eyre!(
+                    "category '{name}' is used by {used} transactions, merge it instead"
+                ));
+            }
+
+            let before = service.stored_rows()?;
+            service
+                .statement("UPDATE categories SET parent_id = ?2 WHERE parent_id = ?1")?
+                .execute(params![category_id, parent_id])?;
+            service
+                .statement("DELETE FROM budgets WHERE category = ?1")?
+                .execute(params![name])?;
+            service
+                .statement("DELETE FROM categories WHERE category_id = ?1")?
+                .execute(params![category_id])?;
+
+            let rows = row_changes(&before, &service.stored_rows()?);
+            service.record_with_rows(&format!("delete category {name}"), &[], &rows)
+        })
+    }
+
+    /// Saves transactions like `put_trns`, first adding the categories they use
+    /// that do not exist yet as top level ones
+    pub fn put_trns_with_categories(&mut self, data: &[Transaction]) -> Result<Vec<isize>> {
+        self.atomically(|service| {
+            for category in data.iter().flat_map(used_categories) {
+                service.register_category(category)?;
+            }
+            service.put_trns(data)
+        })
+    }
+
+    /// Moves transactions and splits in category `from` to `to` without recording
+    /// history. Fails when a reconciled transaction uses `from`.
+    fn recategorize_trns(&mut self, from: &str, to: &str) -> Result<Vec<Change>> {
+        let ids = self
+            .statement(&format!(
+                "SELECT DISTINCT transaction_id FROM ({CATEGORY_USES})
+                 WHERE category = ?1 ORDER BY transaction_id"
+            ))?
+            .query_map(params![from], |row| row.get(0))?
+            .collect::<Result<Vec<isize>, rusqlite::Error>>()?;
+
+        let rename = |category: &mut Option<String>| {
+            if category.as_deref() == Some(from) {
+                *category = Some(to.to_string());
+            }
+        };
+        let mut changes = vec![];
+        for id in ids {
+            let mut trn = self
+                .get_trn(id)?
+                .ok_or_else(|| eyre!("transaction {id} does not exist"))?;
+            if trn.status == TrnStatus::Reconciled {
+                return Err(eyre!(
+                    "category '{from}' is used by reconciled transaction {id}"
+                ));
+            }
+            rename(&mut trn.category);
+            trn.splits
+                .iter_mut()
+                .for_each(|split| rename(&mut split.category));
+            changes.push(self.write_trn(&trn)?);
+        }
+        Ok(changes)
+    }
+
+    #[cfg(test)]
+    pub fn add_categories(&mut self, names: &[&str]) -> Result<()> {
+        for name in names {
+            self.register_category(name)?;
+        }
+        Ok(())
+    }
+
+    /// Fails when the transaction uses a category that does not exist, unless
+    /// its stored version `before` used it already
+    pub(super) fn check_trn_categories(
+        &self,
+        item: &Transaction,
+        before: Option<&Transaction>,
+    ) -> Result<()> {
+        let known: Vec<&String> = before.into_iter().flat_map(used_categories).collect();
+        for category in used_categories(item) {
+            if !known.contains(&category) {
+                self.check_category(category)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails unless the category exists, empty names mean no category
+    pub(super) fn check_category(&self, name: &str) -> Result<()> {
+        match name.trim().is_empty() || self.category_id(name)?.is_some() {
+            true => Ok(()),
+            false => Err(eyre!("category '{name}' does not exist")),
+        }
+    }
+
+    /// Adds a top level category `name` unless it exists already
+    fn register_category(&self, name: &str) -> Result<()> {
+        if !name.trim().is_empty() {
+            self.statement("INSERT OR IGNORE INTO categories (name) VALUES (?1)")?
+                .execute(params![name])?;
+        }
+        Ok(())
+    }
+
+    fn category_id(&self, name: &str) -> Result<Option<isize>> {
+        Ok(self
+            .statement("SELECT category_id FROM categories WHERE name = ?1")?
+            .query_row(params![name], |row| row.get(0))
+            .optional()?)
+    }
+
+    /// Name and parent of the category
+    fn category(&self, category_id: isize) -> Result<(String, Option<isize>)> {
+        self.statement("SELECT name, parent_id FROM categories WHERE category_id = ?1")?
+            .query_row(params![category_id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .optional()?
+            .ok_or_else(|| eyre!("category {category_id} does not exist"))
+    }
+
+    /// Parent, grandparent and so on up to the top level category
+    fn ancestors(&self, category_id: isize) -> Result<Vec<isize>> {
+        let mut ancestors = vec![];
+        let (_, mut parent) = self.category(category_id)?;
+        while let Some(parent_id) = parent {
+            if ancestors.contains(&parent_id) {
+                return Err(eyre!("categories nesting has a loop at {parent_id}"));
+            }
+            ancestors.push(parent_id);
+            parent = self.category(parent_id)?.1;
+        }
+        Ok(ancestors)
+    }
+}
+
+/// Categories of the transaction and of its splits
+fn used_categories(trn: &Transaction) -> impl Iterator<Item = &String> {
+    let splits = trn.splits.iter().map(|split| &split.category);
+    std::iter::once(&trn.category).chain(splits).flatten()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        service::fixtures::{self, account},
+        types::{AccountType, Budget, Transaction, TrnStatus},
+    };
+    use chrono::NaiveDate;
+
+    fn category(name: &str, parent_id: Option<isize>) -> Category {
+        Category {
+            category_id: None,
+            name: name.to_string(),
+            parent_id,
+        }
+    }
+
+    fn names(service: &BudgetService) -> Result<Vec<String>> {
+        let categories = service.get_categories()?;
+        Ok(categories.into_iter().map(|c| c.name).collect())
+    }
+
+    fn service_with_trns(categories: &[&str]) -> Result<BudgetService> {
+        let mut service = fixtures::service(
+            &[
+                account("Cash", AccountType::Asset),
+                account("Food", AccountType::Expense),
+            ],
+            categories,
+        )?;
+        for name in categories {
+            service.put_trn(&Transaction {
+                category: Some(name.to_string()),
+                ..fixtures::trn(Some(1), Some(2), 100)
+            })?;
+        }
+        Ok(service)
+    }
+
+    fn trn_categories(service: &BudgetService) -> Result<Vec<Option<String>>> {
+        let trns = service.get_trns()?;
+        Ok(trns.into_iter().map(|trn| trn.category).collect())
+    }
+
+    #[test]
+    fn hierarchy_order() -> Result<()> {
+        let mut service = service_with_trns(&["Rent"])?;
+        let food = service.put_category(&category("Food", None))?;
+        service.put_category(&category("Snacks", Some(food)))?;
+        service.put_category(&category("Groceries", Some(food)))?;
+
+        assert_eq!(names(&service)?, ["Food", "Groceries", "Snacks", "Rent"]);
+        assert!(service.put_category(&category("Food", None)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn reject_nesting_loop() -> Result<()> {
+        let mut service = service_with_trns(&[])?;
+        let food = service.put_category(&category("Food", None))?;
+        let groceries = service.put_category(&category("Groceries", Some(food)))?;
+
+        let mut looped = service.get_categories()?.remove(0);
+        looped.parent_id = Some(groceries);
+        assert!(service.put_category(&looped).is_err());
+        looped.parent_id = Some(food);
+        assert!(service.put_category(&looped).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn rename_rewrites_transactions() -> Result<()> {
+        let mut service = service_with_trns(&["Fod", "Rent"])?;
+        let mut typo = service.get_categories()?.remove(0);
+        assert_eq!(typo.name, "Fod");
+
+        typo.name = String::from("Food");
+        service.put_category(&typo)?;
+        assert_eq!(
+            trn_categories(&service)?,
+            [Some(String::from("Food")), Some(String::from("Rent"))]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn merge_moves_everything() -> Result<()> {
+        let mut service = service_with_trns(&["Fod", "Food"])?;
+        let typo = service.category_id("Fod")?.unwrap();
+        let food = service.category_id("Food")?.unwrap();
+        let snacks = service.put_category(&category("Snacks", Some(typo)))?;
+        let period = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        for (category, limit) in [("Fod", 1), ("Food", 2)] {
+            service.put_budget(&Budget {
+                category: category.to_string(),
+                period,
+                limit,
+            })?;
+        }
+
+        service.merge_categories(typo, food)?;
+        assert_eq!(names(&service)?, ["Food", "Snacks"]);
+        assert_eq!(service.category(snacks)?.1, Some(food));
+        assert_eq!(
+            trn_categories(&service)?,
+            vec![Some(String::from("Food")); 2]
+        );
+        assert_eq!(
+            service.get_budget_statuses("USD", period)?[0].budget.limit,
+            2
+        );
+        assert_eq!(service.get_category_usage()?[&food], 2);
+        Ok(())
+    }
+
+    #[test]
+    fn merge_into_subcategory() -> Result<()> {
+        let mut service = service_with_trns(&["Food"])?;
+        let food = service.category_id("Food")?.unwrap();
+        let groceries = service.put_category(&category("Groceries", Some(food)))?;
+        service.put_category(&category("Snacks", Some(food)))?;
+
+        service.merge_categories(food, groceries)?;
+        let categories = service.get_categories()?;
+        assert_eq!(names(&service)?, ["Groceries", "Snacks"]);
+        assert_eq!(categories[0].parent_id, None);
+        assert_eq!(categories[1].parent_id, Some(groceries));
+        Ok(())
+    }
+
+    #[test]
+    fn delete_only_unused() -> Result<()> {
+        let mut service = service_with_trns(&["Food"])?;
+        let food = service.category_id("Food")?.unwrap();
+        let spare = service.put_category(&category("Spare", None))?;
+        let parts = service.put_category(&category("Parts", Some(spare)))?;
+        let period = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        service.put_budget(&Budget {
+            category: String::from("Spare"),
+            period,
+            limit: 1,
+        })?;
+
+        assert!(service.del_category(food).is_err());
+        service.del_category(spare)?;
+        assert_eq!(names(&service)?, ["Food", "Parts"]);
+        assert!(service.get_budget_statuses("USD", period)?.is_empty());
+
+        assert_eq!(service.undo()?.as_deref(), Some("delete category Spare"));
+        assert_eq!(names(&service)?, ["Food", "Spare", "Parts"]);
+        assert_eq!(service.category(parts)?.1, Some(spare));
+        assert_eq!(
+            service.get_budget_statuses("USD", period)?[0].budget.limit,
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn recategorizing_is_undoable_and_locked() -> Result<()> {
+        let mut service = service_with_trns(&["Fod", "Food"])?;
+        let typo = service.category_id("Fod")?.unwrap();
+        let food = service.category_id("Food")?.unwrap();
+
+        let snacks = service.put_category(&category("Snacks", Some(typo)))?;
+        let period = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        service.put_budget(&Budget {
+            category: String::from("Fod"),
+            period,
+            limit: 1,
+        })?;
+        let budgets = |service: &BudgetService| -> Result<Vec<(String, i64)>> {
+            let statuses = service.get_budget_statuses("USD", period)?;
+            Ok(statuses
+                .into_iter()
+                .map(|status| (status.budget.category, status.budget.limit))
+                .collect())
+        };
+
+        service.merge_categories(typo, food)?;
+        assert_eq!(
+            trn_categories(&service)?,
+            vec![Some(String::from("Food")); 2]
+        );
+        assert_eq!(budgets(&service)?, [(String::from("Food"), 1)]);
+        assert_eq!(
+            service.undo()?.as_deref(),
+            Some("merge category Fod into Food")
+        );
+        assert_eq!(
+            trn_categories(&service)?,
+            [Some(String::from("Fod")), Some(String::from("Food"))]
+        );
+        assert_eq!(names(&service)?, ["Fod", "Snacks", "Food"]);
+        assert_eq!(service.category(snacks)?.1, Some(typo));
+        assert_eq!(budgets(&service)?, [(String::from("Fod"), 1)]);
+
+        service.redo()?;
+        assert_eq!(names(&service)?, ["Food", "Snacks"]);
+        assert_eq!(budgets(&service)?, [(String::from("Food"), 1)]);
+        service.undo()?;
+
+        let mut renamed = service.get_categories()?.remove(0);
+        renamed.name = String::from("Feed");
+        service.put_category(&renamed)?;
+        assert_eq!(budgets(&service)?, [(String::from("Feed"), 1)]);
+        service.undo()?;
+        assert_eq!(names(&service)?, ["Fod", "Snacks", "Food"]);
+        assert_eq!(budgets(&service)?, [(String::from("Fod"), 1)]);
+        service.del_category(snacks)?;
+
+        service.set_trn_status(&[1], TrnStatus::Cleared)?;
+        service.reconcile(1, NaiveDate::from_ymd_opt(2025, 1, 31).unwrap(), -100)?;
+        service.put_category(&Category {
+            category_id: Some(food),
+            ..category("Groceries", None)
+        })?;
+        let locked = Category {
+            category_id: Some(typo),
+            ..category("Typo", None)
+        };
+        assert!(service.put_category(&locked).is_err());
+        assert!(service.merge_categories(typo, food).is_err());
+        assert_eq!(names(&service)?, ["Fod", "Groceries"]);
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_categories_are_not_created() -> Result<()> {
+        let mut service = service_with_trns(&["Food"])?;
+        let mut trn = service.get_trn(1)?.unwrap();
+        trn.transaction_id = None;
+        trn.category = Some(String::from("Fod"));
+        assert!(service.put_trn(&trn).is_err());
+        assert_eq!(names(&service)?, ["Food"]);
+
+        service.put_trns_with_categories(&[trn])?;
+        assert_eq!(names(&service)?, ["Fod", "Food"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::HashSet;
+
+use chrono::Duration;
+use color_eyre::eyre::{eyre, Result};
+use rusqlite::params;
+
+use super::{ledger::SUSPENSE_PREFIX, trn_from_row, BudgetService, TRN_COLUMNS};
+use crate::types::{Duplicate, Transaction, TrnStatus};
+
+/// How many days apart the bank and the books may date the same transaction
+const DATE_WINDOW_DAYS: i64 = 3;
+
+impl BudgetService {
+    /// Splits new transactions into the ones not stored yet and likely duplicates
+    /// of stored ones. A transaction matches a stored one with the same bank id,
+    /// or else one between the same accounts with the same amount dated within
+    /// a few days and the same description, ignoring case and punctuation. Every stored
+    /// transaction matches at most one new transaction.
+    pub fn find_duplicates(
+        &self,
+        items: Vec<Transaction>,
+    ) -> Result<(Vec<Transaction>, Vec<Duplicate>)> {
+        let mut unique = vec![];
+        let mut duplicates = vec![];
+        let mut matched = HashSet::new();
+        for candidate in items {
+            let existing = self
+                .matching_trns(&candidate)?
+                .into_iter()
+                .find(|trn| trn.transaction_id.is_some_and(|id| matched.insert(id)));
+            match existing {
+                Some(existing) => duplicates.push(Duplicate {
+                    candidate,
+                    existing,
+                }),
+                None => unique.push(candidate),
+            }
+        }
+        Ok((unique, duplicates))
+    }
+
+    /// Completes the stored transaction of a duplicate with what the new one adds:
+    /// the bank id, missing category and description, and the cleared status
+    /// when the new one comes from a statement
+    pub fn merge_duplicate(&mut self, duplicate: &Duplicate) -> Result<()> {
+        let Duplicate {
+            candidate,
+            existing,
+        } = duplicate;
+        let mut merged = existing.clone();
+        merged.external_id = merged.external_id.or(candidate.external_id.clone());
+        merged.category = merged.category.or(candidate.category.clone());
+        merged.description = merged.description.or(candidate.description.clone());
+        if candidate.external_id.is_some() && merged.status == TrnStatus::Uncleared {
+            merged.status = TrnStatus::Cleared;
+        }
+        if &merged != existing {
+            self.put_trns_with_categories(&[merged])?;
+        }
+        Ok(())
+    }
+
+    /// Stored transactions the candidate may duplicate, best matches first
+    fn matching_trns(&self, candidate: &Transaction) -> Result<Vec<Transaction>> {
+        let mut found = vec![];
+        // bank ids are unique only within one bank account, so they count on the
+        // side of the statement account, never on a shared suspense side
+        if let Some(external_id) = &candidate.external_id {
+            let mut stmt = self.statement(&format!(
+                "WITH suspense AS (
+                    SELECT account_id FROM accounts WHERE name LIKE '{SUSPENSE_PREFIX}-%'
+                 )
+                 SELECT {TRN_COLUMNS} FROM fin_transaction
+                 WHERE external_id = ?1
+                    AND (
+                        debit_acc_id = ?2 AND ?2 NOT IN suspense
+                        OR credit_acc_id = ?3 AND ?3 NOT IN suspense
+                    )
+                 ORDER BY transaction_id"
+            ))?;
+            let rows = stmt.query_map(
+                params![external_id, candidate.debit_acc_id, candidate.credit_acc_id],
+                trn_from_row,
+            )?;
+            found = rows.collect::<Result<Vec<Transaction>, rusqlite::Error>>()?;
+        }
+
+        let window = Duration::days(DATE_WINDOW_DAYS);
+        let from = candidate
+            .timestamp
+            .checked_sub_signed(window)
+            .ok_or_else(|| eyre!("date {} is out of range", candidate.timestamp))?;
+        let to = candidate
+            .timestamp
+            .checked_add_signed(window)
+            .ok_or_else(|| eyre!("date {} is out of range", candidate.timestamp))?;
+        // suspense accounts stand in for any account on their side
+        let mut stmt = self.statement(&format!(
+            "WITH suspense AS (
+                SELECT account_id FROM accounts WHERE name LIKE '{SUSPENSE_PREFIX}-%'
+             )
+             SELECT {TRN_COLUMNS} FROM fin_transaction
+             WHERE amount = ?1
+                AND (
+                    debit_acc_id = ?2 AND (
+                        credit_acc_id = ?3
+                        OR credit_acc_id IN suspense OR ?3 IN suspense
+                    )
+                    OR credit_acc_id = ?3 AND (debit_acc_id IN suspense OR ?2 IN suspense)
+                )
+                AND timestamp >= ?4 AND timestamp <= ?5
+                AND (external_id IS NULL OR ?6 IS NULL)
+             ORDER BY abs(julianday(timestamp) - julianday(?7)), transaction_id"
+        ))?;
+        let rows = stmt.query_map(
+            params![
+                candidate.amount,
+                candidate.debit_acc_id,
+                candidate.credit_acc_id,
+                from,
+                to,
+                candidate.external_id,
+                candidate.timestamp,
+            ],
+            trn_from_row,
+        )?;
+        let fingerprint = normalize(candidate.description.as_deref());
+        for trn in rows {
+            let trn = trn?;
+            if normalize(trn.description.as_deref()) == fingerprint && !found.contains(&trn) {
+                found.push(trn);
+            }
+        }
+        Ok(found)
+    }
+}
+
+/// Description reduced to lowercase words of letters and digits
+fn normalize(description: Option<&str>) -> String {
+    description
+        .unwrap_or_default()
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        parsing::local_datetime,
+        service::fixtures::{self, account},
+        types::AccountType,
+    };
+    use chrono::{NaiveDate, NaiveTime};
+
+    fn service() -> Result<BudgetService> {
+        fixtures::service(
+            &[
+                account("Bank", AccountType::Asset),
+                account("Food", AccountType::Expense),
+                account("Card", AccountType::Liability),
+            ],
+            &["Food"],
+        )
+    }
+
+    fn trn(day: u32, credit: u8, amount: i64, description: &str) -> Transaction {
+        Transaction {
+            timestamp: local_datetime(
+                NaiveDate::from_ymd_opt(2025, 1, day).unwrap(),
+                NaiveTime::MIN,
+            )
+            .unwrap(),
+            description: Some(description.to_string()),
+            ..fixtures::trn(Some(credit), Some(2), amount)
+        }
+    }
+
+    fn ids(duplicates: &[Duplicate]) -> Vec<Option<isize>> {
+        duplicates
+            .iter()
+            .map(|duplicate| duplicate.existing.transaction_id)
+            .collect()
+    }
+
+    #[test]
+    fn fingerprint_matches() -> Result<()> {
+        let mut service = service()?;
+        service.put_trns(&[
+            trn(10, 1, 1250, "ACME Market #12"),
+            trn(10, 1, 1250, "ACME Market #12"),
+            trn(20, 1, 900, "Bakery"),
+        ])?;
+
+        let (unique, duplicates) = service.find_duplicates(vec![
+            trn(12, 1, 1250, "acme market 12"),
+            trn(9, 1, 1250, "Acme  Market, 12"),
+            trn(11, 1, 1250, "ACME Market #12"),
+            trn(20, 3, 900, "Bakery"),
+            trn(24, 1, 900, "Bakery"),
+            trn(20, 1, 901, "Bakery"),
+        ])?;
+        assert_eq!(ids(&duplicates), [Some(1), Some(2)]);
+        assert_eq!(unique.len(), 4);
+
+        // imported from a statement, not categorized yet
+        let mut imported = trn(21, 1, 900, "BAKERY");
+        imported.debit_acc_id = Some(service.suspense_account("USD")?);
+        let (_, duplicates) = service.find_duplicates(vec![imported])?;
+        assert_eq!(ids(&duplicates), [Some(3)]);
+        assert_eq!(normalize(Some(" Café—Bar! ")), "café bar");
+        Ok(())
+    }
+
+    #[test]
+    fn bank_ids_match() -> Result<()> {
+        let mut service = service()?;
+        let mut stored = trn(10, 1, 1250, "Coffee");
+        stored.external_id = Some(String::from("FIT-1"));
+        service.put_trn(&stored)?;
+
+        // the bank id wins over a changed amount, differing ids never match
+        let mut changed = trn(25, 1, 999, "Coffee shop");
+        changed.external_id = Some(String::from("FIT-1"));
+        let mut other = trn(10, 1, 1250, "Coffee");
+        other.external_id = Some(String::from("FIT-2"));
+        let (unique, duplicates) = service.find_duplicates(vec![changed, other.clone()])?;
+        assert_eq!(ids(&duplicates), [Some(1)]);
+        assert_eq!(unique, [other]);
+        Ok(())
+    }
+
+    #[test]
+    fn merge_completes_stored() -> Result<()> {
+        let mut service = service()?;
+        let mut stored = trn(10, 1, 1250, "Coffee");
+        stored.category = Some(String::from("Food"));
+        service.put_trn(&stored)?;
+
+        let mut imported = trn(11, 1, 1250, "COFFEE");
+        imported.external_id = Some(String::from("FIT-1"));
+        imported.category = Some(String::from("Drinks"));
+        let (_, duplicates) = service.find_duplicates(vec![imported])?;
+        service.merge_duplicate(&duplicates[0])?;
+
+        let merged = service.get_trn(1)?.unwrap();
+        assert_eq!(merged.external_id.as_deref(), Some("FIT-1"));
+        assert_eq!(merged.category.as_deref(), Some("Food"));
+        assert_eq!(merged.description.as_deref(), Some("Coffee"));
+        assert_eq!(merged.status, TrnStatus::Cleared);
+        Ok(())
+    }
+}
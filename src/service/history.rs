@@ -0,0 +1,466 @@
+use chrono::{Local, NaiveDate};
+use color_eyre::eyre::Result;
+use rusqlite::{params, OptionalExtension};
+
+use serde::{Deserialize, Serialize};
+
+use super::BudgetService;
+use crate::types::Transaction;
+
+/// Number of steps kept for undo
+const HISTORY_LIMIT: i64 = 1000;
+
+/// States of one transaction before and after a command, `None` when it does not exist
+#[derive(Debug)]
+pub(super) struct Change {
+    pub transaction_id: isize,
+    pub before: Option<Transaction>,
+    pub after: Option<Transaction>,
+}
+
+/// Row besides transactions changed by an undoable command, as stored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(super) enum StoredRow {
+    Category {
+        category_id: isize,
+        name: String,
+        parent_id: Option<isize>,
+    },
+    Budget {
+        category: String,
+        period: String,
+        limit: i64,
+    },
+    /// Next occurrence of a schedule, the only field running it changes
+    ScheduleDate {
+        schedule_id: isize,
+        next_date: NaiveDate,
+    },
+}
+
+impl StoredRow {
+    /// Identity of the row, its primary key
+    fn key(&self) -> String {
+        match self {
+            StoredRow::Category { category_id, .. } => format!("category {category_id}"),
+            StoredRow::Budget {
+                category, period, ..
+            } => format!("budget {period} {category}"),
+            StoredRow::ScheduleDate { schedule_id, .. } => format!("schedule {schedule_id}"),
+        }
+    }
+}
+
+/// States of one row besides transactions before and after a command,
+/// `None` when it does not exist
+#[derive(Debug)]
+pub(super) struct RowChange {
+    pub before: Option<StoredRow>,
+    pub after: Option<StoredRow>,
+}
+
+/// Changes turning rows `before` into rows `after`. Added and updated rows come
+/// before deleted ones, so that replaying them in either direction never leaves
+/// a category nested in a missing one.
+pub(super) fn row_changes(before: &[StoredRow], after: &[StoredRow]) -> Vec<RowChange> {
+    let find = |rows: &[StoredRow], key: &str| rows.iter().find(|row| row.key() == key).cloned();
+
+    let updated = after.iter().filter_map(|row| {
+        let old = find(before, &row.key());
+        (old.as_ref() != Some(row)).then(|| RowChange {
+            before: old,
+            after: Some(row.clone()),
+        })
+    });
+    let deleted = before
+        .iter()
+        .rev()
+        .filter(|row| find(after, &row.key()).is_none())
+        .map(|row| RowChange {
+            before: Some(row.clone()),
+            after: None,
+        });
+    updated.chain(deleted).collect()
+}
+
+impl BudgetService {
+    /// Reverts the latest step, returns its description or `None` when there is nothing to undo
+    pub fn undo(&mut self) -> Result<Option<String>> {
+        self.atomically(|service| {
+            let step = service.step(
+                "SELECT step_id, description FROM history
+                 WHERE undone = 0 ORDER BY step_id DESC LIMIT 1",
+            )?;
+            let Some((step_id, description)) = step else {
+                return Ok(None);
+            };
+
+            for change in service.row_changes(step_id)?.iter().rev() {
+                service.restore_row(change.before.as_ref(), change.after.as_ref())?;
+            }
+            for change in service.changes(step_id)?.iter().rev() {
+                service.restore(change.transaction_id, change.before.as_ref())?;
+            }
+            service
+                .statement("UPDATE history SET undone = 1 WHERE step_id = ?1")?
+                .execute(params![step_id])?;
+            Ok(Some(description))
+        })
+    }
+
+    /// Applies again the earliest undone step, returns its description or `None`
+    /// when there is nothing to redo
+    pub fn redo(&mut self) -> Result<Option<String>> {
+        self.atomically(|service| {
+            let step = service.step(
+                "SELECT step_id, description FROM history
+                 WHERE undone = 1 ORDER BY step_id LIMIT 1",
+            )?;
+            let Some((step_id, description)) = step else {
+                return Ok(None);
+            };
+
+            for change in service.row_changes(step_id)? {
+                service.restore_row(change.after.as_ref(), change.before.as_ref())?;
+            }
+            for change in service.changes(step_id)? {
+                service.restore(change.transaction_id, change.after.as_ref())?;
+            }
+            service
+                .statement("UPDATE history SET undone = 0 WHERE step_id = ?1")?
+                .execute(params![step_id])?;
+            Ok(Some(description))
+        })
+    }
+
+    /// Adds a step to the history. Undone steps can not be redone after that
+    pub(super) fn record(&self, description: &str, changes: &[Change]) -> Result<()> {
+        self.record_with_rows(description, changes, &[])
+    }
+
+    /// Adds a step changing categories or budgets besides transactions
+    pub(super) fn record_with_rows(
+        &self,
+        description: &str,
+        changes: &[Change],
+        rows: &[RowChange],
+    ) -> Result<()> {
+        if changes.is_empty() && rows.is_empty() {
+            return Ok(());
+        }
+
+        self.statement("DELETE FROM history WHERE undone = 1")?
+            .execute([])?;
+        let step_id: i64 = self
+            .statement(
+                "INSERT INTO history (description, created) VALUES (?1, ?2) RETURNING step_id",
+            )?
+            .query_row(params![description, Local::now()], |row| row.get(0))?;
+
+        let mut insert = self.statement(
+            "INSERT INTO history_changes (step_id, transaction_id, before, after)
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for change in changes {
+            let before = change
+                .before
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?;
+            let after = change
+                .after
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?;
+            insert.execute(params![step_id, change.transaction_id, before, after])?;
+        }
+        let mut insert = self
+            .statement("INSERT INTO history_rows (step_id, before, after) VALUES (?1, ?2, ?3)")?;
+        for change in rows {
+            let before = change
+                .before
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?;
+            let after = change
+                .after
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?;
+            insert.execute(params![step_id, before, after])?;
+        }
+
+        self.statement("DELETE FROM history WHERE step_id <= ?1")?
+            .execute(params![step_id - HISTORY_LIMIT])?;
+        Ok(())
+    }
+
+    fn step(&self, sql: &str) -> Result<Option<(i64, String)>> {
+        Ok(self
+            .statement(sql)?
+            .query_row([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .optional()?)
+    }
+
+    fn changes(&self, step_id: i64) -> Result<Vec<Change>> {
+        let mut stmt = self.statement(
+            "SELECT transaction_id, before, after FROM history_changes
+             WHERE step_id = ?1 ORDER BY change_id",
+        )?;
+        let rows = stmt
+            .query_map(params![step_id], |row| {
+                Ok((
+                    row.get::<_, isize>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        rows.into_iter()
+            .map(|(transaction_id, before, after)| {
+                Ok(Change {
+                    transaction_id,
+                    before: before.as_deref().map(serde_json::from_str).transpose()?,
+                    after: after.as_deref().map(serde_json::from_str).transpose()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Categories by id, then budgets, as stored
+    pub(super) fn stored_rows(&self) -> Result<Vec<StoredRow>> {
+        let mut rows = self
+            .statement("SELECT category_id, name, parent_id FROM categories ORDER BY category_id")?
+            .query_map([], |row| {
+                Ok(StoredRow::Category {
+                    category_id: row.get(0)?,
+                    name: row.get(1)?,
+                    parent_id: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<StoredRow>, rusqlite::Error>>()?;
+        let budgets = self
+            .statement(
+                "SELECT category, period, limit_amount FROM budgets ORDER BY category, period",
+            )?
+            .query_map([], |row| {
+                Ok(StoredRow::Budget {
+                    category: row.get(0)?,
+                    period: row.get(1)?,
+                    limit: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<StoredRow>, rusqlite::Error>>()?;
+        rows.extend(budgets);
+        Ok(rows)
+    }
+
+    fn row_changes(&self, step_id: i64) -> Result<Vec<RowChange>> {
+        let mut stmt = self.statement(
+            "SELECT before, after FROM history_rows WHERE step_id = ?1 ORDER BY change_id",
+        )?;
+        let rows = stmt
+            .query_map(params![step_id], |row| {
+                Ok((
+                    row.get::<_, Option<String>>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        rows.into_iter()
+            .map(|(before, after)| {
+                Ok(RowChange {
+                    before: before.as_deref().map(serde_json::from_str).transpose()?,
+                    after: after.as_deref().map(serde_json::from_str).transpose()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Puts the row back into `state`, deleting the `other` state of it when
+    /// `state` is `None`. A category keeps its id, so taking back a name used
+    /// by another category meanwhile fails.
+    fn restore_row(&self, state: Option<&StoredRow>, other: Option<&StoredRow>) -> Result<()> {
+        match (state, other) {
+            (
+                Some(StoredRow::Category {
+                    category_id,
+                    name,
+                    parent_id,
+                }),
+                _,
+            ) => {
+                self.statement(
+                    "INSERT INTO categories (category_id, name, parent_id) VALUES (?1, ?2, ?3)
+                     ON CONFLICT (category_id)
+                     DO UPDATE SET name = excluded.name, parent_id = excluded.parent_id",
+                )?
+                .execute(params![category_id, name, parent_id])?;
+            }
+            (
+                Some(StoredRow::Budget {
+                    category,
+                    period,
+                    limit,
+                }),
+                _,
+            ) => {
+                self.statement(
+                    "INSERT OR REPLACE INTO budgets (category, period, limit_amount)
+                     VALUES (?1, ?2, ?3)",
+                )?
+                .execute(params![category, period, limit])?;
+            }
+            (
+                Some(StoredRow::ScheduleDate {
+                    schedule_id,
+                    next_date,
+                }),
+                _,
+            ) => {
+                self.statement("UPDATE schedules SET next_date = ?2 WHERE schedule_id = ?1")?
+                    .execute(params![schedule_id, next_date])?;
+            }
+            (None, Some(StoredRow::Category { category_id, .. })) => {
+                self.statement("DELETE FROM categories WHERE category_id = ?1")?
+                    .execute(params![category_id])?;
+            }
+            (
+                None,
+                Some(StoredRow::Budget {
+                    category, period, ..
+                }),
+            ) => {
+                self.statement("DELETE FROM budgets WHERE category = ?1 AND period = ?2")?
+                    .execute(params![category, period])?;
+            }
+            (None, Some(StoredRow::ScheduleDate { .. }) | None) => {}
+        }
+        Ok(())
+    }
+
+    /// Puts the transaction back into `state`, deleting it when `state` is `None`
+    fn restore(&self, transaction_id: isize, state: Option<&Transaction>) -> Result<()> {
+        match state {
+            Some(trn) => {
+                self.statement(
+                    "INSERT OR REPLACE INTO fin_transaction (
+                        transaction_id, timestamp, credit_acc_id, debit_acc_id,
+                        amount, category, description, status, external_id
+                     )
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                )?
+                .execute(params![
+                    transaction_id,
+                    trn.timestamp,
+                    trn.credit_acc_id,
+                    trn.debit_acc_id,
+                    trn.amount,
+                    trn.category,
+                    trn.description,
+                    trn.status.as_str(),
+                    trn.external_id
+                ])?;
+                self.write_splits(transaction_id, &trn.splits)?;
+            }
+            None => {
+                self.statement("DELETE FROM fin_transaction WHERE transaction_id = ?1")?
+                    .execute(params![transaction_id])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        service::fixtures::{self, account},
+        types::AccountType,
+    };
+
+    fn service() -> Result<BudgetService> {
+        fixtures::service(
+            &[
+                account("Cash", AccountType::Asset),
+                account("Food", AccountType::Expense),
+            ],
+            &["Food"],
+        )
+    }
+
+    fn trn(amount: i64) -> Transaction {
+        Transaction {
+            category: Some(String::from("Food")),
+            ..fixtures::trn(Some(1), Some(2), amount)
+        }
+    }
+
+    fn amounts(service: &BudgetService) -> Result<Vec<i64>> {
+        Ok(service.get_trns()?.iter().map(|trn| trn.amount).collect())
+    }
+
+    #[test]
+    fn undo_and_redo_every_command() -> Result<()> {
+        let mut service = service()?;
+        let id = service.put_trn(&trn(100))?;
+        service.put_trn(&Transaction {
+            transaction_id: Some(id),
+            ..trn(200)
+        })?;
+        service.put_trns(&[trn(1), trn(2)])?;
+        service.del_trns(&[id])?;
+        assert_eq!(amounts(&service)?, [1, 2]);
+
+        assert_eq!(
+            service.undo()?.as_deref(),
+            Some(format!("delete transaction {id}").as_str())
+        );
+        assert_eq!(amounts(&service)?, [200, 1, 2]);
+        assert_eq!(service.undo()?.as_deref(), Some("save 2 transactions"));
+        assert_eq!(service.undo()?.as_deref(), Some("edit transaction 1"));
+        assert_eq!(amounts(&service)?, [100]);
+        assert_eq!(service.undo()?.as_deref(), Some("add transaction 1"));
+        assert_eq!(amounts(&service)?, Vec::<i64>::new());
+        assert_eq!(service.undo()?, None);
+
+        assert_eq!(service.redo()?.as_deref(), Some("add transaction 1"));
+        assert_eq!(service.redo()?.as_deref(), Some("edit transaction 1"));
+        assert_eq!(amounts(&service)?, [200]);
+        Ok(())
+    }
+
+    #[test]
+    fn new_command_drops_redo() -> Result<()> {
+        let mut service = service()?;
+        service.put_trn(&trn(100))?;
+        service.undo()?;
+        service.put_trn(&trn(300))?;
+
+        assert_eq!(service.redo()?, None);
+        assert_eq!(amounts(&service)?, [300]);
+        Ok(())
+    }
+
+    #[test]
+    fn failed_command_is_not_recorded() -> Result<()> {
+        let mut service = service()?;
+        service.put_trn(&trn(100))?;
+        assert!(service
+            .put_trns(&[
+                trn(1),
+                Transaction {
+                    debit_acc_id: Some(9),
+                    ..trn(2)
+                }
+            ])
+            .is_err());
+
+        assert_eq!(service.undo()?.as_deref(), Some("add transaction 1"));
+        assert_eq!(service.undo()?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use color_eyre::eyre::{eyre, Result};
+use rusqlite::{params, types::Type, Row};
+
+use super::BudgetService;
+use crate::types::CsvProfile;
+
+const PROFILE_COLUMNS: &str = "
+    name, delimiter, skip_rows, date_column, date_format, amount_column,
+    outflow_column, description_column, category_column, decimal_separator, invert_sign";
+
+impl BudgetService {
+    pub fn get_import_profiles(&self) -> Result<Vec<CsvProfile>> {
+        let mut stmt = self.statement(&format!(
+            "SELECT {PROFILE_COLUMNS} FROM import_profiles ORDER BY name"
+        ))?;
+
+        let profiles = stmt.query_map([], profile_from_row)?;
+        Ok(profiles.collect::<Result<Vec<CsvProfile>, rusqlite::Error>>()?)
+    }
+
+    pub fn get_import_profile(&self, name: &str) -> Result<CsvProfile> {
+        let mut stmt = self.statement(&format!(
+            "SELECT {PROFILE_COLUMNS} FROM import_profiles WHERE name = ?1"
+        ))?;
+
+        stmt.query_row(params![name], profile_from_row)
+            .map_err(|err| match err {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    eyre!("import profile '{name}' does not exist")
+                }
+                err => err.into(),
+            })
+    }
+
+    /// Creates the profile or replaces the one with the same name
+    pub fn put_import_profile(&mut self, profile: &CsvProfile) -> Result<()> {
+        let mut upsert = self.statement(&format!(
+            "INSERT OR REPLACE INTO import_profiles ({PROFILE_COLUMNS})
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)"
+        ))?;
+
+        upsert.execute(params![
+            profile.name,
+            profile.delimiter.to_string(),
+            profile.skip_rows,
+            profile.date_column,
+            profile.date_format,
+            profile.amount_column,
+            profile.outflow_column,
+            profile.description_column,
+            profile.category_column,
+            profile.decimal_separator.to_string(),
+            profile.invert_sign,
+        ])?;
+        Ok(())
+    }
+}
+
+fn profile_from_row(row: &Row) -> rusqlite::Result<CsvProfile> {
+    let char_at = |idx: usize| -> rusqlite::Result<char> {
+        let value: String = row.get(idx)?;
+        value
+            .chars()
+            .next()
+            .ok_or(rusqlite::Error::InvalidColumnType(idx, value, Type::Text))
+    };
+
+    Ok(CsvProfile {
+        name: row.get(0)?,
+        delimiter: char_at(1)?,
+        skip_rows: row.get(2)?,
+        date_column: row.get(3)?,
+        date_format: row.get(4)?,
+        amount_column: row.get(5)?,
+        outflow_column: row.get(6)?,
+        description_column: row.get(7)?,
+        category_column: row.get(8)?,
+        decimal_separator: char_at(9)?,
+        invert_sign: row.get(10)?,
+    })
+}
@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use color_eyre::eyre::{eyre, Result};
+use rusqlite::{params, OptionalExtension};
+
+use super::BudgetService;
+use crate::{
+    currency::exponent,
+    parsing::format_money,
+    types::{Account, AccountType, Split, Transaction, TrialBalance, TrialBalanceLine},
+};
+
+/// Prefix of the equity accounts that take the other side of one-sided entries
+pub(super) const SUSPENSE_PREFIX: &str = "Imbalance";
+
+impl BudgetService {
+    /// Sum of debits and credits of every account, by account type and name,
+    /// converted to the `base` currency at the rates of `date`.
+    /// Only transactions count, opening balances have no other side.
+    pub fn get_trial_balance(&self, base: &str, date: NaiveDate) -> Result<TrialBalance> {
+        let accounts = self.get_accounts()?;
+        let mut stmt = self.statement(
+            "SELECT
+                a.account_id,
+                COALESCE(SUM(CASE WHEN t.debit_acc_id  = a.account_id THEN t.amount END), 0),
+                COALESCE(SUM(CASE WHEN t.credit_acc_id = a.account_id THEN t.amount END), 0)
+             FROM accounts a
+             LEFT JOIN fin_transaction t
+                ON a.account_id IN (t.debit_acc_id, t.credit_acc_id)
+             GROUP BY a.account_id",
+        )?;
+        let totals = stmt
+            .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?
+            .collect::<Result<HashMap<u8, (i64, i64)>, rusqlite::Error>>()?;
+
+        // debit balance of each currency before and after converting its accounts
+        let mut balances: HashMap<&str, (i64, i64)> = HashMap::new();
+        let mut lines = vec![];
+        for account in &accounts {
+            let (debits, credits) = account
+                .account_id
+                .and_then(|id| totals.get(&id))
+                .copied()
+                .unwrap_or_default();
+            let line = TrialBalanceLine {
+                debits: self.convert(debits, &account.currency, base, date)?,
+                credits: self.convert(credits, &account.currency, base, date)?,
+                account: account.clone(),
+            };
+            let balance = balances.entry(&account.currency).or_default();
+            balance.0 += debits - credits;
+            balance.1 += line.balance();
+            lines.push(line);
+        }
+        let mut rounding = 0;
+        for (currency, (native, converted)) in balances {
+            rounding += self.convert(native, currency, base, date)? - converted;
+        }
+
+        lines.sort_by_key(|line| {
+            let rank = AccountType::ALL
+                .iter()
+                .position(|t| *t == line.account.acc_type);
+            (rank, line.account.name.to_lowercase())
+        });
+        Ok(TrialBalance { lines, rounding })
+    }
+
+    /// Id of the equity account balancing one-sided entries in `currency`, such as
+    /// imported statement records, created when missing
+    pub fn suspense_account(&mut self, currency: &str) -> Result<u8> {
+        let name = format!("{SUSPENSE_PREFIX}-{currency}");
+        let existing = self
+            .statement("SELECT account_id FROM accounts WHERE name = ?1")?
+            .query_row(params![name], |row| row.get(0))
+            .optional()?;
+
+        match existing {
+            Some(account_id) => Ok(account_id),
+            None => self.put_account(&Account {
+                account_id: None,
+                name,
+                acc_type: AccountType::Equity,
+                currency: currency.to_string(),
+                opening_balance: 0,
+            }),
+        }
+    }
+
+    /// Checks the transaction is a complete double entry: money leaves one
+    /// existing account and enters another one in the same currency, and its
+    /// splits add up to the amount
+    pub(super) fn validate_trn(&self, item: &Transaction) -> Result<()> {
+        let (Some(credit), Some(debit)) = (item.credit_acc_id, item.debit_acc_id) else {
+            return Err(eyre!("transaction needs both a debit and a credit account"));
+        };
+        if credit == debit {
+            return Err(eyre!(
+                "transaction can not move money from account {credit} to itself"
+            ));
+        }
+
+        let mut currency = self.statement("SELECT currency FROM accounts WHERE account_id = ?1")?;
+        let mut currencies = vec![];
+        for account_id in [debit, credit] {
+            match currency
+                .query_row(params![account_id], |row| row.get::<_, String>(0))
+                .optional()?
+            {
+                Some(code) => currencies.push(code),
+                None => return Err(eyre!("account {account_id} does not exist")),
+            }
+        }
+        if currencies[0] != currencies[1] {
+            return Err(eyre!(
+                "accounts {debit} in {} and {credit} in {} have different currencies, \
+                 transfers between currencies are not supported",
+                currencies[0],
+                currencies[1]
+            ));
+        }
+
+        let split_total = Split::total(&item.splits)?;
+        if !item.splits.is_empty() && split_total != item.amount {
+            return Err(eyre!(
+                "splits add up to {}, not to the amount {}",
+                format_money(split_total, exponent(&currencies[0])),
+                format_money(item.amount, exponent(&currencies[0]))
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        service::fixtures::{self, account, trn},
+        types::ExchangeRate,
+    };
+
+    fn service() -> Result<BudgetService> {
+        fixtures::service(
+            &[
+                account("Food", AccountType::Expense),
+                account("Cash", AccountType::Asset),
+                account("Bank", AccountType::Asset),
+                account("Salary", AccountType::Income),
+            ],
+            &[],
+        )
+    }
+
+    #[test]
+    fn reject_incomplete_entries() -> Result<()> {
+        let mut service = service()?;
+
+        for invalid in [
+            trn(None, Some(1), 100),
+            trn(Some(1), None, 100),
+            trn(Some(2), Some(2), 100),
+            trn(Some(9), Some(2), 100),
+            Transaction {
+                splits: vec![
+                    Split {
+                        category: None,
+                        amount: i64::MAX,
+                        description: None,
+                    };
+                    2
+                ],
+                ..trn(Some(2), Some(1), 100)
+            },
+        ] {
+            assert!(service.put_trn(&invalid).is_err(), "{invalid:?}");
+        }
+        assert!(service.put_trn(&trn(Some(2), Some(1), 100)).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn trial_balance_by_type() -> Result<()> {
+        let mut service = service()?;
+        service.put_trns(&[
+            trn(Some(4), Some(3), 100000),
+            trn(Some(3), Some(2), 20000),
+            trn(Some(2), Some(1), 1500),
+        ])?;
+
+        let balance = service.get_trial_balance("USD", NaiveDate::MAX)?;
+        let summary: Vec<_> = balance
+            .lines
+            .iter()
+            .map(|line| (line.account.name.as_str(), line.debits, line.credits))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("Bank", 100000, 20000),
+                ("Cash", 20000, 1500),
+                ("Salary", 0, 100000),
+                ("Food", 1500, 0),
+            ]
+        );
+        assert_eq!(balance.rounding, 0);
+        assert_eq!(balance.debits(), balance.credits());
+        Ok(())
+    }
+
+    #[test]
+    fn trial_balance_in_base_currency() -> Result<()> {
+        let mut service = service()?;
+        for name in ["Euro cash", "Euro food"] {
+            service.put_account(&Account {
+                account_id: None,
+                name: name.to_string(),
+                acc_type: AccountType::Asset,
+                currency: String::from("EUR"),
+                opening_balance: 0,
+            })?;
+        }
+        assert!(service.put_trn(&trn(Some(5), Some(1), 1000)).is_err());
+        service.put_trns(&[trn(Some(5), Some(6), 1000), trn(Some(2), Some(1), 500)])?;
+
+        let day = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+        assert!(service.get_trial_balance("USD", day).is_err());
+        service.put_rates(&[ExchangeRate {
+            date: day,
+            currency: String::from("EUR"),
+            quote: String::from("USD"),
+            rate: 1.1,
+        }])?;
+
+        let balance = service.get_trial_balance("USD", day)?;
+        let totals = |name: &str| {
+            let line = balance
+                .lines
+                .iter()
+                .find(|line| line.account.name == name)
+                .unwrap();
+            (line.debits, line.credits)
+        };
+        assert_eq!(totals("Euro cash"), (0, 1100));
+        assert_eq!(totals("Euro food"), (1100, 0));
+        assert_eq!(totals("Food"), (500, 0));
+        assert_eq!(balance.rounding, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn trial_balance_shows_rounding() -> Result<()> {
+        let mut service = service()?;
+        for name in ["Euro cash", "Euro food", "Euro fun"] {
+            service.put_account(&Account {
+                account_id: None,
+                name: name.to_string(),
+                acc_type: AccountType::Asset,
+                currency: String::from("EUR"),
+                opening_balance: 0,
+            })?;
+        }
+        service.put_trns(&[trn(Some(5), Some(6), 1), trn(Some(5), Some(7), 1)])?;
+        let day = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+        service.put_rates(&[ExchangeRate {
+            date: day,
+            currency: String::from("EUR"),
+            quote: String::from("USD"),
+            rate: 1.5,
+        }])?;
+
+        // 1.5 cents round up on each debit, the 3 cents of credits are exact
+        let balance = service.get_trial_balance("USD", day)?;
+        assert_eq!(balance.rounding, -1);
+        assert_eq!((balance.debits(), balance.credits()), (4, 4));
+        Ok(())
+    }
+
+    #[test]
+    fn suspense_account_is_reused() -> Result<()> {
+        let mut service = service()?;
+        let suspense = service.suspense_account("USD")?;
+
+        assert_eq!(service.suspense_account("USD")?, suspense);
+        assert_ne!(service.suspense_account("EUR")?, suspense);
+        assert_eq!(service.get_accounts()?.len(), 6);
+        Ok(())
+    }
+}
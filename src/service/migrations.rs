@@ -0,0 +1,455 @@
+use color_eyre::eyre::{eyre, Result};
+use rusqlite::Connection;
+
+/// Schema changes, applied in order. Step `i` upgrades the database from
+/// version `i` to `i + 1`, the version is kept in `PRAGMA user_version`.
+/// Never edit released steps, append new ones instead.
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema
+    "CREATE TABLE IF NOT EXISTS fin_transaction (
+        transaction_id  INTEGER PRIMARY KEY AUTOINCREMENT,
+        timestamp       TEXT    NULL,
+        credit_acc_id   INTEGER NULL,
+        debit_acc_id    INTEGER NULL,
+        amount          INTEGER NULL,
+        category        TEXT    NULL,
+        description     TEXT    NULL
+    ) STRICT;",
+    // 2: accounts, with placeholders for ids already used by transactions
+    "CREATE TABLE IF NOT EXISTS accounts (
+        account_id      INTEGER PRIMARY KEY AUTOINCREMENT,
+        name            TEXT    NOT NULL UNIQUE,
+        acc_type        TEXT    NOT NULL,
+        currency        TEXT    NOT NULL,
+        opening_balance INTEGER NOT NULL DEFAULT 0
+    ) STRICT;
+
+    INSERT INTO accounts (account_id, name, acc_type, currency)
+    SELECT acc_id, 'Account ' || acc_id, 'Asset', 'USD'
+    FROM (
+        SELECT credit_acc_id AS acc_id FROM fin_transaction
+        UNION
+        SELECT debit_acc_id FROM fin_transaction
+    )
+    WHERE acc_id IS NOT NULL
+      AND acc_id NOT IN (SELECT account_id FROM accounts);
+
+    CREATE TABLE fin_transaction_new (
+        transaction_id  INTEGER PRIMARY KEY AUTOINCREMENT,
+        timestamp       TEXT    NULL,
+        credit_acc_id   INTEGER NULL REFERENCES accounts (account_id),
+        debit_acc_id    INTEGER NULL REFERENCES accounts (account_id),
+        amount          INTEGER NULL,
+        category        TEXT    NULL,
+        description     TEXT    NULL
+    ) STRICT;
+
+    INSERT INTO fin_transaction_new
+    SELECT transaction_id, timestamp, credit_acc_id, debit_acc_id, amount, category, description
+    FROM fin_transaction;
+
+    DROP TABLE fin_transaction;
+    ALTER TABLE fin_transaction_new RENAME TO fin_transaction;",
+    // 3: column mapping profiles for CSV statement import
+    "CREATE TABLE import_profiles (
+        name               TEXT    PRIMARY KEY,
+        delimiter          TEXT    NOT NULL,
+        skip_rows          INTEGER NOT NULL,
+        date_column        INTEGER NOT NULL,
+        date_format        TEXT    NOT NULL,
+        amount_column      INTEGER NOT NULL,
+        outflow_column     INTEGER NULL,
+        description_column INTEGER NOT NULL,
+        category_column    INTEGER NULL,
+        decimal_separator  TEXT    NOT NULL,
+        invert_sign        INTEGER NOT NULL
+    ) STRICT;
+
+    INSERT INTO import_profiles VALUES ('default', ',', 1, 1, '%Y-%m-%d', 2, NULL, 3, NULL, '.', 0);",
+    // 4: monthly budgets per category, period is 'YYYY-MM'
+    "CREATE TABLE budgets (
+        category        TEXT    NOT NULL,
+        period          TEXT    NOT NULL,
+        limit_amount    INTEGER NOT NULL,
+        PRIMARY KEY (category, period)
+    ) STRICT;",
+    // 5: category hierarchy, filled with the categories already in use
+    "CREATE TABLE categories (
+        category_id     INTEGER PRIMARY KEY AUTOINCREMENT,
+        name            TEXT    NOT NULL UNIQUE,
+        parent_id       INTEGER NULL REFERENCES categories (category_id)
+    ) STRICT;
+
+    INSERT INTO categories (name)
+    SELECT category FROM fin_transaction WHERE category IS NOT NULL AND category <> ''
+    UNION
+    SELECT category FROM budgets
+    ORDER BY 1;",
+    // 6: undo history of transaction changes, states are JSON, NULL when absent
+    "CREATE TABLE history (
+        step_id         INTEGER PRIMARY KEY AUTOINCREMENT,
+        description     TEXT    NOT NULL,
+        created         TEXT    NOT NULL,
+        undone          INTEGER NOT NULL DEFAULT 0
+    ) STRICT;
+
+    CREATE TABLE history_changes (
+        change_id       INTEGER PRIMARY KEY AUTOINCREMENT,
+        step_id         INTEGER NOT NULL REFERENCES history (step_id) ON DELETE CASCADE,
+        transaction_id  INTEGER NOT NULL,
+        before          TEXT    NULL,
+        after           TEXT    NULL
+    ) STRICT;",
+    // 7: indexes for sorting transactions by any column of the transactions table
+    "CREATE INDEX fin_transaction_timestamp ON fin_transaction (timestamp, transaction_id);
+    CREATE INDEX fin_transaction_category
+        ON fin_transaction (category COLLATE NOCASE, transaction_id);
+    CREATE INDEX fin_transaction_description
+        ON fin_transaction (description COLLATE NOCASE, transaction_id);
+    CREATE INDEX fin_transaction_amount ON fin_transaction (amount, transaction_id);",
+    // 8: splits of transactions across categories, and the amount booked to each
+    // category by every transaction, taken from its splits when it has any
+    "CREATE TABLE postings (
+        posting_id      INTEGER PRIMARY KEY AUTOINCREMENT,
+        transaction_id  INTEGER NOT NULL
+                        REFERENCES fin_transaction (transaction_id) ON DELETE CASCADE,
+        category        TEXT    NULL,
+        amount          INTEGER NOT NULL,
+        description     TEXT    NULL
+    ) STRICT;
+
+    CREATE INDEX postings_transaction ON postings (transaction_id);
+
+    CREATE VIEW category_amounts AS
+    SELECT t.transaction_id, t.timestamp, t.category, t.amount
+    FROM fin_transaction t
+    WHERE NOT EXISTS (SELECT 1 FROM postings p WHERE p.transaction_id = t.transaction_id)
+    UNION ALL
+    SELECT t.transaction_id, t.timestamp, p.category, p.amount
+    FROM fin_transaction t JOIN postings p ON p.transaction_id = t.transaction_id;",
+    // 9: recurring transactions
+    "CREATE TABLE schedules (
+        schedule_id     INTEGER PRIMARY KEY AUTOINCREMENT,
+        frequency       TEXT    NOT NULL,
+        day_of_month    INTEGER NULL,
+        next_date       TEXT    NOT NULL,
+        end_date        TEXT    NULL,
+        paused          INTEGER NOT NULL DEFAULT 0,
+        credit_acc_id   INTEGER NOT NULL REFERENCES accounts (account_id),
+        debit_acc_id    INTEGER NOT NULL REFERENCES accounts (account_id),
+        amount          INTEGER NOT NULL,
+        category        TEXT    NULL,
+        description     TEXT    NULL
+    ) STRICT;",
+    // 10: dated exchange rates, one unit of `currency` costs `rate` units of `quote`
+    "CREATE TABLE exchange_rates (
+        date            TEXT    NOT NULL,
+        currency        TEXT    NOT NULL,
+        quote           TEXT    NOT NULL,
+        rate            REAL    NOT NULL,
+        PRIMARY KEY (currency, quote, date)
+    ) STRICT;",
+    // 11: reconciliation state of transactions
+    "ALTER TABLE fin_transaction ADD COLUMN status TEXT NOT NULL DEFAULT 'Uncleared';",
+    // 12: ids given to transactions by banks, to recognize them in later statements
+    "ALTER TABLE fin_transaction ADD COLUMN external_id TEXT NULL;
+    CREATE INDEX fin_transaction_external_id ON fin_transaction (external_id);",
+    // 13: one-sided transactions get the suspense account of their currency as
+    // the missing side, the same way statement imports fill it
+    "INSERT INTO accounts (name, acc_type, currency)
+    SELECT DISTINCT 'Imbalance-' || a.currency, 'Equity', a.currency
+    FROM fin_transaction t
+    JOIN accounts a ON a.account_id = COALESCE(t.credit_acc_id, t.debit_acc_id)
+    WHERE (t.credit_acc_id IS NULL OR t.debit_acc_id IS NULL)
+      AND 'Imbalance-' || a.currency NOT IN (SELECT name FROM accounts);
+
+    UPDATE fin_transaction
+    SET credit_acc_id = (
+        SELECT s.account_id
+        FROM accounts a JOIN accounts s ON s.name = 'Imbalance-' || a.currency
+        WHERE a.account_id = fin_transaction.debit_acc_id
+    )
+    WHERE credit_acc_id IS NULL AND debit_acc_id IS NOT NULL;
+
+    UPDATE fin_transaction
+    SET debit_acc_id = (
+        SELECT s.account_id
+        FROM accounts a JOIN accounts s ON s.name = 'Imbalance-' || a.currency
+        WHERE a.account_id = fin_transaction.credit_acc_id
+    )
+    WHERE debit_acc_id IS NULL AND credit_acc_id IS NOT NULL;",
+    // 14: monthly and yearly schedules keep the day of their next occurrence
+    "UPDATE schedules
+    SET day_of_month = CAST(strftime('%d', next_date) AS INTEGER)
+    WHERE day_of_month IS NULL AND frequency IN ('Monthly', 'Yearly');",
+    // 15: categories and budgets changed by undoable commands, states are JSON
+    "CREATE TABLE history_rows (
+        change_id       INTEGER PRIMARY KEY AUTOINCREMENT,
+        step_id         INTEGER NOT NULL REFERENCES history (step_id) ON DELETE CASCADE,
+        before          TEXT    NULL,
+        after           TEXT    NULL
+    ) STRICT;",
+];
+
+pub const LATEST_VERSION: usize = MIGRATIONS.len();
+
+pub fn version(conn: &Connection) -> Result<usize> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    Ok(version.try_into()?)
+}
+
+/// Brings database schema up to `LATEST_VERSION`.
+/// Refuses to touch a database created by a newer version of the application.
+pub fn migrate(conn: &mut Connection) -> Result<()> {
+    migrate_to(conn, LATEST_VERSION)
+}
+
+fn migrate_to(conn: &mut Connection, target: usize) -> Result<()> {
+    let current = version(conn)?;
+    if current > LATEST_VERSION {
+        return Err(eyre!(
+            "database schema version {current} is newer than supported version {LATEST_VERSION}"
+        ));
+    }
+
+    // Tables are rebuilt while migrating, so references are checked manually
+    conn.pragma_update(None, "foreign_keys", false)?;
+    let result = MIGRATIONS
+        .iter()
+        .enumerate()
+        .take(target)
+        .skip(current)
+        .try_for_each(|(step, sql)| apply(conn, step + 1, sql));
+    conn.pragma_update(None, "foreign_keys", true)?;
+
+    result
+}
+
+fn apply(conn: &mut Connection, version: usize, sql: &str) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(sql)
+        .map_err(|err| eyre!("migration to version {version} failed: {err}"))?;
+
+    let violations: i64 =
+        tx.query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| {
+            row.get(0)
+        })?;
+    if violations > 0 {
+        return Err(eyre!(
+            "migration to version {version} left {violations} broken references"
+        ));
+    }
+
+    tx.pragma_update(None, "user_version", version as i64)?;
+    Ok(tx.commit()?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Sample data, each entry added at the schema version that made room for it,
+    /// the way users fill their databases while upgrading. Version 0 is a database
+    /// created before versioning, where `fin_transaction` existed but
+    /// `user_version` was never set. Migrations adding tables append their data here.
+    const SAMPLE: &[(usize, &str)] = &[
+        (
+            0,
+            "CREATE TABLE fin_transaction (
+                transaction_id  INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp       TEXT    NULL,
+                credit_acc_id   INTEGER NULL,
+                debit_acc_id    INTEGER NULL,
+                amount          INTEGER NULL,
+                category        TEXT    NULL,
+                description     TEXT    NULL
+            ) STRICT;
+            INSERT INTO fin_transaction (timestamp, credit_acc_id, debit_acc_id, amount, category)
+            VALUES
+                ('2001-02-03T04:05:06+00:00', 1, 2, 100, 'Food'),
+                ('2002-02-03T04:05:06+00:00', 1, NULL, 200, NULL);",
+        ),
+        (
+            2,
+            "UPDATE accounts SET name = 'Cash' WHERE account_id = 1;
+            UPDATE accounts SET name = 'Food', acc_type = 'Expense' WHERE account_id = 2;",
+        ),
+        (
+            3,
+            "INSERT INTO import_profiles
+            VALUES ('bank', ';', 0, 2, '%d.%m.%Y', 4, NULL, 3, NULL, ',', 1);",
+        ),
+        (4, "INSERT INTO budgets VALUES ('Food', '2001-02', 5000);"),
+        (
+            5,
+            "INSERT INTO categories (name, parent_id) VALUES ('Groceries', 1);
+            UPDATE fin_transaction SET category = 'Groceries' WHERE transaction_id = 2;",
+        ),
+        (
+            6,
+            "INSERT INTO history (description, created) VALUES ('delete transaction 3', '2002-02-04');
+            INSERT INTO history_changes (step_id, transaction_id, before, after)
+            VALUES (1, 3, '{}', NULL);",
+        ),
+        (
+            8,
+            "INSERT INTO postings (transaction_id, category, amount)
+            VALUES (2, 'Groceries', 150), (2, 'Food', 50);",
+        ),
+        (
+            9,
+            "INSERT INTO schedules (frequency, next_date, credit_acc_id, debit_acc_id, amount)
+            VALUES ('Monthly', '2002-03-31', 1, 2, 90000);",
+        ),
+        (
+            10,
+            "INSERT INTO exchange_rates VALUES ('2002-01-01', 'EUR', 'USD', 1.08);",
+        ),
+        (
+            11,
+            "UPDATE fin_transaction SET status = 'Reconciled' WHERE transaction_id = 1;
+            UPDATE fin_transaction SET status = 'Cleared' WHERE transaction_id = 2;",
+        ),
+        (
+            12,
+            "UPDATE fin_transaction SET external_id = 'FIT-1' WHERE transaction_id = 1;",
+        ),
+        (
+            15,
+            "INSERT INTO history_rows (step_id, before, after) VALUES (1, '{}', NULL);",
+        ),
+    ];
+
+    /// Database at schema `version` holding the sample data added up to it
+    fn fixture(version: usize) -> Result<Connection> {
+        let mut conn = Connection::open_in_memory()?;
+        for (since, sql) in SAMPLE.iter().take_while(|(since, _)| *since <= version) {
+            migrate_to(&mut conn, *since)?;
+            conn.execute_batch(sql)?;
+        }
+        migrate_to(&mut conn, version)?;
+        Ok(conn)
+    }
+
+    fn count(conn: &Connection, table: &str) -> Result<i64> {
+        Ok(
+            conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })?,
+        )
+    }
+
+    #[test]
+    fn upgrade_from_every_version() -> Result<()> {
+        for from in 0..=LATEST_VERSION {
+            let mut conn = fixture(from)?;
+            migrate(&mut conn)?;
+
+            assert_eq!(version(&conn)?, LATEST_VERSION, "upgrade from {from}");
+            assert_eq!(count(&conn, "fin_transaction")?, 2, "upgrade from {from}");
+            // the suspense account takes the missing side of the one-sided transaction
+            assert_eq!(count(&conn, "accounts")?, 3, "upgrade from {from}");
+            let one_sided: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM fin_transaction
+                 WHERE credit_acc_id IS NULL OR debit_acc_id IS NULL",
+                [],
+                |row| row.get(0),
+            )?;
+            assert_eq!(one_sided, 0, "upgrade from {from}");
+            assert_eq!(count(&conn, "pragma_foreign_key_check")?, 0);
+
+            if from >= 9 {
+                let day: u32 =
+                    conn.query_row("SELECT day_of_month FROM schedules", [], |row| row.get(0))?;
+                assert_eq!(day, 31, "upgrade from {from}");
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn upgrade_unversioned_database_with_accounts() -> Result<()> {
+        // layout created by `create_db` before versioning was introduced
+        let mut conn = Connection::open_in_memory()?;
+        conn.execute_batch(
+            "CREATE TABLE accounts (
+                account_id      INTEGER PRIMARY KEY AUTOINCREMENT,
+                name            TEXT    NOT NULL UNIQUE,
+                acc_type        TEXT    NOT NULL,
+                currency        TEXT    NOT NULL,
+                opening_balance INTEGER NOT NULL DEFAULT 0
+            ) STRICT;
+            CREATE TABLE fin_transaction (
+                transaction_id  INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp       TEXT    NULL,
+                credit_acc_id   INTEGER NULL REFERENCES accounts (account_id),
+                debit_acc_id    INTEGER NULL REFERENCES accounts (account_id),
+                amount          INTEGER NULL,
+                category        TEXT    NULL,
+                description     TEXT    NULL
+            ) STRICT;
+            INSERT INTO accounts (name, acc_type, currency) VALUES ('Cash', 'Asset', 'USD');
+            INSERT INTO fin_transaction (credit_acc_id, debit_acc_id, amount)
+            VALUES (1, NULL, 100);",
+        )?;
+
+        migrate(&mut conn)?;
+        assert_eq!(version(&conn)?, LATEST_VERSION);
+        assert_eq!(count(&conn, "accounts")?, 2);
+        assert_eq!(count(&conn, "fin_transaction")?, 1);
+        let debit: u8 = conn.query_row(
+            "SELECT a.account_id FROM fin_transaction t
+             JOIN accounts a ON a.account_id = t.debit_acc_id AND a.name = 'Imbalance-USD'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(debit, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn categories_from_existing_data() -> Result<()> {
+        let mut conn = fixture(4)?;
+        conn.execute_batch("INSERT INTO budgets VALUES ('Rent', '2001-02', 9000);")?;
+        migrate(&mut conn)?;
+
+        let names = conn
+            .prepare("SELECT name FROM categories ORDER BY category_id")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        assert_eq!(names, ["Food", "Rent"]);
+        Ok(())
+    }
+
+    #[test]
+    fn migrate_is_idempotent() -> Result<()> {
+        let mut conn = fixture(LATEST_VERSION)?;
+        migrate(&mut conn)?;
+        migrate(&mut conn)?;
+
+        assert_eq!(version(&conn)?, LATEST_VERSION);
+        assert_eq!(count(&conn, "fin_transaction")?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn refuse_newer_database() -> Result<()> {
+        let mut conn = Connection::open_in_memory()?;
+        conn.pragma_update(None, "user_version", LATEST_VERSION as i64 + 1)?;
+
+        assert!(migrate(&mut conn).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn failed_step_is_rolled_back() -> Result<()> {
+        let mut conn = fixture(0)?;
+        conn.execute_batch("CREATE TABLE accounts (account_id INTEGER PRIMARY KEY)")?;
+
+        // step 2 can not insert into the incompatible `accounts` table
+        assert!(migrate(&mut conn).is_err());
+        assert_eq!(version(&conn)?, 1);
+        assert_eq!(count(&conn, "fin_transaction")?, 2);
+        Ok(())
+    }
+}
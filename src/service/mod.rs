@@ -0,0 +1,932 @@
+use crate::{
+    currency::parse_currency,
+    parsing::local_datetime,
+    types::{Account, Transaction, TrnColumn, TrnFilter, TrnSort, TrnStatus},
+};
+use chrono::NaiveTime;
+use color_eyre::eyre::{self, eyre, Result};
+use history::Change;
+use rusqlite::{
+    params, params_from_iter, types::Type, CachedStatement, Connection, OptionalExtension, Row,
+    ToSql,
+};
+
+use std::{cell, collections::HashMap, path::Path};
+
+mod budgets;
+mod categories;
+mod duplicates;
+mod history;
+mod import_profiles;
+mod ledger;
+mod migrations;
+mod rates;
+mod reconcile;
+mod reports;
+mod schedules;
+mod splits;
+
+type LazyCell<T> = cell::LazyCell<T, Box<dyn FnOnce() -> T>>;
+
+pub struct BudgetService {
+    connection: LazyCell<Result<Connection>>,
+}
+
+impl BudgetService {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let connection = LazyCell::new(Box::new(|| -> Result<Connection> {
+            let mut conn = Connection::open(path)?;
+            migrations::migrate(&mut conn)?;
+            Ok(conn)
+        }));
+        Self { connection }
+    }
+
+    fn connection(&self) -> Result<&Connection> {
+        match self.connection.as_ref() {
+            Ok(conn) => Ok(conn),
+            Err(err) => Err(eyre!("failed to connect: {err}")),
+        }
+    }
+
+    fn statement(&self, sql: &str) -> Result<CachedStatement<'_>> {
+        Ok(self.connection()?.prepare_cached(sql)?)
+    }
+
+    /// Runs `f` inside of a database transaction, rolling back everything it did on error.
+    /// Calls can be nested, an inner failure only rolls back the inner part.
+    fn atomically<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.connection()?.execute_batch("SAVEPOINT atomically")?;
+        let result = f(self);
+        let end = match result {
+            Ok(_) => "RELEASE atomically",
+            Err(_) => "ROLLBACK TO atomically; RELEASE atomically",
+        };
+        self.connection()?.execute_batch(end)?;
+        result
+    }
+
+    #[cfg(test)]
+    pub fn get_trns(&self) -> Result<Vec<Transaction>> {
+        self.find_trns(&TrnFilter::default())
+    }
+
+    pub fn find_trns(&self, filter: &TrnFilter) -> Result<Vec<Transaction>> {
+        let (conditions, values) = trn_conditions(filter)?;
+        let sql = format!(
+            "SELECT {TRN_COLUMNS} FROM fin_transaction{conditions}{}",
+            order_by(filter.sort)
+        );
+
+        let mut stmt = self.statement(&sql)?;
+        let tr_iter = stmt.query_map(params_from_iter(values), trn_from_row)?;
+
+        let mut transactions = tr_iter.collect::<Result<Vec<Transaction>, rusqlite::Error>>()?;
+        self.load_splits(&mut transactions)?;
+        Ok(transactions)
+    }
+
+    /// At most `limit` of the transactions `find_trns` returns, skipping the first `offset`
+    pub fn find_trns_window(
+        &self,
+        filter: &TrnFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Transaction>> {
+        let (conditions, mut values) = trn_conditions(filter)?;
+        let sql = format!(
+            "SELECT {TRN_COLUMNS} FROM fin_transaction{conditions}{} LIMIT ? OFFSET ?",
+            order_by(filter.sort)
+        );
+        values.push(Box::new(i64::try_from(limit)?));
+        values.push(Box::new(i64::try_from(offset)?));
+
+        let mut stmt = self.statement(&sql)?;
+        let tr_iter = stmt.query_map(params_from_iter(values), trn_from_row)?;
+
+        let mut transactions = tr_iter.collect::<Result<Vec<Transaction>, rusqlite::Error>>()?;
+        self.load_splits(&mut transactions)?;
+        Ok(transactions)
+    }
+
+    /// Number of transactions matching the filter
+    pub fn count_trns(&self, filter: &TrnFilter) -> Result<usize> {
+        let (conditions, values) = trn_conditions(filter)?;
+        let sql = format!("SELECT COUNT(*) FROM fin_transaction{conditions}");
+
+        let count: i64 = self
+            .statement(&sql)?
+            .query_row(params_from_iter(values), |row| row.get(0))?;
+        Ok(count.try_into()?)
+    }
+
+    /// Transaction with its splits
+    pub fn get_trn(&self, transaction_id: isize) -> Result<Option<Transaction>> {
+        let trn = self
+            .statement(&format!(
+                "SELECT {TRN_COLUMNS} FROM fin_transaction WHERE transaction_id = ?1"
+            ))?
+            .query_row(params![transaction_id], trn_from_row)
+            .optional()?;
+
+        let mut transactions = Vec::from_iter(trn);
+        self.load_splits(&mut transactions)?;
+        Ok(transactions.pop())
+    }
+
+    /// Deletes transactions as one step of the undo history, returns number of
+    /// deleted ones, missing ids are skipped
+    pub fn del_trns(&mut self, items: &[isize]) -> Result<usize> {
+        let description = match items {
+            [id] => format!("delete transaction {id}"),
+            _ => format!("delete {} transactions", items.len()),
+        };
+
+        self.atomically(|service| {
+            let mut changes = vec![];
+            for &id in items {
+                if let Some(before) = service.get_trn(id)? {
+                    check_unlocked(&before)?;
+                    service
+                        .statement("DELETE FROM fin_transaction WHERE transaction_id = ?1")?
+                        .execute(params![id])?;
+                    changes.push(Change {
+                        transaction_id: id,
+                        before: Some(before),
+                        after: None,
+                    });
+                }
+            }
+            service.record(&description, &changes)?;
+            Ok(changes.len())
+        })
+    }
+
+    /// Creates or updates the transaction together with its splits as one step
+    /// of the undo history
+    pub fn put_trn(&mut self, item: &Transaction) -> Result<isize> {
+        let description = match item.transaction_id {
+            Some(id) => format!("edit transaction {id}"),
+            None => String::from("add transaction"),
+        };
+
+        self.atomically(|service| {
+            let change = service.write_trn(item)?;
+            let trn_id = change.transaction_id;
+            let description = match change.before {
+                Some(_) => description,
+                None => format!("{description} {trn_id}"),
+            };
+            service.record(&description, &[change])?;
+            Ok(trn_id)
+        })
+    }
+
+    /// Saves all transactions or none of them as one step of the undo history,
+    /// returning their ids
+    pub fn put_trns(&mut self, data: &[Transaction]) -> Result<Vec<isize>> {
+        self.atomically(|service| {
+            let changes = data
+                .iter()
+                .enumerate()
+                .map(|(idx, item)| {
+                    service
+                        .write_trn(item)
+                        .map_err(|err| eyre!("transaction #{}: {err}", idx + 1))
+                })
+                .collect::<Result<Vec<Change>>>()?;
+
+            service.record(&format!("save {} transactions", data.len()), &changes)?;
+            Ok(changes.iter().map(|change| change.transaction_id).collect())
+        })
+    }
+
+    /// Creates or updates the transaction without recording history
+    fn write_trn(&mut self, item: &Transaction) -> Result<Change> {
+        self.validate_trn(item)?;
+        let before = match item.transaction_id {
+            Some(id) => Some(
+                self.get_trn(id)?
+                    .ok_or_else(|| eyre!("transaction {id} does not exist"))?,
+            ),
+            None => None,
+        };
+        if let Some(before) = &before {
+            check_unlocked(before)?;
+        }
+        self.check_trn_categories(item, before.as_ref())?;
+        let trn_id = match item.transaction_id {
+            Some(transaction_id) => {
+                let mut update = self.statement(
+                    "UPDATE fin_transaction 
+                     SET 
+                        timestamp     = ?2,
+                        credit_acc_id = ?3,
+                        debit_acc_id  = ?4,
+                        amount        = ?5,
+                        category      = ?6,
+                        description   = ?7,
+                        status        = ?8,
+                        external_id   = ?9
+                    WHERE
+                        transaction_id = ?1
+                    ",
+                )?;
+
+                update.execute(params![
+                    transaction_id,
+                    item.timestamp,
+                    item.credit_acc_id,
+                    item.debit_acc_id,
+                    item.amount,
+                    item.category,
+                    item.description,
+                    item.status.as_str(),
+                    item.external_id
+                ])?;
+                transaction_id
+            }
+            None => {
+                let mut insert = self.statement(
+                    "INSERT INTO fin_transaction (
+                       timestamp, credit_acc_id, debit_acc_id,
+                       amount, category, description, status, external_id
+                    )
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                    RETURNING transaction_id
+                    ",
+                )?;
+                insert.query_row(
+                    params![
+                        item.timestamp,
+                        item.credit_acc_id,
+                        item.debit_acc_id,
+                        item.amount,
+                        item.category,
+                        item.description,
+                        item.status.as_str(),
+                        item.external_id
+                    ],
+                    |row| row.get(0),
+                )?
+            }
+        };
+        self.write_splits(trn_id, &item.splits)?;
+
+        Ok(Change {
+            transaction_id: trn_id,
+            before,
+            after: Some(Transaction {
+                transaction_id: Some(trn_id),
+                ..item.clone()
+            }),
+        })
+    }
+
+    pub fn get_accounts(&self) -> Result<Vec<Account>> {
+        let mut stmt = self.statement(
+            "SELECT account_id, name, acc_type, currency, opening_balance
+             FROM accounts
+             ORDER BY account_id",
+        )?;
+
+        let acc_iter = stmt.query_map([], |row| {
+            let acc_type: String = row.get(2)?;
+            Ok(Account {
+                account_id: row.get(0)?,
+                name: row.get(1)?,
+                acc_type: acc_type.parse().map_err(|err: eyre::Report| {
+                    rusqlite::Error::FromSqlConversionFailure(2, Type::Text, err.into())
+                })?,
+                currency: row.get(3)?,
+                opening_balance: row.get(4)?,
+            })
+        })?;
+
+        Ok(acc_iter.collect::<Result<Vec<Account>, rusqlite::Error>>()?)
+    }
+
+    /// Creates or updates an account. The currency of an account with transactions
+    /// can not change, their amounts are in it.
+    pub fn put_account(&mut self, item: &Account) -> Result<u8> {
+        let currency = parse_currency(&item.currency)?;
+        let acc_id = match item.account_id {
+            Some(account_id) => {
+                let used: bool = self
+                    .statement(
+                        "SELECT EXISTS (
+                            SELECT 1 FROM fin_transaction t JOIN accounts a
+                                ON a.account_id IN (t.debit_acc_id, t.credit_acc_id)
+                            WHERE a.account_id = ?1 AND a.currency != ?2
+                         )",
+                    )?
+                    .query_row(params![account_id, currency], |row| row.get(0))?;
+                if used {
+                    return Err(eyre!(
+                        "can not change currency of account {account_id} with transactions"
+                    ));
+                }
+
+                let mut update = self.statement(
+                    "UPDATE accounts
+                     SET
+                        name            = ?2,
+                        acc_type        = ?3,
+                        currency        = ?4,
+                        opening_balance = ?5
+                     WHERE
+                        account_id = ?1
+                    ",
+                )?;
+
+                let updated = update.execute(params![
+                    account_id,
+                    item.name,
+                    item.acc_type.as_str(),
+                    currency,
+                    item.opening_balance
+                ])?;
+                if updated == 0 {
+                    return Err(eyre!("account {account_id} does not exist"));
+                }
+                account_id
+            }
+            None => {
+                let mut insert = self.statement(
+                    "INSERT INTO accounts (name, acc_type, currency, opening_balance)
+                     VALUES (?1, ?2, ?3, ?4)
+                     RETURNING account_id
+                    ",
+                )?;
+                insert.query_row(
+                    params![
+                        item.name,
+                        item.acc_type.as_str(),
+                        currency,
+                        item.opening_balance
+                    ],
+                    |row| row.get(0),
+                )?
+            }
+        };
+
+        Ok(acc_id)
+    }
+
+    /// Deletes account. Fails if any transaction still refers to it
+    pub fn del_account(&mut self, account_id: u8) -> Result<()> {
+        let mut delete = self.statement("DELETE FROM accounts WHERE account_id = ?1")?;
+        delete.execute(params![account_id])?;
+        Ok(())
+    }
+
+    /// Current balance of every account: opening balance plus debits minus credits
+    pub fn get_balances(&self) -> Result<HashMap<u8, i64>> {
+        let mut stmt = self.statement(
+            "SELECT
+                a.account_id,
+                a.opening_balance
+                    + COALESCE(SUM(CASE WHEN t.debit_acc_id  = a.account_id THEN t.amount END), 0)
+                    - COALESCE(SUM(CASE WHEN t.credit_acc_id = a.account_id THEN t.amount END), 0)
+             FROM accounts a
+             LEFT JOIN fin_transaction t
+                ON a.account_id IN (t.debit_acc_id, t.credit_acc_id)
+             GROUP BY a.account_id",
+        )?;
+
+        let balances = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(balances.collect::<Result<HashMap<u8, i64>, rusqlite::Error>>()?)
+    }
+
+    /// Balance of the account right after each of the given transactions, by transaction id.
+    /// Transactions add up in date order, debits increase the balance and credits decrease it.
+    pub fn get_running_balances(
+        &self,
+        account_id: u8,
+        transaction_ids: &[isize],
+    ) -> Result<HashMap<isize, i64>> {
+        let mut stmt = self.statement(
+            "SELECT transaction_id, balance FROM (
+                SELECT
+                    t.transaction_id,
+                    a.opening_balance + SUM(
+                        CASE WHEN t.debit_acc_id  = a.account_id THEN t.amount ELSE 0 END
+                        - CASE WHEN t.credit_acc_id = a.account_id THEN t.amount ELSE 0 END
+                    ) OVER (ORDER BY t.timestamp, t.transaction_id) AS balance
+                FROM accounts a
+                JOIN fin_transaction t ON a.account_id IN (t.debit_acc_id, t.credit_acc_id)
+                WHERE a.account_id = ?1
+             )
+             WHERE transaction_id IN (SELECT value FROM json_each(?2))",
+        )?;
+
+        let ids = serde_json::to_string(transaction_ids)?;
+        let balances = stmt.query_map(params![account_id, ids], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+        Ok(balances.collect::<Result<HashMap<isize, i64>, rusqlite::Error>>()?)
+    }
+}
+
+const TRN_COLUMNS: &str = "
+    transaction_id, timestamp, credit_acc_id, debit_acc_id, amount, category, description, status,
+    external_id";
+
+/// Reconciled transactions can not be changed or deleted
+fn check_unlocked(trn: &Transaction) -> Result<()> {
+    match (trn.status, trn.transaction_id) {
+        (TrnStatus::Reconciled, Some(id)) => Err(eyre!(
+            "transaction {id} is reconciled and can not be changed"
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// `WHERE` clause selecting the transactions matching the filter, with its parameters
+fn trn_conditions(filter: &TrnFilter) -> Result<(String, Vec<Box<dyn ToSql>>)> {
+    let mut conditions = vec![];
+    let mut values: Vec<Box<dyn ToSql>> = vec![];
+
+    if let Some(from) = filter.from {
+        conditions.push("timestamp >= ?");
+        values.push(Box::new(local_datetime(from, NaiveTime::MIN)?));
+    }
+    if let Some(to) = filter.to {
+        let next_day = to
+            .succ_opt()
+            .ok_or_else(|| eyre!("date {to} is out of range"))?;
+        conditions.push("timestamp < ?");
+        values.push(Box::new(local_datetime(next_day, NaiveTime::MIN)?));
+    }
+    if let Some(account_id) = filter.account_id {
+        conditions.push("(credit_acc_id = ? OR debit_acc_id = ?)");
+        values.push(Box::new(account_id));
+        values.push(Box::new(account_id));
+    }
+    for word in &filter.text {
+        conditions.push(
+            "(instr(lower(COALESCE(description, '')), ?) > 0
+              OR instr(lower(COALESCE(category, '')), ?) > 0)",
+        );
+        values.push(Box::new(word.to_lowercase()));
+        values.push(Box::new(word.to_lowercase()));
+    }
+    if let Some(amount) = filter.amount_from {
+        conditions.push("amount >= ?");
+        values.push(Box::new(amount));
+    }
+    if let Some(amount) = filter.amount_to {
+        conditions.push("amount <= ?");
+        values.push(Box::new(amount));
+    }
+
+    if conditions.is_empty() {
+        return Ok((String::new(), values));
+    }
+    Ok((format!(" WHERE {}", conditions.join(" AND ")), values))
+}
+
+/// `ORDER BY` clause, ties are broken by id so equal rows keep a stable order
+fn order_by(sort: TrnSort) -> String {
+    let column = match sort.column {
+        TrnColumn::Date => "timestamp",
+        TrnColumn::Category => "category COLLATE NOCASE",
+        TrnColumn::Description => "description COLLATE NOCASE",
+        TrnColumn::Amount => "amount",
+    };
+    let direction = if sort.descending { "DESC" } else { "ASC" };
+    format!(" ORDER BY {column} {direction}, transaction_id {direction}")
+}
+
+fn trn_from_row(row: &Row) -> rusqlite::Result<Transaction> {
+    let status: String = row.get(7)?;
+    Ok(Transaction {
+        transaction_id: row.get(0)?,
+        timestamp: row.get(1)?,
+        credit_acc_id: row.get(2)?,
+        debit_acc_id: row.get(3)?,
+        amount: row.get(4)?,
+        category: row.get(5)?,
+        description: row.get(6)?,
+        splits: vec![],
+        status: status.parse().map_err(|err: eyre::Report| {
+            rusqlite::Error::FromSqlConversionFailure(7, Type::Text, err.into())
+        })?,
+        external_id: row.get(8)?,
+    })
+}
+
+/// Services and transactions shared by the tests of the service modules
+#[cfg(test)]
+mod fixtures {
+    use super::BudgetService;
+    use crate::types::{Account, AccountType, Transaction, TrnStatus};
+    use chrono::{Local, TimeZone};
+    use color_eyre::eyre::Result;
+
+    /// New account in USD without an opening balance
+    pub fn account(name: &str, acc_type: AccountType) -> Account {
+        Account {
+            account_id: None,
+            name: name.to_string(),
+            acc_type,
+            currency: String::from("USD"),
+            opening_balance: 0,
+        }
+    }
+
+    /// In-memory service with the accounts, numbered from 1 in the given order,
+    /// and the categories
+    pub fn service(accounts: &[Account], categories: &[&str]) -> Result<BudgetService> {
+        let mut service = BudgetService::new(":memory:");
+        for account in accounts {
+            service.put_account(account)?;
+        }
+        service.add_categories(categories)?;
+        Ok(service)
+    }
+
+    /// Uncategorized transaction moving `amount` from `credit` to `debit` on 2 January 2025
+    pub fn trn(credit: Option<u8>, debit: Option<u8>, amount: i64) -> Transaction {
+        Transaction {
+            transaction_id: None,
+            credit_acc_id: credit,
+            debit_acc_id: debit,
+            timestamp: Local.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap(),
+            category: None,
+            amount,
+            description: None,
+            splits: vec![],
+            status: TrnStatus::Uncleared,
+            external_id: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::types::AccountType;
+    use chrono::{Datelike, Duration, Local, NaiveDate, TimeZone};
+    use fixtures::account;
+    use rand::Rng;
+
+    const TEST_DB: &str = ":memory:";
+
+    fn generate_random_string(length: usize) -> String {
+        const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+        let mut rng = rand::rng();
+
+        (0..length)
+            .map(|_| {
+                let idx = rng.random_range(0..CHARSET.len());
+                CHARSET[idx] as char
+            })
+            .collect()
+    }
+
+    /// Service with two accounts, ids 1 and 2, used by `random_trn`
+    fn test_service() -> Result<BudgetService> {
+        fixtures::service(
+            &[
+                account("Cash", AccountType::Asset),
+                account("Food", AccountType::Expense),
+            ],
+            &["Food", "Shopping"],
+        )
+    }
+
+    fn random_trn() -> Transaction {
+        let mut rng = rand::rng();
+        let credit_acc_id = rng.random_range(1..=2);
+        Transaction {
+            transaction_id: None,
+            credit_acc_id: Some(credit_acc_id),
+            debit_acc_id: Some(3 - credit_acc_id),
+            timestamp: Local::now() + Duration::days(rng.random_range(0..30)),
+            amount: rng.random_range(i64::MIN..i64::MAX),
+            category: None,
+            description: Some(generate_random_string(10)),
+            splits: vec![],
+            status: TrnStatus::Uncleared,
+            external_id: None,
+        }
+    }
+
+    fn fake_data() -> Vec<Transaction> {
+        (0..2usize).map(|_| random_trn()).collect()
+    }
+
+    #[test]
+    fn create_service() -> Result<()> {
+        let service = BudgetService::new(TEST_DB);
+        let trans = service.get_trns()?;
+
+        assert_eq!(trans, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn insert_transactions() -> Result<()> {
+        let fake_data = fake_data();
+        let mut service = test_service()?;
+        if let Err(err) = service.put_trns(&fake_data) {
+            println!("{err:?}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn update_transaction() -> Result<()> {
+        let mut service = test_service()?;
+        let mut trn = random_trn();
+
+        trn.transaction_id = Some(service.put_trn(&trn)?);
+
+        let content = service.get_trns()?;
+        assert_eq!(content, vec![trn]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn update_existing_transaction() -> Result<()> {
+        let mut service = test_service()?;
+        let mut trn = random_trn();
+        trn.transaction_id = Some(service.put_trn(&trn)?);
+
+        trn.amount = 1250;
+        trn.description = None;
+        assert_eq!(service.put_trn(&trn)?, trn.transaction_id.unwrap());
+        assert_eq!(service.get_trns()?, vec![trn.clone()]);
+
+        trn.transaction_id = Some(trn.transaction_id.unwrap() + 1);
+        assert!(service.put_trn(&trn).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn insert_transactions_atomically() -> Result<()> {
+        let mut service = test_service()?;
+        let mut data = fake_data();
+        data[1].debit_acc_id = Some(3);
+
+        assert!(service.put_trns(&data).is_err());
+        assert_eq!(service.get_trns()?, vec![]);
+
+        data[1].credit_acc_id = Some(1);
+        data[1].debit_acc_id = Some(2);
+        assert_eq!(service.put_trns(&data)?.len(), 2);
+        assert_eq!(service.get_trns()?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn filter_transactions() -> Result<()> {
+        let mut service = test_service()?;
+        service.put_account(&account("Bank", AccountType::Asset))?;
+
+        let dates = [(2025, 1, 31), (2025, 2, 1), (2025, 2, 28), (2025, 3, 1)];
+        for (idx, (y, m, d)) in dates.into_iter().enumerate() {
+            let mut trn = random_trn();
+            trn.timestamp = Local.with_ymd_and_hms(y, m, d, 23, 59, 0).unwrap();
+            trn.credit_acc_id = Some(if idx % 2 == 0 { 1 } else { 3 });
+            trn.debit_acc_id = Some(2);
+            service.put_trn(&trn)?;
+        }
+
+        let february = TrnFilter {
+            from: NaiveDate::from_ymd_opt(2025, 2, 1),
+            to: NaiveDate::from_ymd_opt(2025, 2, 28),
+            ..Default::default()
+        };
+        let found = service.find_trns(&february)?;
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|trn| trn.timestamp.month() == 2));
+
+        let bank = TrnFilter {
+            account_id: Some(3),
+            ..february
+        };
+        assert_eq!(service.find_trns(&bank)?.len(), 1);
+        assert_eq!(service.find_trns(&TrnFilter::default())?.len(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn search_transactions() -> Result<()> {
+        let mut service = test_service()?;
+        let samples = [
+            (Some("Food"), Some("Corner SHOP"), 1250),
+            (Some("Shopping"), None, 9900),
+            (None, Some("Salary"), -100000),
+        ];
+        for (category, description, amount) in samples {
+            let mut trn = random_trn();
+            trn.category = category.map(String::from);
+            trn.description = description.map(String::from);
+            trn.amount = amount;
+            service.put_trn(&trn)?;
+        }
+
+        let search = |text: &[&str], amount_from, amount_to| TrnFilter {
+            text: text.iter().map(|word| word.to_string()).collect(),
+            amount_from,
+            amount_to,
+            ..Default::default()
+        };
+        assert_eq!(service.find_trns(&search(&["shop"], None, None))?.len(), 2);
+        assert_eq!(
+            service
+                .find_trns(&search(&["shop", "food"], None, None))?
+                .len(),
+            1
+        );
+        assert_eq!(
+            service.find_trns(&search(&[], Some(0), Some(9900)))?.len(),
+            2
+        );
+        assert_eq!(
+            service.find_trns(&search(&["sal"], None, Some(-1)))?.len(),
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn sort_transactions() -> Result<()> {
+        let mut service = test_service()?;
+        let samples = [(3, "b", 100), (1, "C", 300), (2, "a", 200), (1, "d", 300)];
+        for (day, description, amount) in samples {
+            let mut trn = random_trn();
+            trn.timestamp = Local.with_ymd_and_hms(2025, 1, day, 0, 0, 0).unwrap();
+            trn.description = Some(description.to_string());
+            trn.amount = amount;
+            service.put_trn(&trn)?;
+        }
+
+        let sorted = |column, descending| -> Result<Vec<String>> {
+            let filter = TrnFilter {
+                sort: TrnSort { column, descending },
+                ..Default::default()
+            };
+            let trns = service.find_trns(&filter)?;
+            Ok(trns.into_iter().filter_map(|trn| trn.description).collect())
+        };
+        assert_eq!(sorted(TrnColumn::Date, false)?, ["C", "d", "a", "b"]);
+        assert_eq!(sorted(TrnColumn::Date, true)?, ["b", "a", "d", "C"]);
+        assert_eq!(sorted(TrnColumn::Description, false)?, ["a", "b", "C", "d"]);
+        assert_eq!(sorted(TrnColumn::Amount, true)?, ["d", "C", "a", "b"]);
+        Ok(())
+    }
+
+    #[test]
+    fn windowed_transactions() -> Result<()> {
+        let mut service = test_service()?;
+        let trns: Vec<Transaction> = (1..=5)
+            .map(|day| Transaction {
+                timestamp: Local.with_ymd_and_hms(2025, 1, day, 0, 0, 0).unwrap(),
+                amount: day as i64,
+                ..random_trn()
+            })
+            .collect();
+        service.put_trns(&trns)?;
+
+        let filter = TrnFilter {
+            amount_from: Some(2),
+            ..Default::default()
+        };
+        let amounts = |offset, limit| -> Result<Vec<i64>> {
+            let window = service.find_trns_window(&filter, offset, limit)?;
+            Ok(window.into_iter().map(|trn| trn.amount).collect())
+        };
+        assert_eq!(service.count_trns(&filter)?, 4);
+        assert_eq!(amounts(1, 2)?, [3, 4]);
+        assert_eq!(amounts(3, 10)?, [5]);
+        assert_eq!(amounts(9, 10)?, Vec::<i64>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn sort_cycle() {
+        let by_amount = TrnSort::default().cycle(TrnColumn::Amount);
+        assert_eq!(by_amount.column, TrnColumn::Amount);
+        assert!(!by_amount.descending);
+        assert!(by_amount.cycle(TrnColumn::Amount).descending);
+        assert_eq!(
+            by_amount.cycle(TrnColumn::Amount).cycle(TrnColumn::Amount),
+            TrnSort::default()
+        );
+        assert!(TrnSort::default().cycle(TrnColumn::Date).descending);
+    }
+
+    #[test]
+    fn delete_transactions() -> Result<()> {
+        let mut service = test_service()?;
+        let mut trn1 = random_trn();
+        let mut trn2 = random_trn();
+
+        trn1.transaction_id = Some(service.put_trn(&trn1)?);
+        trn2.transaction_id = Some(service.put_trn(&trn2)?);
+
+        dbg!(&trn1, &trn2);
+
+        assert!(service.get_trns()?.len() == 2);
+
+        let _ = service.del_trns(&[trn1.transaction_id.unwrap()]);
+
+        assert_eq!(service.get_trns()?, vec![trn2]);
+        Ok(())
+    }
+
+    #[test]
+    fn accounts_crud() -> Result<()> {
+        let mut service = BudgetService::new(TEST_DB);
+        let mut cash = account("Cash", AccountType::Asset);
+
+        cash.account_id = Some(service.put_account(&cash)?);
+        assert_eq!(service.get_accounts()?, vec![cash.clone()]);
+
+        cash.name = String::from("Wallet");
+        cash.opening_balance = 1000;
+        service.put_account(&cash)?;
+        assert_eq!(service.get_accounts()?, vec![cash.clone()]);
+
+        assert!(service
+            .put_account(&account("Wallet", AccountType::Asset))
+            .is_err());
+
+        service.del_account(cash.account_id.unwrap())?;
+        assert_eq!(service.get_accounts()?, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn transactions_require_accounts() -> Result<()> {
+        let mut service = test_service()?;
+        let mut trn = random_trn();
+        trn.credit_acc_id = Some(1);
+        trn.debit_acc_id = Some(3);
+        assert!(service.put_trn(&trn).is_err());
+
+        trn.debit_acc_id = Some(2);
+        service.put_trn(&trn)?;
+        assert!(service.del_account(2).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn account_balances() -> Result<()> {
+        let mut service = test_service()?;
+        let mut cash = service.get_accounts()?.remove(0);
+        cash.opening_balance = 10000;
+        service.put_account(&cash)?;
+
+        for amount in [1500, 250] {
+            let mut trn = random_trn();
+            trn.credit_acc_id = Some(1);
+            trn.debit_acc_id = Some(2);
+            trn.amount = amount;
+            service.put_trn(&trn)?;
+        }
+
+        let balances = service.get_balances()?;
+        assert_eq!(balances[&1], 10000 - 1750);
+        assert_eq!(balances[&2], 1750);
+        Ok(())
+    }
+
+    #[test]
+    fn running_balances() -> Result<()> {
+        let mut service = test_service()?;
+        let mut cash = service.get_accounts()?.remove(0);
+        cash.opening_balance = 10000;
+        service.put_account(&cash)?;
+
+        // added out of date order, the last one is an income into cash
+        let mut ids = vec![];
+        for (day, credit, debit, amount) in [(3, 1, 2, 100), (1, 1, 2, 500), (2, 2, 1, 40)] {
+            ids.push(service.put_trn(&Transaction {
+                timestamp: Local.with_ymd_and_hms(2025, 1, day, 0, 0, 0).unwrap(),
+                credit_acc_id: Some(credit),
+                debit_acc_id: Some(debit),
+                amount,
+                ..random_trn()
+            })?);
+        }
+
+        let cash = service.get_running_balances(1, &ids)?;
+        assert_eq!(cash[&ids[1]], 9500);
+        assert_eq!(cash[&ids[2]], 9540);
+        assert_eq!(cash[&ids[0]], 9440);
+
+        let food = service.get_running_balances(2, &ids[..2])?;
+        assert_eq!(food.len(), 2);
+        assert_eq!(food[&ids[1]], 500);
+        assert_eq!(food[&ids[0]], 560);
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+use chrono::NaiveDate;
+use color_eyre::eyre::{eyre, Result};
+use rusqlite::{params, OptionalExtension};
+
+use super::BudgetService;
+use crate::{
+    currency::{exponent, parse_currency},
+    types::ExchangeRate,
+};
+
+impl BudgetService {
+    /// Saves exchange rates all at once, replacing rates of the same currencies and date
+    pub fn put_rates(&mut self, rates: &[ExchangeRate]) -> Result<usize> {
+        self.atomically(|service| {
+            let mut upsert = service.statement(
+                "INSERT OR REPLACE INTO exchange_rates (date, currency, quote, rate)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for rate in rates {
+                if !(rate.rate.is_finite() && rate.rate > 0.0) {
+                    return Err(eyre!(
+                        "invalid rate {} of {} in {}",
+                        rate.rate,
+                        rate.currency,
+                        rate.quote
+                    ));
+                }
+                upsert.execute(params![
+                    rate.date,
+                    parse_currency(&rate.currency)?,
+                    parse_currency(&rate.quote)?,
+                    rate.rate
+                ])?;
+            }
+            Ok(rates.len())
+        })
+    }
+
+    /// Units of `to` one unit of `from` costs on `date`, by the latest rate known then.
+    /// Rates quoted the other way around are inverted.
+    pub fn get_rate(&self, from: &str, to: &str, date: NaiveDate) -> Result<f64> {
+        if from == to {
+            return Ok(1.0);
+        }
+        self.statement(
+            "SELECT rate FROM (
+                SELECT date, rate FROM exchange_rates
+                WHERE currency = ?1 AND quote = ?2 AND date <= ?3
+                UNION ALL
+                SELECT date, 1.0 / rate FROM exchange_rates
+                WHERE currency = ?2 AND quote = ?1 AND date <= ?3
+             )
+             ORDER BY date DESC LIMIT 1",
+        )?
+        .query_row(params![from, to, date], |row| row.get(0))
+        .optional()?
+        .ok_or_else(|| eyre!("no exchange rate from {from} to {to} on or before {date}"))
+    }
+
+    /// Converts minor units of `from` into minor units of `to` at the rate of `date`
+    pub fn convert(&self, amount: i64, from: &str, to: &str, date: NaiveDate) -> Result<i64> {
+        if from == to {
+            return Ok(amount);
+        }
+        let rate = self.get_rate(from, to, date)?;
+        let scale = 10_f64.powi(exponent(to) as i32 - exponent(from) as i32);
+        Ok((amount as f64 * rate * scale).round() as i64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn rate(date: NaiveDate, currency: &str, quote: &str, rate: f64) -> ExchangeRate {
+        ExchangeRate {
+            date,
+            currency: currency.to_string(),
+            quote: quote.to_string(),
+            rate,
+        }
+    }
+
+    #[test]
+    fn latest_rate_applies() -> Result<()> {
+        let mut service = BudgetService::new(":memory:");
+        service.put_rates(&[
+            rate(date(2025, 1, 1), "EUR", "USD", 1.04),
+            rate(date(2025, 2, 1), "EUR", "USD", 1.08),
+            rate(date(2025, 1, 15), "USD", "JPY", 150.0),
+        ])?;
+
+        assert_eq!(service.get_rate("EUR", "USD", date(2025, 1, 31))?, 1.04);
+        assert_eq!(service.get_rate("EUR", "USD", date(2025, 3, 1))?, 1.08);
+        assert!(service.get_rate("EUR", "USD", date(2024, 12, 31)).is_err());
+        assert!(service.get_rate("EUR", "JPY", date(2025, 3, 1)).is_err());
+
+        // 10.00 EUR, 1000 JPY
+        assert_eq!(service.convert(1000, "EUR", "USD", date(2025, 2, 1))?, 1080);
+        assert_eq!(service.convert(1000, "JPY", "USD", date(2025, 2, 1))?, 667);
+        assert_eq!(service.convert(1000, "USD", "JPY", date(2025, 2, 1))?, 1500);
+        Ok(())
+    }
+
+    #[test]
+    fn reject_invalid_rates() {
+        let mut service = BudgetService::new(":memory:");
+        for invalid in [
+            rate(date(2025, 1, 1), "EUR", "USD", 0.0),
+            rate(date(2025, 1, 1), "EUR", "USD", f64::NAN),
+            rate(date(2025, 1, 1), "EUR", "XYZ", 1.0),
+        ] {
+            assert!(service.put_rates(&[invalid]).is_err());
+        }
+    }
+}
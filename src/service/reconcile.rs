@@ -0,0 +1,189 @@
+use chrono::{DateTime, Days, Local, NaiveDate, NaiveTime};
+use color_eyre::eyre::{eyre, Result};
+use rusqlite::params;
+
+use super::{history::Change, BudgetService};
+use crate::{
+    currency::exponent,
+    parsing::{format_money, local_datetime},
+    types::TrnStatus,
+};
+
+impl BudgetService {
+    /// Sets the status of transactions as one step of the undo history
+    pub fn set_trn_status(&mut self, items: &[isize], status: TrnStatus) -> Result<()> {
+        let status_name = status.as_str().to_lowercase();
+        let description = match items {
+            [id] => format!("mark transaction {id} {status_name}"),
+            _ => format!("mark {} transactions {status_name}", items.len()),
+        };
+
+        self.atomically(|service| {
+            let changes = service.write_status(items, status)?;
+            service.record(&description, &changes)
+        })
+    }
+
+    /// Balance of the account counting only cleared and reconciled transactions
+    /// up to the end of `date`, as a bank statement of that day should show it
+    pub fn get_cleared_balance(&self, account_id: u8, date: NaiveDate) -> Result<i64> {
+        let balance = self
+            .statement(
+                "SELECT
+                    a.opening_balance
+                        + COALESCE(SUM(CASE WHEN t.debit_acc_id  = a.account_id THEN t.amount END), 0)
+                        - COALESCE(SUM(CASE WHEN t.credit_acc_id = a.account_id THEN t.amount END), 0)
+                 FROM accounts a
+                 LEFT JOIN fin_transaction t
+                    ON a.account_id IN (t.debit_acc_id, t.credit_acc_id)
+                    AND t.status IN ('Cleared', 'Reconciled')
+                    AND t.timestamp < ?2
+                 WHERE a.account_id = ?1
+                 GROUP BY a.account_id",
+            )?
+            .query_row(params![account_id, day_end(date)?], |row| row.get(0))?;
+        Ok(balance)
+    }
+
+    /// Locks the cleared transactions of the account up to `date` once their balance
+    /// matches the statement balance, as one step of the undo history.
+    /// Returns how many transactions were reconciled.
+    pub fn reconcile(&mut self, account_id: u8, date: NaiveDate, balance: i64) -> Result<usize> {
+        let cleared = self.get_cleared_balance(account_id, date)?;
+        if cleared != balance {
+            let currency: String = self
+                .statement("SELECT currency FROM accounts WHERE account_id = ?1")?
+                .query_row(params![account_id], |row| row.get(0))?;
+            let format = |amount| format_money(amount, exponent(&currency));
+            return Err(eyre!(
+                "cleared balance {} differs from statement balance {}",
+                format(cleared),
+                format(balance)
+            ));
+        }
+
+        self.atomically(|service| {
+            let ids = service
+                .statement(
+                    "SELECT transaction_id FROM fin_transaction
+                     WHERE ?1 IN (credit_acc_id, debit_acc_id)
+                        AND status = 'Cleared' AND timestamp < ?2
+                     ORDER BY transaction_id",
+                )?
+                .query_map(params![account_id, day_end(date)?], |row| row.get(0))?
+                .collect::<Result<Vec<isize>, rusqlite::Error>>()?;
+
+            let changes = service.write_status(&ids, TrnStatus::Reconciled)?;
+            service.record(&format!("reconcile account {account_id}"), &changes)?;
+            Ok(changes.len())
+        })
+    }
+
+    /// Sets the status of transactions without recording history
+    fn write_status(&mut self, items: &[isize], status: TrnStatus) -> Result<Vec<Change>> {
+        let mut changes = vec![];
+        for &id in items {
+            let mut trn = self
+                .get_trn(id)?
+                .ok_or_else(|| eyre!("transaction {id} does not exist"))?;
+            if trn.status != status {
+                trn.status = status;
+                changes.push(self.write_trn(&trn)?);
+            }
+        }
+        Ok(changes)
+    }
+}
+
+/// Start of the day after `date`, timestamps of that date are before it
+fn day_end(date: NaiveDate) -> Result<DateTime<Local>> {
+    let next_day = date
+        .checked_add_days(Days::new(1))
+        .ok_or_else(|| eyre!("date {date} is out of range"))?;
+    local_datetime(next_day, NaiveTime::MIN)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        service::fixtures::{self, account},
+        types::{Account, AccountType, Transaction, TrnFilter},
+    };
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn service() -> Result<BudgetService> {
+        let mut service = fixtures::service(
+            &[
+                Account {
+                    opening_balance: 10000,
+                    ..account("Bank", AccountType::Asset)
+                },
+                account("Food", AccountType::Expense),
+            ],
+            &[],
+        )?;
+        // paid 10.00, 20.00 and 40.00 for food on 1st, 2nd and 3rd of January
+        service.put_trns(
+            &[(1, 1000), (2, 2000), (3, 4000)].map(|(day, amount)| Transaction {
+                timestamp: local_datetime(date(2025, 1, day), NaiveTime::MIN).unwrap(),
+                ..fixtures::trn(Some(1), Some(2), amount)
+            }),
+        )?;
+        Ok(service)
+    }
+
+    fn statuses(service: &BudgetService) -> Result<Vec<TrnStatus>> {
+        let trns = service.find_trns(&TrnFilter::default())?;
+        Ok(trns.iter().map(|trn| trn.status).collect())
+    }
+
+    #[test]
+    fn cleared_balance_by_statement_date() -> Result<()> {
+        let mut service = service()?;
+        assert_eq!(service.get_cleared_balance(1, date(2025, 1, 31))?, 10000);
+
+        service.set_trn_status(&[1, 3], TrnStatus::Cleared)?;
+        assert_eq!(service.get_cleared_balance(1, date(2025, 1, 2))?, 9000);
+        assert_eq!(service.get_cleared_balance(1, date(2025, 1, 3))?, 5000);
+        assert_eq!(service.get_cleared_balance(2, date(2025, 1, 3))?, 5000);
+
+        service.undo()?;
+        assert_eq!(service.get_cleared_balance(1, date(2025, 1, 3))?, 10000);
+        Ok(())
+    }
+
+    #[test]
+    fn reconciled_transactions_are_locked() -> Result<()> {
+        let mut service = service()?;
+        service.set_trn_status(&[1, 2, 3], TrnStatus::Cleared)?;
+        let mismatch = service.reconcile(1, date(2025, 1, 2), 8000).unwrap_err();
+        assert_eq!(
+            mismatch.to_string(),
+            "cleared balance 70.00 differs from statement balance 80.00"
+        );
+        assert_eq!(service.reconcile(1, date(2025, 1, 2), 7000)?, 2);
+        assert_eq!(
+            statuses(&service)?,
+            [
+                TrnStatus::Reconciled,
+                TrnStatus::Reconciled,
+                TrnStatus::Cleared
+            ]
+        );
+
+        let mut reconciled = service.get_trn(1)?.unwrap();
+        reconciled.amount = 1;
+        assert!(service.put_trn(&reconciled).is_err());
+        assert!(service.del_trns(&[1]).is_err());
+        assert!(service.set_trn_status(&[2], TrnStatus::Uncleared).is_err());
+
+        // undo is the way back
+        service.undo()?;
+        assert_eq!(statuses(&service)?, [TrnStatus::Cleared; 3]);
+        Ok(())
+    }
+}
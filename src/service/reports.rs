@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveTime};
+use color_eyre::eyre::{eyre, Result};
+use rusqlite::params;
+
+use super::BudgetService;
+use crate::{
+    parsing::local_datetime,
+    types::{CategorySpending, MonthlyTotals},
+};
+
+impl BudgetService {
+    /// Net amount debited to expense accounts per category from `start` until
+    /// before `end`, converted into `base` at the rates of the last day, largest first
+    pub fn get_spending(
+        &self,
+        base: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<CategorySpending>> {
+        let mut stmt = self.statement(
+            "SELECT
+                c.category,
+                CASE WHEN d.acc_type = 'Expense' THEN d.currency ELSE cr.currency END,
+                SUM(CASE WHEN d.acc_type = 'Expense' THEN c.amount ELSE -c.amount END)
+             FROM category_amounts c
+             JOIN fin_transaction t ON t.transaction_id = c.transaction_id
+             LEFT JOIN accounts d ON d.account_id = t.debit_acc_id
+             LEFT JOIN accounts cr ON cr.account_id = t.credit_acc_id
+             WHERE (COALESCE(d.acc_type, '') = 'Expense') <> (COALESCE(cr.acc_type, '') = 'Expense')
+                AND c.timestamp >= ?1 AND c.timestamp < ?2
+             GROUP BY 1, 2",
+        )?;
+        let rows = stmt
+            .query_map(
+                params![
+                    local_datetime(start, NaiveTime::MIN)?,
+                    local_datetime(end, NaiveTime::MIN)?,
+                ],
+                |row| {
+                    Ok((
+                        row.get::<_, Option<String>>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                },
+            )?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        let rate_date = last_day(end)?;
+        let mut totals: HashMap<Option<String>, i64> = HashMap::new();
+        for (category, currency, amount) in rows {
+            *totals.entry(category).or_default() +=
+                self.convert(amount, &currency, base, rate_date)?;
+        }
+        let mut spending: Vec<CategorySpending> = totals
+            .into_iter()
+            .filter(|&(_, amount)| amount != 0)
+            .map(|(category, amount)| CategorySpending { category, amount })
+            .collect();
+        spending.sort_by(|a, b| b.amount.cmp(&a.amount).then(a.category.cmp(&b.category)));
+        Ok(spending)
+    }
+
+    /// Income and expenses of every month from the one of `start` until the one
+    /// before `end`, converted into `base` at the rates of the last day of each month
+    pub fn get_monthly_totals(
+        &self,
+        base: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<MonthlyTotals>> {
+        let start = start.with_day(1).unwrap_or(start);
+        let mut stmt = self.statement(
+            "SELECT
+                substr(t.timestamp, 1, 7),
+                COALESCE(d.currency, cr.currency),
+                SUM(CASE WHEN cr.acc_type = 'Income' THEN t.amount ELSE 0 END)
+                    - SUM(CASE WHEN d.acc_type = 'Income' THEN t.amount ELSE 0 END),
+                SUM(CASE WHEN d.acc_type = 'Expense' THEN t.amount ELSE 0 END)
+                    - SUM(CASE WHEN cr.acc_type = 'Expense' THEN t.amount ELSE 0 END)
+             FROM fin_transaction t
+             LEFT JOIN accounts d ON d.account_id = t.debit_acc_id
+             LEFT JOIN accounts cr ON cr.account_id = t.credit_acc_id
+             WHERE t.timestamp >= ?1 AND t.timestamp < ?2
+             GROUP BY 1, 2",
+        )?;
+        let rows = stmt
+            .query_map(
+                params![
+                    local_datetime(start, NaiveTime::MIN)?,
+                    local_datetime(end, NaiveTime::MIN)?,
+                ],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, i64>(2)?,
+                        row.get::<_, i64>(3)?,
+                    ))
+                },
+            )?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+
+        let mut months = vec![];
+        let mut month = start;
+        while month < end {
+            months.push(MonthlyTotals {
+                month,
+                income: 0,
+                expenses: 0,
+            });
+            month = month
+                .checked_add_months(Months::new(1))
+                .ok_or_else(|| eyre!("month {month} is out of range"))?;
+        }
+        for (period, currency, income, expenses) in rows {
+            let Some(totals) = months
+                .iter_mut()
+                .find(|totals| totals.month.format("%Y-%m").to_string() == period)
+            else {
+                continue;
+            };
+            let currency = currency.as_deref().unwrap_or(base);
+            let rate_date = last_day(
+                totals
+                    .month
+                    .checked_add_months(Months::new(1))
+                    .unwrap_or(end),
+            )?;
+            totals.income += self.convert(income, currency, base, rate_date)?;
+            totals.expenses += self.convert(expenses, currency, base, rate_date)?;
+        }
+        Ok(months)
+    }
+}
+
+/// Day before `end`, the last one of a period ending there
+fn last_day(end: NaiveDate) -> Result<NaiveDate> {
+    end.checked_sub_days(Days::new(1))
+        .ok_or_else(|| eyre!("date {end} is out of range"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        service::fixtures::{self, account},
+        types::{Account, AccountType, ExchangeRate, Split, Transaction},
+    };
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    /// Bank, Salary, Food and Rent in USD, then Trip in EUR paid from Wallet
+    fn service() -> Result<BudgetService> {
+        let eur = |name, acc_type| Account {
+            currency: String::from("EUR"),
+            ..account(name, acc_type)
+        };
+        let mut service = fixtures::service(
+            &[
+                account("Bank", AccountType::Asset),
+                account("Salary", AccountType::Income),
+                account("Food", AccountType::Expense),
+                account("Rent", AccountType::Expense),
+                eur("Wallet", AccountType::Asset),
+                eur("Trip", AccountType::Expense),
+            ],
+            &["Food", "Household", "Rent", "Salary", "Travel"],
+        )?;
+        service.put_rates(&[ExchangeRate {
+            date: date(2025, 1, 1),
+            currency: String::from("EUR"),
+            quote: String::from("USD"),
+            rate: 1.5,
+        }])?;
+        Ok(service)
+    }
+
+    fn trn(day: NaiveDate, credit: u8, debit: u8, amount: i64, category: &str) -> Transaction {
+        Transaction {
+            timestamp: local_datetime(day, NaiveTime::MIN).unwrap(),
+            category: Some(category.to_string()),
+            ..fixtures::trn(Some(credit), Some(debit), amount)
+        }
+    }
+
+    fn spending(category: &str, amount: i64) -> CategorySpending {
+        CategorySpending {
+            category: Some(category.to_string()),
+            amount,
+        }
+    }
+
+    #[test]
+    fn spending_by_category() -> Result<()> {
+        let mut service = service()?;
+        let mut shopping = trn(date(2025, 1, 5), 1, 3, 5000, "Food");
+        shopping.splits = vec![
+            Split {
+                category: Some(String::from("Food")),
+                amount: 3000,
+                description: None,
+            },
+            Split {
+                category: Some(String::from("Household")),
+                amount: 2000,
+                description: None,
+            },
+        ];
+        service.put_trns(&[
+            shopping,
+            trn(date(2025, 1, 10), 3, 1, 500, "Food"),
+            trn(date(2025, 1, 1), 1, 4, 90000, "Rent"),
+            trn(date(2025, 1, 20), 5, 6, 1000, "Travel"),
+            trn(date(2025, 1, 25), 2, 1, 300000, "Salary"),
+            trn(date(2025, 2, 1), 1, 3, 700, "Food"),
+        ])?;
+
+        assert_eq!(
+            service.get_spending("USD", date(2025, 1, 1), date(2025, 2, 1))?,
+            vec![
+                spending("Rent", 90000),
+                spending("Food", 2500),
+                spending("Household", 2000),
+                spending("Travel", 1500),
+            ]
+        );
+        assert_eq!(
+            service.get_spending("USD", date(2025, 2, 1), date(2025, 3, 1))?,
+            vec![spending("Food", 700)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn income_and_expenses_by_month() -> Result<()> {
+        let mut service = service()?;
+        service.put_trns(&[
+            trn(date(2025, 1, 25), 2, 1, 300000, "Salary"),
+            trn(date(2025, 1, 1), 1, 4, 90000, "Rent"),
+            trn(date(2025, 3, 20), 5, 6, 1000, "Travel"),
+            trn(date(2025, 3, 21), 3, 1, 100, "Food"),
+        ])?;
+
+        let totals = service.get_monthly_totals("USD", date(2025, 1, 15), date(2025, 4, 1))?;
+        let totals: Vec<(NaiveDate, i64, i64)> = totals
+            .into_iter()
+            .map(|month| (month.month, month.income, month.expenses))
+            .collect();
+        assert_eq!(
+            totals,
+            [
+                (date(2025, 1, 1), 300000, 90000),
+                (date(2025, 2, 1), 0, 0),
+                (date(2025, 3, 1), 0, 1400),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,356 @@
+use chrono::{Datelike, Local, NaiveDate, NaiveTime};
+use color_eyre::eyre::{self, eyre, Result};
+use rusqlite::{params, types::Type, OptionalExtension, Row};
+
+use super::{
+    history::{Change, RowChange, StoredRow},
+    BudgetService,
+};
+use crate::{
+    parsing::local_datetime,
+    types::{Frequency, Schedule, Transaction, TrnStatus},
+};
+
+const SCHEDULE_COLUMNS: &str = "schedule_id, frequency, day_of_month, next_date, end_date, paused,
+    credit_acc_id, debit_acc_id, amount, category, description";
+
+impl BudgetService {
+    /// Schedules by the date of their next occurrence
+    pub fn get_schedules(&self) -> Result<Vec<Schedule>> {
+        let mut stmt = self.statement(&format!(
+            "SELECT {SCHEDULE_COLUMNS} FROM schedules ORDER BY next_date, schedule_id"
+        ))?;
+        let schedules = stmt.query_map([], schedule_from_row)?;
+        Ok(schedules.collect::<Result<Vec<Schedule>, rusqlite::Error>>()?)
+    }
+
+    /// Creates or updates a schedule, returns its id. Resuming a paused schedule
+    /// skips the occurrences missed while it was paused.
+    pub fn put_schedule(&mut self, item: &Schedule) -> Result<isize> {
+        if item
+            .day_of_month
+            .is_some_and(|day| !(1..=31).contains(&day))
+        {
+            return Err(eyre!("day of month must be between 1 and 31"));
+        }
+        let trn = occurrence(item, item.next_date)?;
+        self.validate_trn(&trn)?;
+        self.check_trn_categories(&trn, None)?;
+
+        // later occurrences keep the day of the first one, also after shorter months
+        let day_of_month = match item.frequency {
+            Frequency::Monthly | Frequency::Yearly => {
+                item.day_of_month.or(Some(item.next_date.day()))
+            }
+            Frequency::Daily | Frequency::Weekly => item.day_of_month,
+        };
+        let resumed = match item.schedule_id {
+            Some(schedule_id) if !item.paused => self
+                .statement("SELECT paused FROM schedules WHERE schedule_id = ?1")?
+                .query_row(params![schedule_id], |row| row.get(0))
+                .optional()?
+                .unwrap_or(false),
+            _ => false,
+        };
+        let next_date = match resumed {
+            true => Schedule {
+                day_of_month,
+                ..item.clone()
+            }
+            .first_from(Local::now().date_naive())
+            .ok_or_else(|| eyre!("schedule {:?} is out of range", item.schedule_id))?,
+            false => item.next_date,
+        };
+        let values = params![
+            item.frequency.as_str(),
+            day_of_month,
+            next_date,
+            item.end_date,
+            item.paused,
+            item.credit_acc_id,
+            item.debit_acc_id,
+            item.amount,
+            item.category,
+            item.description,
+        ];
+        let schedule_id = match item.schedule_id {
+            Some(schedule_id) => {
+                let mut update = self.statement(
+                    "UPDATE schedules
+                     SET
+                        frequency     = ?1,
+                        day_of_month  = ?2,
+                        next_date     = ?3,
+                        end_date      = ?4,
+                        paused        = ?5,
+                        credit_acc_id = ?6,
+                        debit_acc_id  = ?7,
+                        amount        = ?8,
+                        category      = ?9,
+                        description   = ?10
+                     WHERE schedule_id = ?11",
+                )?;
+                let mut values = values.to_vec();
+                values.push(&schedule_id);
+                if update.execute(values.as_slice())? == 0 {
+                    return Err(eyre!("schedule {schedule_id} does not exist"));
+                }
+                schedule_id
+            }
+            None => {
+                let mut insert = self.statement(
+                    "INSERT INTO schedules (
+                        frequency, day_of_month, next_date, end_date, paused,
+                        credit_acc_id, debit_acc_id, amount, category, description
+                     )
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                     RETURNING schedule_id",
+                )?;
+                insert.query_row(values, |row| row.get(0))?
+            }
+        };
+        Ok(schedule_id)
+    }
+
+    pub fn del_schedule(&mut self, schedule_id: isize) -> Result<()> {
+        self.statement("DELETE FROM schedules WHERE schedule_id = ?1")?
+            .execute(params![schedule_id])?;
+        Ok(())
+    }
+
+    /// Adds transactions of all occurrences of active schedules due by `today`
+    /// as one step of the undo history, returns how many were added
+    pub fn run_schedules(&mut self, today: NaiveDate) -> Result<usize> {
+        let schedules = self.get_schedules()?;
+        self.atomically(|service| {
+            let mut changes: Vec<Change> = vec![];
+            let mut rows: Vec<RowChange> = vec![];
+            for schedule in schedules.into_iter().filter(|schedule| !schedule.paused) {
+                let due: Vec<NaiveDate> = schedule
+                    .occurrences()
+                    .take_while(|&date| date <= today)
+                    .collect();
+                let Some(&last) = due.last() else {
+                    continue;
+                };
+                for date in due {
+                    changes.push(service.write_trn(&occurrence(&schedule, date)?)?);
+                }
+
+                let next_date = schedule
+                    .following(last)
+                    .ok_or_else(|| eyre!("schedule {:?} is out of range", schedule.schedule_id))?;
+                service
+                    .statement("UPDATE schedules SET next_date = ?2 WHERE schedule_id = ?1")?
+                    .execute(params![schedule.schedule_id, next_date])?;
+                // undoing the run brings the occurrences back on the next run
+                let state = |next_date| StoredRow::ScheduleDate {
+                    schedule_id: schedule.schedule_id.unwrap_or_default(),
+                    next_date,
+                };
+                rows.push(RowChange {
+                    before: Some(state(schedule.next_date)),
+                    after: Some(state(next_date)),
+                });
+            }
+
+            service.record_with_rows(
+                &format!("add {} scheduled transactions", changes.len()),
+                &changes,
+                &rows,
+            )?;
+            Ok(changes.len())
+        })
+    }
+}
+
+/// Transaction of the schedule occurring on `date`
+fn occurrence(schedule: &Schedule, date: NaiveDate) -> Result<Transaction> {
+    Ok(Transaction {
+        transaction_id: None,
+        credit_acc_id: Some(schedule.credit_acc_id),
+        debit_acc_id: Some(schedule.debit_acc_id),
+        timestamp: local_datetime(date, NaiveTime::MIN)?,
+        category: schedule.category.clone(),
+        amount: schedule.amount,
+        description: schedule.description.clone(),
+        splits: vec![],
+        status: TrnStatus::Uncleared,
+        external_id: None,
+    })
+}
+
+fn schedule_from_row(row: &Row) -> rusqlite::Result<Schedule> {
+    let frequency: String = row.get(1)?;
+    Ok(Schedule {
+        schedule_id: row.get(0)?,
+        frequency: frequency.parse().map_err(|err: eyre::Report| {
+            rusqlite::Error::FromSqlConversionFailure(1, Type::Text, err.into())
+        })?,
+        day_of_month: row.get(2)?,
+        next_date: row.get(3)?,
+        end_date: row.get(4)?,
+        paused: row.get(5)?,
+        credit_acc_id: row.get(6)?,
+        debit_acc_id: row.get(7)?,
+        amount: row.get(8)?,
+        category: row.get(9)?,
+        description: row.get(10)?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        service::fixtures::{self, account},
+        types::{AccountType, TrnFilter},
+    };
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn service() -> Result<BudgetService> {
+        fixtures::service(
+            &[
+                account("Bank", AccountType::Asset),
+                account("Rent", AccountType::Expense),
+            ],
+            &["Rent"],
+        )
+    }
+
+    fn schedule(frequency: Frequency, next_date: NaiveDate) -> Schedule {
+        Schedule {
+            schedule_id: None,
+            frequency,
+            day_of_month: None,
+            next_date,
+            end_date: None,
+            paused: false,
+            credit_acc_id: 1,
+            debit_acc_id: 2,
+            amount: 90000,
+            category: Some(String::from("Rent")),
+            description: None,
+        }
+    }
+
+    fn dates(schedule: &Schedule, count: usize) -> Vec<NaiveDate> {
+        schedule.occurrences().take(count).collect()
+    }
+
+    #[test]
+    fn recurrence_rules() {
+        let weekly = schedule(Frequency::Weekly, date(2025, 1, 27));
+        assert_eq!(
+            dates(&weekly, 3),
+            [date(2025, 1, 27), date(2025, 2, 3), date(2025, 2, 10)]
+        );
+
+        let mut monthly = schedule(Frequency::Monthly, date(2025, 1, 31));
+        monthly.day_of_month = Some(31);
+        assert_eq!(
+            dates(&monthly, 3),
+            [date(2025, 1, 31), date(2025, 2, 28), date(2025, 3, 31)]
+        );
+
+        let mut yearly = schedule(Frequency::Yearly, date(2024, 2, 29));
+        yearly.day_of_month = Some(29);
+        yearly.end_date = Some(date(2028, 2, 28));
+        assert_eq!(
+            dates(&yearly, 10),
+            [
+                date(2024, 2, 29),
+                date(2025, 2, 28),
+                date(2026, 2, 28),
+                date(2027, 2, 28)
+            ]
+        );
+    }
+
+    #[test]
+    fn materialize_due_occurrences() -> Result<()> {
+        let mut service = service()?;
+        let mut monthly = schedule(Frequency::Monthly, date(2025, 1, 1));
+        monthly.day_of_month = Some(1);
+        let schedule_id = service.put_schedule(&monthly)?;
+        let mut paused = schedule(Frequency::Daily, date(2025, 1, 1));
+        paused.paused = true;
+        service.put_schedule(&paused)?;
+
+        assert_eq!(service.run_schedules(date(2025, 3, 15))?, 3);
+        assert_eq!(service.run_schedules(date(2025, 3, 15))?, 0);
+        assert_eq!(service.find_trns(&TrnFilter::default())?.len(), 3);
+
+        let schedules = service.get_schedules()?;
+        let saved = schedules
+            .iter()
+            .find(|schedule| schedule.schedule_id == Some(schedule_id))
+            .unwrap();
+        assert_eq!(saved.next_date, date(2025, 4, 1));
+
+        service.undo()?;
+        assert!(service.find_trns(&TrnFilter::default())?.is_empty());
+        assert_eq!(service.get_schedules()?[0].next_date, date(2025, 1, 1));
+        service.redo()?;
+        assert_eq!(service.find_trns(&TrnFilter::default())?.len(), 3);
+        assert_eq!(service.get_schedules()?[1].next_date, date(2025, 4, 1));
+        service.undo()?;
+        assert_eq!(service.run_schedules(date(2025, 3, 15))?, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn monthly_schedule_keeps_first_day() -> Result<()> {
+        let mut service = service()?;
+        service.put_schedule(&schedule(Frequency::Monthly, date(2025, 1, 31)))?;
+        assert_eq!(service.get_schedules()?[0].day_of_month, Some(31));
+
+        assert_eq!(service.run_schedules(date(2025, 3, 31))?, 3);
+        let dates: Vec<NaiveDate> = service
+            .find_trns(&TrnFilter::default())?
+            .iter()
+            .map(|trn| trn.timestamp.date_naive())
+            .collect();
+        assert!(dates.contains(&date(2025, 2, 28)));
+        assert!(dates.contains(&date(2025, 3, 31)));
+        assert_eq!(service.get_schedules()?[0].next_date, date(2025, 4, 30));
+        Ok(())
+    }
+
+    #[test]
+    fn resuming_skips_missed_occurrences() -> Result<()> {
+        let mut service = service()?;
+        let mut monthly = schedule(Frequency::Monthly, date(2020, 1, 15));
+        monthly.paused = true;
+        monthly.schedule_id = Some(service.put_schedule(&monthly)?);
+        assert_eq!(service.get_schedules()?[0].next_date, date(2020, 1, 15));
+
+        monthly.paused = false;
+        service.put_schedule(&monthly)?;
+        let today = Local::now().date_naive();
+        let resumed = service.get_schedules()?.remove(0);
+        assert!(resumed.next_date >= today);
+        assert!(resumed.next_date < today + chrono::Months::new(1));
+        assert_eq!(resumed.next_date.day(), 15);
+        assert_eq!(
+            service.run_schedules(today)?,
+            usize::from(resumed.next_date == today)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn schedule_needs_valid_accounts() -> Result<()> {
+        let mut service = service()?;
+        let mut invalid = schedule(Frequency::Monthly, date(2025, 1, 1));
+        invalid.debit_acc_id = 1;
+        assert!(service.put_schedule(&invalid).is_err());
+        invalid.debit_acc_id = 9;
+        assert!(service.put_schedule(&invalid).is_err());
+        assert!(service.get_schedules()?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::Result;
+use rusqlite::params;
+
+use super::BudgetService;
+use crate::types::{Split, Transaction};
+
+impl BudgetService {
+    /// Fills in splits of the transactions
+    pub(super) fn load_splits(&self, transactions: &mut [Transaction]) -> Result<()> {
+        let ids: Vec<isize> = transactions
+            .iter()
+            .filter_map(|trn| trn.transaction_id)
+            .collect();
+        let mut stmt = self.statement(
+            "SELECT transaction_id, category, amount, description FROM postings
+             WHERE transaction_id IN (SELECT value FROM json_each(?1))
+             ORDER BY posting_id",
+        )?;
+        let rows = stmt.query_map(params![serde_json::to_string(&ids)?], |row| {
+            Ok((
+                row.get::<_, isize>(0)?,
+                Split {
+                    category: row.get(1)?,
+                    amount: row.get(2)?,
+                    description: row.get(3)?,
+                },
+            ))
+        })?;
+
+        let mut splits: HashMap<isize, Vec<Split>> = HashMap::new();
+        for row in rows {
+            let (transaction_id, split) = row?;
+            splits.entry(transaction_id).or_default().push(split);
+        }
+        for trn in transactions {
+            trn.splits = trn
+                .transaction_id
+                .and_then(|id| splits.remove(&id))
+                .unwrap_or_default();
+        }
+        Ok(())
+    }
+
+    /// Replaces splits of the transaction
+    pub(super) fn write_splits(&self, transaction_id: isize, splits: &[Split]) -> Result<()> {
+        self.statement("DELETE FROM postings WHERE transaction_id = ?1")?
+            .execute(params![transaction_id])?;
+
+        let mut insert = self.statement(
+            "INSERT INTO postings (transaction_id, category, amount, description)
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for split in splits {
+            insert.execute(params![
+                transaction_id,
+                split.category,
+                split.amount,
+                split.description
+            ])?;
+        }
+        Ok(())
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        service::fixtures::{self, account},
+        types::{AccountType, Budget, TrnFilter},
+    };
+    use chrono::NaiveDate;
+
+    fn service() -> Result<BudgetService> {
+        fixtures::service(
+            &[
+                account("Cash", AccountType::Asset),
+                account("Shop", AccountType::Expense),
+            ],
+            &["Food", "Household"],
+        )
+    }
+
+    fn split(category: &str, amount: i64) -> Split {
+        Split {
+            category: Some(category.to_string()),
+            amount,
+            description: None,
+        }
+    }
+
+    fn trn(amount: i64, splits: Vec<Split>) -> Transaction {
+        Transaction {
+            splits,
+            ..fixtures::trn(Some(1), Some(2), amount)
+        }
+    }
+
+    #[test]
+    fn splits_saved_with_transaction() -> Result<()> {
+        let mut service = service()?;
+        let splits = vec![split("Food", 1250), split("Household", 300)];
+        let trn_id = service.put_trn(&trn(1550, splits.clone()))?;
+
+        assert_eq!(service.get_trn(trn_id)?.unwrap().splits, splits);
+        assert_eq!(service.find_trns(&TrnFilter::default())?[0].splits, splits);
+
+        let mut updated = service.get_trn(trn_id)?.unwrap();
+        updated.splits = vec![split("Food", 1550)];
+        service.put_trn(&updated)?;
+        assert_eq!(service.get_trn(trn_id)?.unwrap().splits, updated.splits);
+
+        service.undo()?;
+        assert_eq!(service.get_trn(trn_id)?.unwrap().splits, splits);
+        Ok(())
+    }
+
+    #[test]
+    fn splits_must_add_up() -> Result<()> {
+        let mut service = service()?;
+        let unbalanced = trn(1000, vec![split("Food", 600), split("Household", 300)]);
+        assert!(service.put_trn(&unbalanced).is_err());
+        assert!(service.find_trns(&TrnFilter::default())?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn budgets_count_splits() -> Result<()> {
+        let mut service = service()?;
+        let day = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        for category in ["Food", "Household"] {
+            service.put_budget(&Budget {
+                category: category.to_string(),
+                period: day,
+                limit: 1000,
+            })?;
+        }
+        let mut whole = trn(500, vec![]);
+        whole.category = Some(String::from("Food"));
+        service.put_trns(&[
+            whole,
+            trn(1550, vec![split("Food", 1250), split("Household", 300)]),
+        ])?;
+
+        let spent: Vec<_> = service
+            .get_budget_statuses("USD", day)?
+            .into_iter()
+            .map(|status| (status.budget.category, status.spent))
+            .collect();
+        assert_eq!(
+            spent,
+            [
+                (String::from("Food"), 1750),
+                (String::from("Household"), 300)
+            ]
+        );
+        Ok(())
+    }
+}
@@ -1,6 +1,10 @@
-use chrono::{DateTime, Local};
+use std::str::FromStr;
 
-#[derive(Debug, Default, PartialEq, Eq)]
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate};
+use color_eyre::eyre;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum ScreenMode {
     #[default]
     Browsing,
@@ -22,7 +26,7 @@ pub enum NavEvent {
     Interact,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Transaction {
     pub transaction_id: Option<isize>,
     pub credit_acc_id: Option<u8>,
@@ -31,6 +35,420 @@ pub struct Transaction {
     pub category: Option<String>,
     pub amount: i64,
     pub description: Option<String>,
+    /// Parts of the amount booked to different categories, empty when not split
+    #[serde(default)]
+    pub splits: Vec<Split>,
+    #[serde(default)]
+    pub status: TrnStatus,
+    /// Id the bank gave the transaction in a statement, like FITID in OFX files
+    #[serde(default)]
+    pub external_id: Option<String>,
+}
+
+/// New transaction that looks like one already stored
+#[derive(Debug, Clone, PartialEq)]
+pub struct Duplicate {
+    pub candidate: Transaction,
+    pub existing: Transaction,
+}
+
+/// Reconciliation state of a transaction. Cleared ones appeared on a bank statement,
+/// reconciled ones were matched to a statement balance and can not be changed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrnStatus {
+    #[default]
+    Uncleared,
+    Cleared,
+    Reconciled,
+}
+
+impl TrnStatus {
+    pub const ALL: [TrnStatus; 3] = [
+        TrnStatus::Uncleared,
+        TrnStatus::Cleared,
+        TrnStatus::Reconciled,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrnStatus::Uncleared => "Uncleared",
+            TrnStatus::Cleared => "Cleared",
+            TrnStatus::Reconciled => "Reconciled",
+        }
+    }
+}
+
+impl FromStr for TrnStatus {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str().eq_ignore_ascii_case(value.trim()))
+            .ok_or_else(|| {
+                let known: Vec<_> = Self::ALL.iter().map(TrnStatus::as_str).collect();
+                eyre::eyre!("unknown status '{value}', expected {}", known.join("/"))
+            })
+    }
+}
+
+/// Part of a split transaction. Splits of a transaction add up to its amount
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Split {
+    pub category: Option<String>,
+    pub amount: i64,
+    pub description: Option<String>,
+}
+
+impl Split {
+    /// Sum of the split amounts, an error when it does not fit into an amount
+    pub fn total(splits: &[Split]) -> eyre::Result<i64> {
+        splits
+            .iter()
+            .try_fold(0i64, |total, split| total.checked_add(split.amount))
+            .ok_or_else(|| eyre::eyre!("splits add up to more than an amount can hold"))
+    }
+}
+
+/// Criteria for selecting transactions, empty fields match everything
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TrnFilter {
+    /// First day of the range, inclusive
+    pub from: Option<NaiveDate>,
+    /// Last day of the range, inclusive
+    pub to: Option<NaiveDate>,
+    /// Account on either side of the transaction
+    pub account_id: Option<u8>,
+    /// Words that must all appear in the description or the category, ignoring case
+    pub text: Vec<String>,
+    /// Smallest amount in cents, inclusive
+    pub amount_from: Option<i64>,
+    /// Largest amount in cents, inclusive
+    pub amount_to: Option<i64>,
+    /// Order of the selected transactions
+    pub sort: TrnSort,
+}
+
+/// Transaction fields rows can be sorted by, in the order of the transactions table columns
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TrnColumn {
+    #[default]
+    Date,
+    Category,
+    Description,
+    Amount,
+}
+
+impl TrnColumn {
+    pub const ALL: [TrnColumn; 4] = [
+        TrnColumn::Date,
+        TrnColumn::Category,
+        TrnColumn::Description,
+        TrnColumn::Amount,
+    ];
+}
+
+/// Order of transactions, equal values keep the order they were added in
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TrnSort {
+    pub column: TrnColumn,
+    pub descending: bool,
+}
+
+impl TrnSort {
+    /// Order after asking to sort by `column` again: ascending first, then descending,
+    /// then back to the default order
+    pub fn cycle(self, column: TrnColumn) -> Self {
+        match (self.column == column, self.descending) {
+            (false, _) => Self {
+                column,
+                descending: false,
+            },
+            (true, false) => Self {
+                column,
+                descending: true,
+            },
+            (true, true) => Self::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccountType {
+    #[default]
+    Asset,
+    Liability,
+    Equity,
+    Income,
+    Expense,
+}
+
+impl AccountType {
+    pub const ALL: [AccountType; 5] = [
+        AccountType::Asset,
+        AccountType::Liability,
+        AccountType::Equity,
+        AccountType::Income,
+        AccountType::Expense,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountType::Asset => "Asset",
+            AccountType::Liability => "Liability",
+            AccountType::Equity => "Equity",
+            AccountType::Income => "Income",
+            AccountType::Expense => "Expense",
+        }
+    }
+}
+
+impl FromStr for AccountType {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|acc_type| acc_type.as_str().eq_ignore_ascii_case(value.trim()))
+            .ok_or_else(|| {
+                let known: Vec<_> = Self::ALL.iter().map(AccountType::as_str).collect();
+                eyre::eyre!(
+                    "unknown account type '{value}', expected {}",
+                    known.join("/")
+                )
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Account {
+    pub account_id: Option<u8>,
+    pub name: String,
+    pub acc_type: AccountType,
+    pub currency: String,
+    pub opening_balance: i64,
+}
+
+/// Describes how columns of a bank statement CSV file map to transaction fields.
+/// Column numbers start from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvProfile {
+    pub name: String,
+    pub delimiter: char,
+    /// Header lines before the first transaction
+    pub skip_rows: usize,
+    pub date_column: usize,
+    /// `chrono` format of the date column, e.g. `%d.%m.%Y`
+    pub date_format: String,
+    /// Signed amount, or the inflow amount when `outflow_column` is set
+    pub amount_column: usize,
+    pub outflow_column: Option<usize>,
+    pub description_column: usize,
+    pub category_column: Option<usize>,
+    pub decimal_separator: char,
+    /// Whether positive amounts mean money leaving the account, as in card statements
+    pub invert_sign: bool,
+}
+
+/// Transaction category. Transactions refer to it by name, so names are unique
+#[derive(Debug, Clone, PartialEq)]
+pub struct Category {
+    pub category_id: Option<isize>,
+    pub name: String,
+    pub parent_id: Option<isize>,
+}
+
+/// Monthly spending limit of a category. It applies from `period` on,
+/// until a budget for a later month replaces it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Budget {
+    pub category: String,
+    /// First day of the month
+    pub period: NaiveDate,
+    pub limit: i64,
+}
+
+/// Budget in effect for a month with the amount spent in its category
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetStatus {
+    pub budget: Budget,
+    pub spent: i64,
+}
+
+impl BudgetStatus {
+    pub fn is_over(&self) -> bool {
+        self.spent > self.budget.limit
+    }
+}
+
+/// Price of one unit of `currency` in units of `quote` on `date`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExchangeRate {
+    pub date: NaiveDate,
+    pub currency: String,
+    pub quote: String,
+    pub rate: f64,
+}
+
+/// Net amount spent in a category during a report period
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategorySpending {
+    pub category: Option<String>,
+    pub amount: i64,
+}
+
+/// Income earned and expenses paid during one month
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonthlyTotals {
+    pub month: NaiveDate,
+    pub income: i64,
+    pub expenses: i64,
+}
+
+/// Totals of one account in the trial balance
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrialBalanceLine {
+    pub account: Account,
+    pub debits: i64,
+    pub credits: i64,
+}
+
+impl TrialBalanceLine {
+    /// Debit balance, negative when credits exceed debits
+    pub fn balance(&self) -> i64 {
+        self.debits - self.credits
+    }
+}
+
+/// Totals of every account converted to one currency
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TrialBalance {
+    pub lines: Vec<TrialBalanceLine>,
+    /// Difference between converting every account and converting the totals of
+    /// each currency, a debit when positive. It keeps rounding of converted amounts
+    /// from putting the books out of balance.
+    pub rounding: i64,
+}
+
+impl TrialBalance {
+    /// Debits of all accounts, with the rounding when it is a debit
+    pub fn debits(&self) -> i64 {
+        self.lines.iter().map(|line| line.debits).sum::<i64>() + self.rounding.max(0)
+    }
+
+    /// Credits of all accounts, with the rounding when it is a credit
+    pub fn credits(&self) -> i64 {
+        self.lines.iter().map(|line| line.credits).sum::<i64>() + (-self.rounding).max(0)
+    }
+}
+
+/// How often a scheduled transaction repeats
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    #[default]
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    pub const ALL: [Frequency; 4] = [
+        Frequency::Daily,
+        Frequency::Weekly,
+        Frequency::Monthly,
+        Frequency::Yearly,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "Daily",
+            Frequency::Weekly => "Weekly",
+            Frequency::Monthly => "Monthly",
+            Frequency::Yearly => "Yearly",
+        }
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = eyre::Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|frequency| frequency.as_str().eq_ignore_ascii_case(value.trim()))
+            .ok_or_else(|| {
+                let known: Vec<_> = Self::ALL.iter().map(Frequency::as_str).collect();
+                eyre::eyre!("unknown frequency '{value}', expected {}", known.join("/"))
+            })
+    }
+}
+
+/// Transaction repeating by a recurrence rule. Occurrences up to today are
+/// added to the transactions, `next_date` is the first one not added yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    pub schedule_id: Option<isize>,
+    pub frequency: Frequency,
+    /// Day of monthly and yearly occurrences, the last day in shorter months.
+    /// Saved as the day of the first occurrence when not set.
+    pub day_of_month: Option<u32>,
+    pub next_date: NaiveDate,
+    /// Last day an occurrence may fall on, inclusive
+    pub end_date: Option<NaiveDate>,
+    pub paused: bool,
+    pub credit_acc_id: u8,
+    pub debit_acc_id: u8,
+    pub amount: i64,
+    pub category: Option<String>,
+    pub description: Option<String>,
+}
+
+impl Schedule {
+    /// Date of the occurrence after the one on `date`
+    pub fn following(&self, date: NaiveDate) -> Option<NaiveDate> {
+        let same_day = |next: NaiveDate| {
+            let day = self.day_of_month.unwrap_or(date.day());
+            (1..=day.min(31)).rev().find_map(|day| next.with_day(day))
+        };
+        match self.frequency {
+            Frequency::Daily => date.checked_add_days(Days::new(1)),
+            Frequency::Weekly => date.checked_add_days(Days::new(7)),
+            Frequency::Monthly => same_day(date.with_day(1)?.checked_add_months(Months::new(1))?),
+            Frequency::Yearly => same_day(date.with_day(1)?.checked_add_months(Months::new(12))?),
+        }
+    }
+
+    /// Occurrences from `next_date` on, until the end date
+    pub fn occurrences(&self) -> impl Iterator<Item = NaiveDate> + '_ {
+        std::iter::successors(Some(self.next_date), |&date| self.following(date))
+            .take_while(|&date| self.end_date.is_none_or(|end| date <= end))
+    }
+
+    /// First occurrence on or after `date`, which may be past the end date
+    pub fn first_from(&self, date: NaiveDate) -> Option<NaiveDate> {
+        std::iter::successors(Some(self.next_date), |&next| self.following(next))
+            .find(|&next| next >= date)
+    }
+
+    /// Whether no occurrences are left
+    pub fn is_finished(&self) -> bool {
+        self.end_date.is_some_and(|end| self.next_date > end)
+    }
+
+    /// Human readable recurrence rule, like `Monthly on day 5 until 2026-01-31`
+    pub fn rule(&self) -> String {
+        let mut rule = String::from(self.frequency.as_str());
+        if let (Frequency::Monthly | Frequency::Yearly, Some(day)) =
+            (self.frequency, self.day_of_month)
+        {
+            rule.push_str(&format!(" on day {day}"));
+        }
+        if let Some(end) = self.end_date {
+            rule.push_str(&format!(" until {end}"));
+        }
+        rule
+    }
 }
 
 // impl Transaction {
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::{eyre, Result};
+use ratatui::{prelude::*, widgets::StatefulWidget};
+
+use super::{
+    editable::{EditableTable, EditableTableState},
+    theme::Theme,
+};
+use crate::{
+    currency::{exponent, parse_currency},
+    parsing::{format_money, parse_money},
+    types::Account,
+};
+
+const TABLE_TITLE: &str = "Accounts";
+const TABLE_HEADER: [&str; 6] = ["Id", "Name", "Type", "Currency", "Opening", "Balance"];
+
+const COLUMN_WIDTHS: [Constraint; 6] = [
+    Constraint::Length(4),
+    Constraint::Fill(1),
+    Constraint::Length(10),
+    Constraint::Length(8),
+    Constraint::Min(13),
+    Constraint::Min(13),
+];
+const COLUMN_ALIGNMENTS: [Alignment; 6] = [
+    Alignment::Right,
+    Alignment::Left,
+    Alignment::Left,
+    Alignment::Left,
+    Alignment::Right,
+    Alignment::Right,
+];
+
+#[derive(Debug)]
+pub struct AccountsTable<'a> {
+    items: &'a [Account],
+    balances: &'a HashMap<u8, i64>,
+    theme: Theme,
+}
+
+impl StatefulWidget for AccountsTable<'_> {
+    type State = EditableTableState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut EditableTableState) {
+        let rows = self.items.iter().map(|data| {
+            let balance = data
+                .account_id
+                .and_then(|id| self.balances.get(&id))
+                .copied()
+                .unwrap_or(data.opening_balance);
+            to_cells(data, balance).to_vec()
+        });
+
+        EditableTable::new(
+            TABLE_TITLE,
+            TABLE_HEADER.map(String::from).to_vec(),
+            COLUMN_WIDTHS.to_vec(),
+            COLUMN_ALIGNMENTS.to_vec(),
+        )
+        .rows(rows)
+        .theme(self.theme)
+        .render(area, buf, state);
+    }
+}
+
+impl<'a> AccountsTable<'a> {
+    pub fn new(items: &'a [Account], balances: &'a HashMap<u8, i64>) -> Self {
+        Self {
+            items,
+            balances,
+            theme: Theme::default(),
+        }
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+fn to_cells(data: &Account, balance: i64) -> [String; 6] {
+    [
+        data.account_id.map(|id| id.to_string()).unwrap_or_default(),
+        data.name.clone(),
+        data.acc_type.as_str().to_string(),
+        data.currency.clone(),
+        format_money(data.opening_balance, exponent(&data.currency)),
+        format_money(balance, exponent(&data.currency)),
+    ]
+}
+
+/// Returns the text of the cell in column `col` as it should appear in the editor
+pub fn cell_value(data: &Account, col: usize) -> String {
+    to_cells(data, 0).into_iter().nth(col).unwrap_or_default()
+}
+
+/// Parses `value` edited in column `col` into an updated copy of `data`
+pub fn apply_edit(data: &Account, col: usize, value: &str) -> Result<Account> {
+    let mut updated = data.clone();
+    let value = value.trim();
+
+    match col {
+        1 if value.is_empty() => return Err(eyre!("account name can not be empty")),
+        1 => updated.name = value.to_string(),
+        2 => updated.acc_type = value.parse()?,
+        3 => updated.currency = parse_currency(value)?,
+        4 => updated.opening_balance = parse_money(value, exponent(&data.currency))?,
+        _ => return Err(eyre!("column {col} is not editable")),
+    }
+
+    Ok(updated)
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::{eyre, Result};
+use ratatui::{prelude::*, widgets::StatefulWidget};
+
+use super::{
+    editable::{EditableTable, EditableTableState},
+    theme::Theme,
+};
+use crate::types::Category;
+
+const TABLE_TITLE: &str = "Categories";
+const TABLE_HEADER: [&str; 4] = ["Id", "Name", "Parent", "Transactions"];
+
+const COLUMN_WIDTHS: [Constraint; 4] = [
+    Constraint::Length(4),
+    Constraint::Fill(1),
+    Constraint::Fill(1),
+    Constraint::Length(12),
+];
+const COLUMN_ALIGNMENTS: [Alignment; 4] = [
+    Alignment::Right,
+    Alignment::Left,
+    Alignment::Left,
+    Alignment::Right,
+];
+
+const INDENT: &str = "  ";
+
+#[derive(Debug)]
+pub struct CategoriesTable<'a> {
+    items: &'a [Category],
+    usage: &'a HashMap<isize, i64>,
+    theme: Theme,
+}
+
+impl StatefulWidget for CategoriesTable<'_> {
+    type State = EditableTableState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut EditableTableState) {
+        let rows = self.items.iter().map(|data| {
+            let used = data
+                .category_id
+                .and_then(|id| self.usage.get(&id))
+                .copied()
+                .unwrap_or_default();
+            let mut cells = to_cells(data, self.items, used);
+            cells[1] = format!("{}{}", INDENT.repeat(depth(data, self.items)), cells[1]);
+            cells.to_vec()
+        });
+
+        EditableTable::new(
+            TABLE_TITLE,
+            TABLE_HEADER.map(String::from).to_vec(),
+            COLUMN_WIDTHS.to_vec(),
+            COLUMN_ALIGNMENTS.to_vec(),
+        )
+        .rows(rows)
+        .theme(self.theme)
+        .render(area, buf, state);
+    }
+}
+
+impl<'a> CategoriesTable<'a> {
+    pub fn new(items: &'a [Category], usage: &'a HashMap<isize, i64>) -> Self {
+        Self {
+            items,
+            usage,
+            theme: Theme::default(),
+        }
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+}
+
+fn to_cells(data: &Category, all: &[Category], used: i64) -> [String; 4] {
+    [
+        data.category_id
+            .map(|id| id.to_string())
+            .unwrap_or_default(),
+        data.name.clone(),
+        data.parent_id
+            .and_then(|id| all.iter().find(|c| c.category_id == Some(id)))
+            .map(|parent| parent.name.clone())
+            .unwrap_or_default(),
+        used.to_string(),
+    ]
+}
+
+/// Number of ancestors of the category
+fn depth(data: &Category, all: &[Category]) -> usize {
+    let parents: HashMap<_, _> = all.iter().map(|c| (c.category_id, c.parent_id)).collect();
+    let mut depth = 0;
+    let mut parent = data.parent_id;
+    while let Some(id) = parent {
+        depth += 1;
+        parent = parents.get(&Some(id)).copied().flatten();
+        if depth > all.len() {
+            break;
+        }
+    }
+    depth
+}
+
+/// Returns the text of the cell in column `col` as it should appear in the editor
+pub fn cell_value(data: &Category, col: usize, all: &[Category]) -> String {
+    to_cells(data, all, 0)
+        .into_iter()
+        .nth(col)
+        .unwrap_or_default()
+}
+
+/// Parses `value` edited in column `col` into an updated copy of `data`.
+/// The parent is given by name and must be one of `all`.
+pub fn apply_edit(data: &Category, col: usize, value: &str, all: &[Category]) -> Result<Category> {
+    let mut updated = data.clone();
+    let value = value.trim();
+
+    match col {
+        1 if value.is_empty() => return Err(eyre!("category name can not be empty")),
+        1 => updated.name = value.to_string(),
+        2 if value.is_empty() => updated.parent_id = None,
+        2 => {
+            updated.parent_id = all
+                .iter()
+                .find(|c| c.name == value)
+                .and_then(|c| c.category_id)
+                .map(Some)
+                .ok_or_else(|| eyre!("unknown category '{value}'"))?
+        }
+        _ => return Err(eyre!("column {col} is not editable")),
+    }
+
+    Ok(updated)
+}
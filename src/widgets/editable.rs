@@ -0,0 +1,474 @@
+use std::rc::Rc;
+
+use crossterm::event::{Event, KeyCode, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{
+        Block, BorderType, Borders, Clear, HighlightSpacing, List, ListState, Row, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, StatefulWidget, Table, TableState,
+    },
+};
+use tui_input::backend::crossterm::EventHandler;
+
+use super::{theme::Theme, utils};
+use crate::types::{NavEvent, ScreenMode};
+
+const TABLE_TITLE_BOTTOM: &str = " ← ↑ ↓ → to move selection ";
+
+const ROW_HEIGHT: u16 = 1;
+const ROW_HIGHLIGHT_SYMBOL: &str = " > ";
+
+const COLUMN_SPACING: u16 = 1;
+
+const PICKER_HEIGHT: usize = 6;
+const PICKER_MIN_WIDTH: u16 = 24;
+
+/// Selection and in-cell editor state shared by all editable tables
+#[derive(Debug, Default)]
+pub struct EditableTableState {
+    pub mode: ScreenMode,
+    table_state: TableState,
+    scroll_state: ScrollbarState,
+    input: tui_input::Input,
+    size: usize,
+    // known values offered while editing, and the highlighted suggestion
+    options: Vec<String>,
+    suggestion: usize,
+}
+
+/// Table of text cells where the selected cell can be edited in place
+#[derive(Debug)]
+pub struct EditableTable<'a> {
+    title: &'a str,
+    header: Vec<String>,
+    widths: Vec<Constraint>,
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<String>>,
+    first_row: usize,
+    theme: Theme,
+}
+
+impl StatefulWidget for EditableTable<'_> {
+    type State = EditableTableState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut EditableTableState) {
+        self.render_table(area, buf, state);
+        self.render_scrollbar(area, buf, state);
+        self.render_picker(area, buf, state);
+    }
+}
+
+impl<'a> EditableTable<'a> {
+    pub fn new(
+        title: &'a str,
+        header: Vec<String>,
+        widths: Vec<Constraint>,
+        alignments: Vec<Alignment>,
+    ) -> Self {
+        Self {
+            title,
+            header,
+            widths,
+            alignments,
+            rows: vec![],
+            first_row: 0,
+            theme: Theme::default(),
+        }
+    }
+
+    pub fn rows(mut self, rows: impl IntoIterator<Item = Vec<String>>) -> Self {
+        self.rows = rows.into_iter().collect();
+        self
+    }
+
+    /// Index of the first of `rows` in the whole table, when only a window of it is loaded.
+    /// The state keeps selection and scroll offset relative to the whole table.
+    pub fn first_row(mut self, first_row: usize) -> Self {
+        self.first_row = first_row;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    fn render_table(&self, area: Rect, buf: &mut Buffer, state: &mut EditableTableState) {
+        let header_style = Style::default().add_modifier(Modifier::REVERSED);
+        let selected_cell_style = match state.mode {
+            ScreenMode::Browsing => Style::default().bg(self.theme.selected_bg),
+            ScreenMode::Editing => Style::default()
+                .bg(self.theme.selected_bg)
+                .fg(self.theme.editing_fg),
+        };
+
+        let widths: Vec<_> = self
+            .column_areas(area)
+            .iter()
+            .map(|part| part.width)
+            .collect();
+
+        let header = self
+            .header
+            .iter()
+            .map(|title| Text::from(title.as_str()))
+            .zip(&self.alignments)
+            .map(|(text, &align)| text.alignment(align))
+            .collect::<Row>()
+            .style(header_style)
+            .height(1);
+
+        let rows = self.rows.iter().enumerate().map(|(row, cells)| {
+            let row = self.first_row + row;
+            let color = match row % 2 {
+                0 => Color::default(),
+                _ => self.theme.alt_row_bg,
+            };
+
+            cells
+                .iter()
+                .map(|cell| Text::from(cell.as_str()))
+                .zip(&widths)
+                .enumerate()
+                .map(|(col, (text, &width))| {
+                    if state.mode == ScreenMode::Editing
+                        && Some(col) == state.table_state.selected_column()
+                        && Some(row) == state.table_state.selected()
+                    {
+                        utils::to_text_with_cursor(&state.input, width)
+                    } else {
+                        text
+                    }
+                })
+                .zip(&self.alignments)
+                .map(|(text, &align)| text.alignment(align))
+                .collect::<Row>()
+                .style(Style::default().bg(color))
+                .height(row_height(cells))
+        });
+
+        let table = Table::new(rows, &self.widths)
+            .header(header)
+            .cell_highlight_style(selected_cell_style)
+            .highlight_symbol(ROW_HIGHLIGHT_SYMBOL)
+            .highlight_spacing(HighlightSpacing::Always)
+            .column_spacing(COLUMN_SPACING)
+            .block(
+                Block::default()
+                    .title(self.title)
+                    .title_bottom(Line::from(TABLE_TITLE_BOTTOM).right_aligned())
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            );
+
+        // the same state within the loaded rows
+        let selected = state.table_state.selected().map(|row| {
+            row.saturating_sub(self.first_row)
+                .min(self.rows.len().saturating_sub(1))
+        });
+        let offset = state.table_state.offset().saturating_sub(self.first_row);
+        let mut window_state = TableState::default()
+            .with_offset(offset.min(selected.unwrap_or(0)))
+            .with_selected(selected)
+            .with_selected_column(state.table_state.selected_column());
+
+        StatefulWidget::render(table, area, buf, &mut window_state);
+        *state.table_state.offset_mut() = self.first_row + window_state.offset();
+    }
+
+    fn column_areas(&self, area: Rect) -> Rc<[Rect]> {
+        Layout::horizontal(&self.widths)
+            .spacing(COLUMN_SPACING)
+            .horizontal_margin(1 + ROW_HIGHLIGHT_SYMBOL.len() as u16)
+            .split(area)
+    }
+
+    /// Draws suggestions under the edited cell, or above it when there is no room
+    fn render_picker(&self, area: Rect, buf: &mut Buffer, state: &mut EditableTableState) {
+        let suggestions = state.suggestions();
+        let (Some(row), Some(col)) = state.selected() else {
+            return;
+        };
+        if state.mode != ScreenMode::Editing || suggestions.is_empty() {
+            return;
+        }
+        let Some(cell) = self.column_areas(area).get(col).copied() else {
+            return;
+        };
+
+        // below the top border and the header
+        let row_y = area.y + 2 + row.saturating_sub(state.table_state.offset()) as u16;
+        let height = suggestions.len().min(PICKER_HEIGHT) as u16 + 2;
+        let y = if row_y + 1 + height <= area.bottom() {
+            row_y + 1
+        } else {
+            row_y.saturating_sub(height).max(area.y)
+        };
+        let picker = Rect {
+            x: cell.x.saturating_sub(1),
+            y,
+            width: (cell.width + 2).max(PICKER_MIN_WIDTH),
+            height,
+        }
+        .intersection(area);
+
+        let list = List::new(suggestions)
+            .highlight_style(Style::default().bg(self.theme.selected_bg))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .title_bottom(Line::from(" <Tab> complete ").right_aligned()),
+            );
+        let mut list_state = ListState::default().with_selected(Some(state.suggestion));
+
+        Clear.render(picker, buf);
+        StatefulWidget::render(list, picker, buf, &mut list_state);
+    }
+
+    fn render_scrollbar(&self, area: Rect, buf: &mut Buffer, state: &mut EditableTableState) {
+        if area.height as usize <= state.size * ROW_HEIGHT as usize {
+            let scrollbar = Scrollbar::default()
+                .orientation(ScrollbarOrientation::VerticalRight)
+                .thumb_symbol("▐")
+                .track_symbol(None)
+                .begin_symbol(None)
+                .end_symbol(None);
+            let area = area.inner(Margin {
+                vertical: 1,
+                horizontal: 0,
+            });
+
+            StatefulWidget::render(scrollbar, area, buf, &mut state.scroll_state);
+        }
+    }
+}
+
+/// Rows are as high as their tallest cell
+fn row_height(cells: &[String]) -> u16 {
+    let lines = cells.iter().map(|cell| cell.lines().count()).max();
+    (lines.unwrap_or_default() as u16).max(ROW_HEIGHT)
+}
+
+impl EditableTableState {
+    pub fn new(size: usize) -> Self {
+        Self {
+            mode: ScreenMode::Browsing,
+            table_state: TableState::default().with_selected(0),
+            scroll_state: ScrollbarState::new(size),
+            size,
+            input: tui_input::Input::default(),
+            options: vec![],
+            suggestion: 0,
+        }
+    }
+
+    /// Enters Editing mode for the selected cell with `value` as initial input
+    pub fn start_editing(&mut self, value: String) {
+        if let (Some(_), Some(_)) = self.selected() {
+            self.input = tui_input::Input::new(value);
+            self.mode = ScreenMode::Editing
+        }
+    }
+
+    /// Like `start_editing`, but offers completions from `options`, the known values of the cell
+    pub fn start_editing_with(&mut self, value: String, options: Vec<String>) {
+        self.start_editing(value);
+        self.options = options;
+        self.suggestion = 0;
+    }
+
+    pub fn stop_editing(&mut self) {
+        self.input.reset();
+        self.options.clear();
+        self.mode = ScreenMode::Browsing;
+    }
+
+    /// Options containing the input, the ones starting with it first
+    fn suggestions(&self) -> Vec<&str> {
+        let needle = self.input.value().trim().to_lowercase();
+        let (mut prefixed, other): (Vec<&str>, Vec<&str>) = self
+            .options
+            .iter()
+            .map(String::as_str)
+            .filter(|option| option.to_lowercase().contains(&needle))
+            .partition(|option| option.to_lowercase().starts_with(&needle));
+        prefixed.extend(other);
+        prefixed
+    }
+
+    /// Edited value. With options it is the matching option or the highlighted suggestion
+    fn accepted_value(&self) -> String {
+        let value = self.input.value().trim();
+        if self.options.is_empty() || value.is_empty() {
+            return self.input.value().to_string();
+        }
+
+        let exact = self
+            .options
+            .iter()
+            .find(|option| option.to_lowercase() == value.to_lowercase());
+        match exact {
+            Some(option) => option.clone(),
+            None => self
+                .suggestions()
+                .get(self.suggestion)
+                .map_or_else(|| value.to_string(), |option| option.to_string()),
+        }
+    }
+
+    pub fn select(&mut self, row: Option<usize>, column: Option<usize>) {
+        self.table_state.select(row);
+        self.table_state.select_column(column);
+        self.scroll_state = self.scroll_state.position(row.unwrap_or(0));
+    }
+    pub fn selected(&self) -> (Option<usize>, Option<usize>) {
+        (
+            self.table_state.selected(),
+            self.table_state.selected_column(),
+        )
+    }
+
+    fn next_row(&mut self) {
+        let i = match self.table_state.selected() {
+            Some(i) if i + 1 < self.size => i + 1,
+            _ => 0,
+        };
+        self.table_state.select(Some(i));
+        self.scroll_state = self.scroll_state.position(i);
+    }
+
+    fn previous_row(&mut self) {
+        let i = match self.table_state.selected() {
+            Some(i) if i > 0 => i - 1,
+            _ => 0,
+        };
+        self.table_state.select(Some(i));
+        self.scroll_state = self.scroll_state.position(i);
+    }
+
+    pub fn handle_input(&mut self, event: &KeyEvent) {
+        if self.mode != ScreenMode::Editing {
+            return;
+        }
+
+        match event.code {
+            KeyCode::Up if !self.options.is_empty() => {
+                self.suggestion = self.suggestion.saturating_sub(1);
+            }
+            KeyCode::Down if !self.options.is_empty() => {
+                let last = self.suggestions().len().saturating_sub(1);
+                self.suggestion = (self.suggestion + 1).min(last);
+            }
+            KeyCode::Tab if !self.options.is_empty() => {
+                if let Some(option) = self.suggestions().get(self.suggestion) {
+                    self.input = tui_input::Input::new(option.to_string());
+                    self.suggestion = 0;
+                }
+            }
+            _ => {
+                if self.input.handle_event(&Event::Key(*event)).is_some() {
+                    self.suggestion = 0;
+                }
+            }
+        }
+    }
+
+    /// Applies navigation event. Returns the edited value when edit is accepted,
+    /// the cell stays in Editing mode until the caller commits it with `stop_editing`
+    pub fn navigate(&mut self, event: NavEvent) -> Option<String> {
+        match self.mode {
+            ScreenMode::Browsing => match event {
+                NavEvent::Left => self.table_state.select_previous_column(),
+                NavEvent::Rigth => self.table_state.select_next_column(),
+                NavEvent::Up => self.previous_row(),
+                NavEvent::Down => self.next_row(),
+                NavEvent::Cancel => self.table_state.select_column(None),
+                NavEvent::Interact => {
+                    // start editing
+                    if let (Some(_), Some(_)) = self.selected() {
+                        self.mode = ScreenMode::Editing
+                    }
+                }
+            },
+            ScreenMode::Editing => match event {
+                NavEvent::Cancel => self.stop_editing(),
+                NavEvent::Interact => {
+                    // accept edit
+                    if let (Some(_row), Some(_col)) = self.selected() {
+                        return Some(self.accepted_value());
+                    }
+                }
+                _ => { /* suppress navigation in Edit Mode */ }
+            },
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn editing(value: &str) -> EditableTableState {
+        let mut state = EditableTableState::new(1);
+        state.select(Some(0), Some(0));
+        let options = ["Food", "Fun", "Seafood"].map(String::from).to_vec();
+        state.start_editing_with(value.to_string(), options);
+        state
+    }
+
+    #[test]
+    fn render_loaded_window() {
+        let mut state = EditableTableState::new(1000);
+        state.select(Some(102), Some(0));
+        let table = EditableTable::new(
+            "Window",
+            vec![String::from("Row")],
+            vec![Constraint::Fill(1)],
+            vec![Alignment::Left],
+        )
+        .rows((100..105).map(|row| vec![format!("r{row}")]))
+        .first_row(100);
+
+        let area = Rect::new(0, 0, 20, 6);
+        let mut buf = Buffer::empty(area);
+        table.render(area, &mut buf, &mut state);
+
+        let line = |y| -> String { (0..area.width).map(|x| buf[(x, y)].symbol()).collect() };
+        assert!(line(4).starts_with("│ > r102"), "{:?}", line(4));
+        assert_eq!(state.table_state.offset(), 100);
+    }
+
+    #[test]
+    fn suggestions_prefer_prefix() {
+        let state = editing("fo");
+        assert_eq!(state.suggestions(), ["Food", "Seafood"]);
+    }
+
+    #[test]
+    fn accept_picked_option() {
+        let mut state = editing("fo");
+        state.handle_input(&key(KeyCode::Down));
+        assert_eq!(
+            state.navigate(NavEvent::Interact).as_deref(),
+            Some("Seafood")
+        );
+
+        let mut state = editing("fun");
+        assert_eq!(state.navigate(NavEvent::Interact).as_deref(), Some("Fun"));
+
+        let mut state = editing("f");
+        state.handle_input(&key(KeyCode::Down));
+        state.handle_input(&key(KeyCode::Tab));
+        assert_eq!(state.input.value(), "Fun");
+
+        let mut state = editing("Rent");
+        assert_eq!(state.navigate(NavEvent::Interact).as_deref(), Some("Rent"));
+    }
+}
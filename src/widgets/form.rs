@@ -0,0 +1,142 @@
+use crossterm::event::{Event, KeyEvent};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, BorderType, Borders, Clear, Padding, Paragraph, StatefulWidget, Widget},
+};
+use tui_input::{backend::crossterm::EventHandler, Input};
+
+use super::utils;
+
+const FORM_TITLE_BOTTOM: &str = " <Tab> next field, <Enter> save, <Esc> cancel ";
+const LABEL_SPACING: u16 = 2;
+
+/// Labeled text fields with one of them focused for input
+#[derive(Debug, Default)]
+pub struct FormState {
+    fields: Vec<(&'static str, Input)>,
+    focused: usize,
+}
+
+/// Pop-up form with one line per field and optional hint lines below
+#[derive(Debug)]
+pub struct Form<'a> {
+    title: &'a str,
+    hints: Vec<Line<'a>>,
+}
+
+impl StatefulWidget for Form<'_> {
+    type State = FormState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut FormState) {
+        let height = state.fields.len() + self.hints.len() + 2;
+        let area = utils::centered(area, 64, height as u16);
+
+        let block = Block::default()
+            .padding(Padding::new(1, 1, 0, 0))
+            .title(self.title)
+            .title_bottom(Line::from(FORM_TITLE_BOTTOM).right_aligned())
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded);
+        let inner = block.inner(area);
+
+        Clear.render(area, buf);
+        block.render(area, buf);
+
+        let label_width = state
+            .fields
+            .iter()
+            .map(|(label, _)| label.len() as u16)
+            .max()
+            .unwrap_or(0);
+        let rows = Layout::vertical(
+            (0..state.fields.len() + self.hints.len()).map(|_| Constraint::Length(1)),
+        )
+        .split(inner);
+
+        for (idx, (label, input)) in state.fields.iter().enumerate() {
+            let [label_area, input_area] = Layout::horizontal([
+                Constraint::Length(label_width + LABEL_SPACING),
+                Constraint::Fill(1),
+            ])
+            .areas(rows[idx]);
+
+            Line::from(*label).bold().render(label_area, buf);
+            let text = if idx == state.focused {
+                utils::to_text_with_cursor(input, input_area.width)
+            } else {
+                Text::from(input.value())
+            };
+            text.render(input_area, buf);
+        }
+
+        for (line, area) in self.hints.into_iter().zip(&rows[state.fields.len()..]) {
+            Paragraph::new(line).render(*area, buf);
+        }
+    }
+}
+
+impl<'a> Form<'a> {
+    pub fn new(title: &'a str) -> Self {
+        Self {
+            title,
+            hints: vec![],
+        }
+    }
+
+    pub fn hints(mut self, hints: Vec<Line<'a>>) -> Self {
+        self.hints = hints;
+        self
+    }
+}
+
+impl FormState {
+    pub fn new(labels: &[&'static str]) -> Self {
+        Self {
+            fields: labels
+                .iter()
+                .map(|&label| (label, Input::default()))
+                .collect(),
+            focused: 0,
+        }
+    }
+
+    pub fn with_value(mut self, field: usize, value: String) -> Self {
+        self.set_value(field, value);
+        self
+    }
+
+    pub fn set_value(&mut self, field: usize, value: String) {
+        if let Some((_, input)) = self.fields.get_mut(field) {
+            *input = Input::new(value);
+        }
+    }
+
+    pub fn value(&self, field: usize) -> &str {
+        self.fields
+            .get(field)
+            .map_or("", |(_, input)| input.value())
+    }
+
+    pub fn focus(&mut self, field: usize) {
+        if field < self.fields.len() {
+            self.focused = field;
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        self.focused = (self.focused + 1) % self.fields.len().max(1);
+    }
+
+    pub fn previous_field(&mut self) {
+        self.focused = self
+            .focused
+            .checked_sub(1)
+            .unwrap_or(self.fields.len().saturating_sub(1));
+    }
+
+    pub fn handle_input(&mut self, event: &KeyEvent) {
+        if let Some((_, input)) = self.fields.get_mut(self.focused) {
+            input.handle_event(&Event::Key(*event));
+        }
+    }
+}
@@ -1,2 +1,7 @@
+pub mod accounts;
+pub mod categories;
+pub mod editable;
+pub mod form;
+pub mod theme;
 pub mod transactions;
 pub mod utils;
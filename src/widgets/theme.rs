@@ -0,0 +1,62 @@
+use std::str::FromStr;
+
+use color_eyre::eyre::{eyre, Report};
+use ratatui::style::{palette::tailwind, Color};
+use serde::Deserialize;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// Colors used by tables and messages
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub alt_row_bg: Color,
+    pub selected_bg: Color,
+    pub editing_fg: Color,
+    pub error_fg: Color,
+    pub gauge_fg: Color,
+}
+
+impl FromStr for ThemeName {
+    type Err = Report;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "dark" => Ok(ThemeName::Dark),
+            "light" => Ok(ThemeName::Light),
+            _ => Err(eyre!("unknown theme '{value}', expected dark or light")),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::from(ThemeName::default())
+    }
+}
+
+impl From<ThemeName> for Theme {
+    fn from(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Dark => Theme {
+                alt_row_bg: tailwind::GRAY.c800,
+                selected_bg: tailwind::GRAY.c600,
+                editing_fg: Color::Yellow,
+                error_fg: Color::Red,
+                gauge_fg: tailwind::GREEN.c500,
+            },
+            ThemeName::Light => Theme {
+                alt_row_bg: tailwind::GRAY.c200,
+                selected_bg: tailwind::GRAY.c400,
+                editing_fg: tailwind::BLUE.c800,
+                error_fg: tailwind::RED.c700,
+                gauge_fg: tailwind::GREEN.c700,
+            },
+        }
+    }
+}
@@ -1,25 +1,25 @@
-use crossterm::event::{Event, KeyEvent};
-use ratatui::{
-    style::palette::tailwind,
-    widgets::{Block, Borders, ScrollbarState, StatefulWidget, TableState},
-};
-use tui_input::backend::crossterm::EventHandler;
+use std::collections::{HashMap, HashSet};
+
+use color_eyre::eyre::{eyre, Result};
+use ratatui::{prelude::*, widgets::StatefulWidget};
 
-use super::utils;
-use crate::types::{NavEvent, ScreenMode, Transaction};
-use ratatui::{
-    prelude::*,
-    widgets::{HighlightSpacing, Row, Scrollbar, ScrollbarOrientation, Table},
+use super::{
+    editable::{EditableTable, EditableTableState},
+    theme::Theme,
+};
+use crate::{
+    currency::DEFAULT_EXPONENT,
+    parsing::{format_money, local_datetime, parse_date, parse_money},
+    types::{Transaction, TrnColumn, TrnSort, TrnStatus},
 };
 
 const TABLE_TITLE: &str = "Transactions";
-const TABLE_TITLE_BOTTOM: &str = " ← ↑ ↓ → to move selection ";
+const BALANCE_HEADER: &str = "Balance";
+const STATUS_HEADER: &str = "";
+const SPLIT_COLLAPSED: &str = "▸";
+const SPLIT_EXPANDED: &str = "▾";
 const TABLE_HEADER: [&str; 4] = ["Date", "Category", "Description", "Amount"];
 
-const ROW_HEIGHT: u16 = 1;
-const ROW_HIGHLIGHT_SYMBOL: &str = " > ";
-
-const COLUMN_SPACING: u16 = 1;
 const COLUMN_WIDTHS: [Constraint; 4] = [
     Constraint::Length(12),
     Constraint::Fill(1),
@@ -33,217 +33,298 @@ const COLUMN_ALIGNMENTS: [Alignment; 4] = [
     Alignment::Right,
 ];
 
-#[derive(Debug, Default)]
-pub struct TransactionsTableState {
-    pub mode: ScreenMode,
-    table_state: TableState,
-    scroll_state: ScrollbarState,
-    input: tui_input::Input,
-    size: usize,
-}
-
 #[derive(Debug)]
 pub struct TransactionsTable<'a> {
     items: &'a [Transaction],
+    date_format: &'a str,
+    first_row: usize,
+    account_id: Option<u8>,
+    balances: Option<&'a HashMap<isize, i64>>,
+    expanded: Option<&'a HashSet<isize>>,
+    exponents: Option<&'a HashMap<u8, u32>>,
+    query: &'a str,
+    sort: TrnSort,
+    theme: Theme,
 }
 
 impl StatefulWidget for TransactionsTable<'_> {
-    type State = TransactionsTableState;
-
-    fn render(self, area: Rect, buf: &mut Buffer, state: &mut TransactionsTableState) {
-        self.render_table(area, buf, state);
-        self.render_scrollbar(area, buf, state);
-    }
-}
+    type State = EditableTableState;
 
-impl<'a> TransactionsTable<'a> {
-    pub fn new(items: &'a [Transaction]) -> Self {
-        Self { items }
-    }
-
-    fn render_table(&self, area: Rect, buf: &mut Buffer, state: &mut TransactionsTableState) {
-        let header_style = Style::default().add_modifier(Modifier::REVERSED);
-        let selected_cell_style = match state.mode {
-            ScreenMode::Browsing => Style::default().bg(tailwind::GRAY.c600),
-            ScreenMode::Editing => Style::default().bg(tailwind::GRAY.c600).fg(Color::Yellow),
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut EditableTableState) {
+        let title = match self.query {
+            "" => String::from(TABLE_TITLE),
+            query => format!("{TABLE_TITLE} [filter: {query}]"),
         };
 
-        let widths: Vec<_> = Layout::horizontal(COLUMN_WIDTHS)
-            .spacing(COLUMN_SPACING)
-            .horizontal_margin(1 + ROW_HIGHLIGHT_SYMBOL.len() as u16)
-            .split(area)
+        let mut header: Vec<String> = TABLE_HEADER
             .iter()
-            .map(|part| part.width)
+            .zip(TrnColumn::ALL)
+            .map(|(&name, column)| match self.sort {
+                TrnSort {
+                    column: sorted,
+                    descending,
+                } if sorted == column => format!("{name} {}", if descending { "▼" } else { "▲" }),
+                _ => String::from(name),
+            })
             .collect();
 
-        let header = TABLE_HEADER
-            .into_iter()
-            .map(Text::from)
-            .zip(COLUMN_ALIGNMENTS)
-            .map(|(text, align)| text.alignment(align))
-            .collect::<Row>()
-            .style(header_style)
-            .height(1);
-
-        let rows = self.items.iter().enumerate().map(|(row, data)| {
-            let color = match row % 2 {
-                0 => Color::default(),
-                _ => tailwind::GRAY.c800,
-            };
-
-            to_text_iter(data)
-                .zip(&widths)
-                .enumerate()
-                .map(|(col, (text, &width))| {
-                    if state.mode == ScreenMode::Editing
-                        && Some(col) == state.table_state.selected_column()
-                        && Some(row) == state.table_state.selected()
-                    {
-                        utils::to_text_with_cursor(&state.input, width)
-                    } else {
-                        text
-                    }
-                })
-                .zip(COLUMN_ALIGNMENTS)
-                .map(|(text, align)| text.alignment(align))
-                .collect::<Row>()
-                .style(Style::default().bg(color))
-                .height(ROW_HEIGHT)
-        });
+        header.push(String::from(STATUS_HEADER));
+        let mut widths = COLUMN_WIDTHS.to_vec();
+        widths.push(Constraint::Length(1));
+        let mut alignments = COLUMN_ALIGNMENTS.to_vec();
+        alignments.push(Alignment::Center);
+        if self.balances.is_some() {
+            header.push(String::from(BALANCE_HEADER));
+            widths.push(Constraint::Min(13));
+            alignments.push(Alignment::Right);
+        }
 
-        let table = Table::new(rows, COLUMN_WIDTHS)
-            .header(header)
-            .cell_highlight_style(selected_cell_style)
-            .highlight_symbol(ROW_HIGHLIGHT_SYMBOL)
-            .highlight_spacing(HighlightSpacing::Always)
-            .column_spacing(COLUMN_SPACING)
-            .block(
-                Block::default()
-                    .title(TABLE_TITLE)
-                    .title_bottom(Line::from(TABLE_TITLE_BOTTOM).right_aligned())
-                    .borders(Borders::ALL)
-                    .border_type(ratatui::widgets::BorderType::Rounded),
-            );
-
-        StatefulWidget::render(table, area, buf, &mut state.table_state);
-    }
-
-    fn render_scrollbar(&self, area: Rect, buf: &mut Buffer, state: &mut TransactionsTableState) {
-        if area.height as usize <= state.size * ROW_HEIGHT as usize {
-            let scrollbar = Scrollbar::default()
-                .orientation(ScrollbarOrientation::VerticalRight)
-                .thumb_symbol("▐")
-                .track_symbol(None)
-                .begin_symbol(None)
-                .end_symbol(None);
-            let area = area.inner(Margin {
-                vertical: 1,
-                horizontal: 0,
+        let rows = self.items.iter().map(|data| {
+            let exponent = self.exponents.map_or(DEFAULT_EXPONENT, |exponents| {
+                amount_exponent(data, exponents)
             });
+            let mut cells = to_cells(data, self.date_format, self.account_id, exponent).to_vec();
+            cells.push(String::from(status_marker(data.status)));
+            if let Some(balances) = self.balances {
+                let balance = data
+                    .transaction_id
+                    .and_then(|id| balances.get(&id))
+                    .map(|&balance| format_money(balance, exponent));
+                cells.push(balance.unwrap_or_default());
+            }
+            if !data.splits.is_empty() {
+                let expanded = data
+                    .transaction_id
+                    .is_some_and(|id| self.expanded.is_some_and(|ids| ids.contains(&id)));
+                add_splits(&mut cells, data, self.account_id, exponent, expanded);
+            }
+            cells
+        });
 
-            StatefulWidget::render(scrollbar, area, buf, &mut state.scroll_state);
-        }
+        EditableTable::new(&title, header, widths, alignments)
+            .rows(rows)
+            .first_row(self.first_row)
+            .theme(self.theme)
+            .render(area, buf, state);
     }
 }
 
-impl TransactionsTableState {
-    pub fn new(size: usize) -> Self {
+impl<'a> TransactionsTable<'a> {
+    pub fn new(items: &'a [Transaction], date_format: &'a str) -> Self {
         Self {
-            mode: ScreenMode::Browsing,
-            table_state: TableState::default().with_selected(0),
-            scroll_state: ScrollbarState::new(size),
-            size,
-            input: tui_input::Input::default(),
+            items,
+            date_format,
+            first_row: 0,
+            account_id: None,
+            balances: None,
+            expanded: None,
+            exponents: None,
+            query: "",
+            sort: TrnSort::default(),
+            theme: Theme::default(),
         }
     }
 
-    pub fn start_editing(&mut self) {
-        if let (Some(_), Some(_)) = self.selected() {
-            self.mode = ScreenMode::Editing
-        }
+    /// Position of the first item among all transactions when `items` is a window of them
+    pub fn first_row(mut self, first_row: usize) -> Self {
+        self.first_row = first_row;
+        self
     }
 
-    pub fn select(&mut self, row: Option<usize>, column: Option<usize>) {
-        self.table_state.select(row);
-        self.table_state.select_column(column);
-        self.scroll_state = self.scroll_state.position(row.unwrap_or(0));
+    /// Account the amounts are shown for, positive when it is the debit side
+    pub fn account(mut self, account_id: Option<u8>) -> Self {
+        self.account_id = account_id;
+        self
     }
-    pub fn selected(&self) -> (Option<usize>, Option<usize>) {
-        (
-            self.table_state.selected(),
-            self.table_state.selected_column(),
-        )
+
+    /// Adds a column with the account balance after each transaction, by transaction id
+    pub fn balances(mut self, balances: Option<&'a HashMap<isize, i64>>) -> Self {
+        self.balances = balances;
+        self
     }
 
-    fn next_row(&mut self) {
-        let i = match self.table_state.selected() {
-            Some(i) if i < self.size => i + 1,
-            _ => 0,
-        };
-        self.table_state.select(Some(i));
-        self.scroll_state = self.scroll_state.position(i);
+    /// Transactions shown with their splits on separate lines, by transaction id.
+    /// Others only mark that they are split.
+    pub fn expanded(mut self, expanded: &'a HashSet<isize>) -> Self {
+        self.expanded = Some(expanded);
+        self
     }
 
-    fn previous_row(&mut self) {
-        let i = match self.table_state.selected() {
-            Some(i) if i > 0 => i - 1,
-            _ => 0,
-        };
-        self.table_state.select(Some(i));
-        self.scroll_state = self.scroll_state.position(i);
+    /// Minor unit digits of the currency of every account, by account id.
+    /// Amounts have two decimals when not given.
+    pub fn exponents(mut self, exponents: &'a HashMap<u8, u32>) -> Self {
+        self.exponents = Some(exponents);
+        self
+    }
+
+    /// Search query the rows were selected by
+    pub fn query(mut self, query: &'a str) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// Order of the rows, shown by an arrow in the header
+    pub fn sort(mut self, sort: TrnSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
     }
+}
+
+fn to_cells(
+    data: &Transaction,
+    date_format: &str,
+    account_id: Option<u8>,
+    exponent: u32,
+) -> [String; 4] {
+    [
+        format!("{}", data.timestamp.format(date_format)),
+        data.category.clone().unwrap_or_default(),
+        data.description.clone().unwrap_or_default(),
+        format_money(sign(data, account_id) * data.amount, exponent),
+    ]
+}
+
+/// Column mark of cleared and reconciled transactions
+fn status_marker(status: TrnStatus) -> &'static str {
+    match status {
+        TrnStatus::Uncleared => "",
+        TrnStatus::Cleared => "c",
+        TrnStatus::Reconciled => "R",
+    }
+}
+
+/// Minor unit digits of the transaction amount, both accounts have the same currency
+pub fn amount_exponent(data: &Transaction, exponents: &HashMap<u8, u32>) -> u32 {
+    data.debit_acc_id
+        .or(data.credit_acc_id)
+        .and_then(|id| exponents.get(&id).copied())
+        .unwrap_or(DEFAULT_EXPONENT)
+}
 
-    pub fn handle_input(&mut self, event: &KeyEvent) {
-        if self.mode == ScreenMode::Editing {
-            self.input.handle_event(&Event::Key(*event));
+/// Marks the category cell of a split transaction, and when `expanded`
+/// appends a line for every split to the category, description and amount cells
+fn add_splits(
+    cells: &mut [String],
+    data: &Transaction,
+    account_id: Option<u8>,
+    exponent: u32,
+    expanded: bool,
+) {
+    let marker = if expanded {
+        SPLIT_EXPANDED
+    } else {
+        SPLIT_COLLAPSED
+    };
+    cells[1] = format!("{marker} {}", cells[1]);
+    if !expanded {
+        return;
+    }
+    for split in &data.splits {
+        let category = split.category.as_deref().unwrap_or_default();
+        let description = split.description.as_deref().unwrap_or_default();
+        let amount = format_money(sign(data, account_id) * split.amount, exponent);
+        for (col, line) in [
+            (1, format!("  {category}")),
+            (2, description.to_string()),
+            (3, amount),
+        ] {
+            cells[col].push('\n');
+            cells[col].push_str(&line);
         }
     }
+}
 
-    pub fn navigate(&mut self, event: NavEvent) -> Option<String> {
-        match self.mode {
-            ScreenMode::Browsing => match event {
-                NavEvent::Left => self.table_state.select_previous_column(),
-                NavEvent::Rigth => self.table_state.select_next_column(),
-                NavEvent::Up => self.previous_row(),
-                NavEvent::Down => self.next_row(),
-                NavEvent::Cancel => self.table_state.select_column(None),
-                NavEvent::Interact => {
-                    // start editing
-                    if let (Some(_), Some(_)) = self.selected() {
-                        self.mode = ScreenMode::Editing
-                    }
-                }
-            },
-            ScreenMode::Editing => match event {
-                NavEvent::Cancel => {
-                    self.input.reset();
-                    self.mode = ScreenMode::Browsing;
-                }
-                NavEvent::Interact => {
-                    // accept edit
-                    self.mode = ScreenMode::Browsing;
-                    if let (Some(_row), Some(_col)) = self.selected() {
-                        return Some(self.input.value_and_reset());
-                    }
-                }
-                _ => { /* suppress navigation in Edit Mode */ }
-            },
+/// -1 when the account is only on the credit side of the transaction, it loses the amount
+fn sign(data: &Transaction, account_id: Option<u8>) -> i64 {
+    match account_id {
+        Some(id) if data.credit_acc_id == Some(id) && data.debit_acc_id != Some(id) => -1,
+        _ => 1,
+    }
+}
+
+/// Returns the text of the cell in column `col` as it should appear in the editor,
+/// with the amount signed for account `account_id` as in the table
+pub fn cell_value(
+    data: &Transaction,
+    col: usize,
+    date_format: &str,
+    account_id: Option<u8>,
+    exponent: u32,
+) -> String {
+    to_cells(data, date_format, account_id, exponent)
+        .into_iter()
+        .nth(col)
+        .unwrap_or_default()
+}
+
+/// Parses `value` edited in column `col` into an updated copy of `data`,
+/// the reverse of `cell_value`
+pub fn apply_edit(
+    data: &Transaction,
+    col: usize,
+    value: &str,
+    date_format: &str,
+    account_id: Option<u8>,
+    exponent: u32,
+) -> Result<Transaction> {
+    let mut updated = data.clone();
+    let text = match value.trim() {
+        "" => None,
+        text => Some(text.to_string()),
+    };
+
+    match col {
+        0 => {
+            updated.timestamp =
+                local_datetime(parse_date(value, date_format)?, data.timestamp.time())?;
         }
-        None
+        1 => updated.category = text,
+        2 => updated.description = text,
+        3 => updated.amount = sign(data, account_id) * parse_money(value, exponent)?,
+        _ => return Err(eyre!("column {col} is not editable")),
     }
+
+    Ok(updated)
 }
 
-fn to_text_iter(data: &Transaction) -> impl Iterator<Item = Text> {
-    let category: &str = data.category.as_ref().map_or("", |x| x);
-    let description: &str = data.description.as_ref().map_or("", |x| x);
-    let amount_whole = data.amount / 100;
-    let amount_frac = data.amount % 100;
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::{Local, TimeZone};
 
-    [
-        Text::from(format!("{}", data.timestamp.format("%Y-%m-%d"))),
-        Text::from(category),
-        Text::from(description),
-        Text::from(format!("{amount_whole}.{amount_frac:02}")),
-    ]
-    .into_iter()
+    const DATE_FORMAT: &str = "%Y-%m-%d";
+
+    fn trn() -> Transaction {
+        Transaction {
+            transaction_id: Some(1),
+            credit_acc_id: Some(1),
+            debit_acc_id: Some(2),
+            timestamp: Local.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap(),
+            category: None,
+            amount: 1250,
+            description: None,
+            splits: vec![],
+            status: TrnStatus::Uncleared,
+            external_id: None,
+        }
+    }
+
+    #[test]
+    fn amount_signed_by_side() -> Result<()> {
+        let amount = |account_id| cell_value(&trn(), 3, DATE_FORMAT, account_id, 2);
+        assert_eq!(amount(None), "12.50");
+        assert_eq!(amount(Some(1)), "-12.50");
+        assert_eq!(amount(Some(2)), "12.50");
+
+        let edited = apply_edit(&trn(), 3, "-20", DATE_FORMAT, Some(1), 2)?;
+        assert_eq!(edited.amount, 2000);
+        let edited = apply_edit(&trn(), 3, "-20", DATE_FORMAT, Some(2), 0)?;
+        assert_eq!(edited.amount, -20);
+        Ok(())
+    }
 }
@@ -1,9 +1,10 @@
 use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
     style::{Modifier, Style},
     text::{Line, Span, Text},
 };
 
-pub fn to_text_with_cursor(input: &tui_input::Input, width: u16) -> ratatui::text::Text {
+pub fn to_text_with_cursor(input: &tui_input::Input, width: u16) -> ratatui::text::Text<'_> {
     let scroll = input.visual_scroll(width as usize - 1); // 1 is for the cursor
     let value = input.value();
     let cursor_pos = input.visual_cursor();
@@ -33,3 +34,14 @@ pub fn to_text_with_cursor(input: &tui_input::Input, width: u16) -> ratatui::tex
         Span::from(part3),
     ]))
 }
+
+/// Rectangle of given size centered in `area`, shrunk to fit if needed
+pub fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}